@@ -24,6 +24,7 @@ fn main() {
 
     process_ast("ast_deref", &out_dir.join("ast_deref_gen.inc.rs"));
     process_ast("ast_equiv", &out_dir.join("ast_equiv_gen.inc.rs"));
+    process_ast("ast_hash", &out_dir.join("ast_hash_gen.inc.rs"));
     process_ast("matcher", &out_dir.join("matcher_impls_gen.inc.rs"));
     process_ast("get_span", &out_dir.join("get_span_gen.inc.rs"));
     process_ast("get_node_id", &out_dir.join("get_node_id_gen.inc.rs"));
@@ -0,0 +1,96 @@
+'''This module generates `AstHash` impls for each AST node type.  The hashes
+are consistent with the `AstEquiv` impls generated by `ast_equiv.py`: any two
+values that are `ast_equiv` hash the same.
+
+- A struct value hashes its fields in order.
+- An enum value hashes the index of its variant, then the variant's fields.
+- A flag value hashes itself using `Hash`.
+
+Each impl also has an `unnamed_hash` method built the same way, which is
+consistent with `unnamed_equiv` instead.
+
+Attributes:
+
+- `#[equiv_mode=eq]`: On a type declaration, generate a trivial `impl` that
+  hashes the value using `Hash`.
+
+- `#[equiv_mode=ignore]`: On a type declaration, generate a trivial `impl` that
+  hashes nothing, since comparisons ignore fields of this type.
+
+- `#[equiv_mode=custom]`: On a type declaration, do not generate an `impl`.
+'''
+
+from datetime import datetime
+from textwrap import indent, dedent
+
+from ast import *
+from util import *
+
+
+@linewise
+def hash_fields(se, method):
+    yield 'match self {'
+    for i, (v, path) in enumerate(variants_paths(se)):
+        yield '  &%s => {' % struct_pattern(v, path)
+        if isinstance(se, Enum):
+            yield '    state.write_usize(%d);' % i
+        for f in v.fields:
+            yield '    AstHash::%s(%s, state);' % (method, f.name)
+        yield '  }'
+    yield '}'
+
+
+@linewise
+def hash_impl(se):
+    yield '#[allow(unused, non_shorthand_field_patterns)]'
+    yield 'impl AstHash for %s {' % se.name
+    yield '  fn ast_hash<H: Hasher>(&self, state: &mut H) {'
+    yield indent(hash_fields(se, 'ast_hash'), '    ')
+    yield '  }'
+    yield '  fn unnamed_hash<H: Hasher>(&self, state: &mut H) {'
+    yield indent(hash_fields(se, 'unnamed_hash'), '    ')
+    yield '  }'
+    yield '}'
+
+@linewise
+def eq_impl(d):
+    yield '#[allow(unused)]'
+    yield 'impl AstHash for %s {' % d.name
+    yield '  fn ast_hash<H: Hasher>(&self, state: &mut H) {'
+    yield '    Hash::hash(self, state)'
+    yield '  }'
+    yield '  fn unnamed_hash<H: Hasher>(&self, state: &mut H) {'
+    yield '    Hash::hash(self, state)'
+    yield '  }'
+    yield '}'
+
+@linewise
+def ignore_impl(d):
+    yield '#[allow(unused)]'
+    yield 'impl AstHash for %s {' % d.name
+    yield '  fn ast_hash<H: Hasher>(&self, state: &mut H) {}'
+    yield '  fn unnamed_hash<H: Hasher>(&self, state: &mut H) {}'
+    yield '}'
+
+@linewise
+def generate(decls):
+    yield '// AUTOMATICALLY GENERATED - DO NOT EDIT'
+    yield '// Produced %s by process_ast.py' % (datetime.now(),)
+    yield ''
+
+    for d in decls:
+        mode = d.attrs.get('equiv_mode')
+        if mode is None:
+            if isinstance(d, (Struct, Enum)):
+                mode = 'compare'
+            else:
+                mode = 'eq'
+
+        if mode == 'compare':
+            yield hash_impl(d)
+        elif mode == 'eq':
+            yield eq_impl(d)
+        elif mode == 'ignore':
+            yield ignore_impl(d)
+        elif mode == 'custom':
+            pass
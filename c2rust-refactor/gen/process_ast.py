@@ -213,6 +213,9 @@ if __name__ == '__main__':
     elif mode == 'ast_equiv':
         import ast_equiv
         text = ast_equiv.generate(decls)
+    elif mode == 'ast_hash':
+        import ast_hash
+        text = ast_hash.generate(decls)
     elif mode == 'matcher':
         import matcher
         text = matcher.generate(decls)
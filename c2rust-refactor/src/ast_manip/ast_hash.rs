@@ -0,0 +1,177 @@
+//! `AstHash` trait for hashing ASTs consistently with `AstEquiv`.
+use rustc_target::spec::abi::Abi;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use syntax::ast::*;
+use syntax::token::{BinOpToken, DelimToken, Nonterminal, Token, TokenKind};
+use syntax::token::{Lit as TokenLit, LitKind as TokenLitKind};
+use syntax::ptr::P;
+use syntax::source_map::{Span, Spanned};
+use syntax::tokenstream::{DelimSpan, TokenStream, TokenTree};
+use syntax::ThinVec;
+use syntax_pos::hygiene::SyntaxContext;
+
+#[cfg(test)]
+mod tests;
+
+/// Trait for hashing AST nodes structurally.  Hashes are consistent with `AstEquiv::ast_equiv`:
+/// nodes that are `ast_equiv` hash the same, since the fields it ignores (`Span`s, `NodeId`s and
+/// so on) are not hashed either.  This lets callers bucket nodes by hash and only compare the
+/// nodes within a bucket.
+pub trait AstHash {
+    fn ast_hash<H: Hasher>(&self, state: &mut H);
+
+    /// Hash consistently with `AstEquiv::unnamed_equiv`, i.e. without
+    /// telling apart the names of C2RustUnnamed* types
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H);
+}
+
+/// Compute the structural hash of `node`.  See `AstHash`.
+pub fn ast_hash<T: AstHash + ?Sized>(node: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    node.ast_hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Compute the structural hash of `node`, ignoring the names of
+/// C2RustUnnamed* types.  See `AstHash::unnamed_hash`.
+pub fn unnamed_hash<T: AstHash + ?Sized>(node: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    node.unnamed_hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<'a, T: AstHash + ?Sized> AstHash for &'a T {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        <T as AstHash>::ast_hash(*self, state)
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        <T as AstHash>::unnamed_hash(*self, state)
+    }
+}
+
+impl<T: AstHash> AstHash for P<T> {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        <T as AstHash>::ast_hash(self, state)
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        <T as AstHash>::unnamed_hash(self, state)
+    }
+}
+
+impl<T: AstHash> AstHash for Rc<T> {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        <T as AstHash>::ast_hash(self, state)
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        <T as AstHash>::unnamed_hash(self, state)
+    }
+}
+
+impl<T: AstHash> AstHash for Spanned<T> {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        self.node.ast_hash(state)
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        self.node.unnamed_hash(state)
+    }
+}
+
+impl<T: AstHash> AstHash for [T] {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for x in self {
+            x.ast_hash(state);
+        }
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for x in self {
+            x.unnamed_hash(state);
+        }
+    }
+}
+
+impl<T: AstHash> AstHash for Vec<T> {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        <[T] as AstHash>::ast_hash(self, state)
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        <[T] as AstHash>::unnamed_hash(self, state)
+    }
+}
+
+impl<T: AstHash> AstHash for ThinVec<T> {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        <[T] as AstHash>::ast_hash(self, state)
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        <[T] as AstHash>::unnamed_hash(self, state)
+    }
+}
+
+impl<T: AstHash> AstHash for Option<T> {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Some(x) => {
+                state.write_u8(1);
+                x.ast_hash(state);
+            }
+            None => state.write_u8(0),
+        }
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Some(x) => {
+                state.write_u8(1);
+                x.unnamed_hash(state);
+            }
+            None => state.write_u8(0),
+        }
+    }
+}
+
+impl<A: AstHash, B: AstHash> AstHash for (A, B) {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        self.0.ast_hash(state);
+        self.1.ast_hash(state);
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        self.0.unnamed_hash(state);
+        self.1.unnamed_hash(state);
+    }
+}
+
+impl<A: AstHash, B: AstHash, C: AstHash> AstHash for (A, B, C) {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        self.0.ast_hash(state);
+        self.1.ast_hash(state);
+        self.2.ast_hash(state);
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        self.0.unnamed_hash(state);
+        self.1.unnamed_hash(state);
+        self.2.unnamed_hash(state);
+    }
+}
+
+// Implementations for specific AST types are auto-generated.
+include!(concat!(env!("OUT_DIR"), "/ast_hash_gen.inc.rs"));
+
+impl AstHash for Ident {
+    fn ast_hash<H: Hasher>(&self, state: &mut H) {
+        self.name.ast_hash(state);
+        self.span.ast_hash(state);
+    }
+    fn unnamed_hash<H: Hasher>(&self, state: &mut H) {
+        // All C2RustUnnamed* names are `unnamed_equiv` to each other
+        if self.as_str().contains("C2RustUnnamed") {
+            state.write_u8(0);
+        } else {
+            state.write_u8(1);
+            self.name.unnamed_hash(state);
+        }
+        self.span.unnamed_hash(state);
+    }
+}
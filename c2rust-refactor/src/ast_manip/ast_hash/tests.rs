@@ -0,0 +1,90 @@
+use syntax::ast::{Item, NodeId};
+use syntax::ptr::P;
+use syntax::sess::ParseSess;
+use syntax::source_map::FilePathMapping;
+use syntax_pos::edition::Edition;
+use syntax_pos::FileName;
+
+use super::{ast_hash, unnamed_hash};
+use crate::ast_manip::AstEquiv;
+use crate::driver;
+
+fn parse_item(sess: &ParseSess, src: &str) -> P<Item> {
+    let mut p = rustc_parse::new_parser_from_source_str(
+        sess,
+        FileName::anon_source_code(src),
+        src.to_owned(),
+    );
+    match p.parse_item() {
+        Ok(item) => item.expect("expected an item"),
+        Err(db) => driver::emit_and_panic(db, "item"),
+    }
+}
+
+/// Parse `src1` and `src2`, which must be the same item up to the fields
+/// `AstEquiv` ignores, and check that they hash the same
+fn check_same_hash(src1: &str, src2: &str) {
+    syntax::with_globals(Edition::Edition2018, || {
+        let sess = ParseSess::new(FilePathMapping::empty());
+        let mut item1 = parse_item(&sess, src1);
+        let mut item2 = parse_item(&sess, src2);
+        item1.id = NodeId::from_u32(1);
+        item2.id = NodeId::from_u32(2);
+        assert!(item1.ast_equiv(&item2), "{} and {} should be equivalent", src1, src2);
+        assert_eq!(ast_hash(&item1), ast_hash(&item2), "{} and {}", src1, src2);
+        assert_eq!(unnamed_hash(&item1), unnamed_hash(&item2), "{} and {}", src1, src2);
+    });
+}
+
+#[test]
+fn spans_and_node_ids_are_not_hashed() {
+    // Different files and offsets give the two items different spans
+    check_same_hash(
+        "pub struct Foo { pub x: i32, pub y: *mut u8 }",
+        "\n\n    pub struct Foo {\n        pub x: i32,\n        pub y: *mut u8,\n    }",
+    );
+}
+
+#[test]
+fn attr_ids_are_not_hashed() {
+    // Every parsed attribute gets a fresh `AttrId`
+    check_same_hash(
+        "#[repr(C)] #[derive(Copy, Clone)] pub struct Foo { pub x: i32 }",
+        "#[repr(C)]\n#[derive(Copy, Clone)]\npub struct Foo { pub x: i32 }",
+    );
+}
+
+#[test]
+fn macro_tokens_are_not_hashed() {
+    // `AstEquiv` ignores token streams, so these are equivalent
+    check_same_hash("foo! { a b c }", "foo! { x }");
+}
+
+#[test]
+fn different_items_hash_differently() {
+    syntax::with_globals(Edition::Edition2018, || {
+        let sess = ParseSess::new(FilePathMapping::empty());
+        let item1 = parse_item(&sess, "pub struct Foo { pub x: i32 }");
+        let item2 = parse_item(&sess, "pub struct Foo { pub x: u32 }");
+        assert!(!item1.ast_equiv(&item2));
+        assert_ne!(ast_hash(&item1), ast_hash(&item2));
+    });
+}
+
+#[test]
+fn unnamed_hash_ignores_unnamed_names() {
+    syntax::with_globals(Edition::Edition2018, || {
+        let sess = ParseSess::new(FilePathMapping::empty());
+        let item1 = parse_item(&sess, "pub struct C2RustUnnamed { pub u: C2RustUnnamed_0, pub x: i32 }");
+        let item2 = parse_item(&sess, "pub struct C2RustUnnamed_3 { pub u: C2RustUnnamed_7, pub x: i32 }");
+        assert!(item1.kind.unnamed_equiv(&item2.kind));
+        assert!(!item1.kind.ast_equiv(&item2.kind));
+        assert_eq!(unnamed_hash(&item1.kind), unnamed_hash(&item2.kind));
+        assert_ne!(ast_hash(&item1.kind), ast_hash(&item2.kind));
+
+        // Other names still count
+        let item3 = parse_item(&sess, "pub struct C2RustUnnamed { pub u: Named, pub x: i32 }");
+        assert!(!item1.kind.unnamed_equiv(&item3.kind));
+        assert_ne!(unnamed_hash(&item1.kind), unnamed_hash(&item3.kind));
+    });
+}
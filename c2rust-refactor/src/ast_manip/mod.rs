@@ -4,6 +4,7 @@
 // Modules with simple APIs are private, with their public definitions reexported.
 mod ast_deref;
 mod ast_equiv;
+mod ast_hash;
 mod ast_map;
 mod ast_names;
 mod ast_node;
@@ -19,6 +20,7 @@ mod visit_node;
 
 pub use self::ast_deref::AstDeref;
 pub use self::ast_equiv::AstEquiv;
+pub use self::ast_hash::{ast_hash, unnamed_hash, AstHash};
 pub use self::ast_map::{map_ast, map_ast_into, map_ast_unified, map_ast_into_unified, AstMap, NodeTable, UnifiedAstMap};
 pub use self::ast_names::AstName;
 pub use self::ast_node::{AstNode, AstNodeRef};
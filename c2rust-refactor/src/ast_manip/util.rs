@@ -221,6 +221,25 @@ pub fn split_uses(item: P<Item>) -> SmallVec<[P<Item>; 1]> {
     out
 }
 
+/// Remove the leaves of a use tree for which `keep` returns false, given the
+/// `NodeId` each leaf gets from `split_uses`. Nested groups left empty are
+/// removed as well. Returns false if nothing is left of the tree.
+pub fn retain_use_leaves<F: Fn(NodeId) -> bool>(tree: &mut UseTree, id: NodeId, keep: &F) -> bool {
+    match &mut tree.kind {
+        UseTreeKind::Simple(..) | UseTreeKind::Glob => keep(id),
+        UseTreeKind::Nested(children) => {
+            let mut kept = Vec::with_capacity(children.len());
+            for (mut u, id) in children.drain(..) {
+                if retain_use_leaves(&mut u, id, keep) {
+                    kept.push((u, id));
+                }
+            }
+            *children = kept;
+            !children.is_empty()
+        }
+    }
+}
+
 /// Is a path relative to the current module?
 pub fn is_relative_path(path: &Path) -> bool {
     !path.segments.is_empty()
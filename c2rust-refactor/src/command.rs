@@ -537,9 +537,9 @@ impl RefactorState {
 
         let mut cmd = self.cmd_reg.get_command(cmd_name, &args)?;
         profile_start!(format!("Command {}", cmd_name));
-        cmd.run(self);
+        let result = cmd.run(self);
         profile_end!(format!("Command {}", cmd_name));
-        Ok(())
+        result
     }
 
     pub fn marks(&self) -> &HashSet<(NodeId, Symbol)> {
@@ -721,7 +721,9 @@ impl CommandState {
 
 /// Implementation of a refactoring command.
 pub trait Command {
-    fn run(&mut self, state: &mut RefactorState);
+    /// Run the command.  Returns a description of what went wrong if the
+    /// command could not be applied.
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String>;
 }
 
 /// A command builder is a function that takes some string arguments and produces a `Command`.
@@ -762,8 +764,9 @@ impl<F> Command for FuncCommand<F>
 where
     F: FnMut(&mut RefactorState),
 {
-    fn run(&mut self, state: &mut RefactorState) {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
         (self.0)(state);
+        Ok(())
     }
 }
 
@@ -790,10 +793,11 @@ impl<F> Command for DriverCommand<F>
 where
     F: FnMut(&CommandState, &RefactorCtxt),
 {
-    fn run(&mut self, state: &mut RefactorState) {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
         state
             .transform_crate(self.phase, |st, cx| (self.func)(st, cx))
             .expect("Failed to run compiler");
+        Ok(())
     }
 }
 
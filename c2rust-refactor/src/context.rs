@@ -11,9 +11,10 @@ use rustc::ty::subst::InternalSubsts;
 use rustc::ty::{FnSig, ParamEnv, PolyFnSig, Ty, TyCtxt, TyKind};
 use rustc_metadata::creader::CStore;
 use syntax::ast::{
-    self, Expr, ExprKind, ForeignItem, ForeignItemKind, FnDecl, FunctionRetTy, Item, ItemKind, NodeId, Path, QSelf, UseTreeKind, DUMMY_NODE_ID,
+    self, Expr, ExprKind, ForeignItem, ForeignItemKind, FnDecl, FnHeader, FunctionRetTy, GenericBounds, Item, ItemKind, NodeId, Path, QSelf, UseTreeKind, DUMMY_NODE_ID,
 };
 use syntax::ptr::P;
+use syntax::symbol::kw;
 
 use crate::ast_manip::AstEquiv;
 use crate::command::{GenerationalTyCtxt, TyCtxtGeneration};
@@ -169,13 +170,32 @@ impl<'a, 'tcx> RefactorCtxt<'a, 'tcx> {
     /// Build a `Path` referring to a particular def.  This method returns an
     /// absolute path when possible.
     pub fn def_path(&self, id: DefId) -> Path {
-        reflect::reflect_def_path(self.ty_ctxt(), id).1
+        self.def_qpath(id).1
     }
 
     pub fn def_qpath(&self, id: DefId) -> (Option<QSelf>, Path) {
+        if !self.has_ty_ctxt() && id.is_local() {
+            return (None, self.local_def_path(id));
+        }
         reflect::reflect_def_path(self.ty_ctxt(), id)
     }
 
+    /// Build the absolute path of a local def from the HIR map alone, for
+    /// phases without a type context.  Unlike `def_path`, this never adds
+    /// generic arguments or a qualified self type.
+    fn local_def_path(&self, id: DefId) -> Path {
+        let def_path = self.hir_map().def_path(id);
+        let mut segments = vec![mk().path_segment(kw::Crate)];
+        segments.extend(
+            def_path
+                .data
+                .iter()
+                .filter_map(|data| data.data.get_opt_name())
+                .map(|name| mk().path_segment(name)),
+        );
+        mk().path(segments)
+    }
+
     /// Obtain the `DefId` of a definition node, such as a `fn` item.
     pub fn node_def_id(&self, id: NodeId) -> DefId {
         match self.hir_map().find(id) {
@@ -494,6 +514,27 @@ impl<'a, 'tcx> RefactorCtxt<'a, 'tcx> {
         TypeCompare::new(self).compatible_types(item1, item2)
     }
 
+    /// Compare the bounds of two trait object (or `impl Trait`) types,
+    /// ignoring their order. Types that are not trait objects are considered
+    /// compatible here; they are checked by the regular type comparison.
+    fn compatible_trait_object_bounds(&self, ty1: &ast::Ty, ty2: &ast::Ty) -> bool {
+        fn bounds(ty: &ast::Ty) -> Option<&GenericBounds> {
+            match &ty.kind {
+                ast::TyKind::TraitObject(bounds, _) | ast::TyKind::ImplTrait(_, bounds) => {
+                    Some(bounds)
+                }
+                ast::TyKind::Paren(ty) => bounds(ty),
+                _ => None,
+            }
+        }
+
+        match (bounds(ty1), bounds(ty2)) {
+            (Some(bounds1), Some(bounds2)) => equivalent_bound_sets(bounds1, bounds2),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
     /// Compare two function declarations for equivalent argument and return types,
     /// ignoring argument names.
     pub fn compatible_fn_prototypes(&self, decl1: &FnDecl, decl2: &FnDecl) -> bool {
@@ -540,6 +581,8 @@ impl<'a, 'tcx> RefactorCtxt<'a, 'tcx> {
 
             ItemKind::Static(..) | ItemKind::Const(..) | ItemKind::Fn(..) => Some(Namespace::ValueNS),
 
+            ItemKind::MacroDef(..) => Some(Namespace::MacroNS),
+
             _ => Some(Namespace::TypeNS),
         }
     }
@@ -664,6 +707,12 @@ impl<'a, 'tcx, 'b> TypeCompare<'a, 'tcx, 'b> {
         match (&item1.kind, &item2.kind) {
             // * Assure that these two items are in fact of the same type, just to be safe.
             (TyAlias(ty1, g1), TyAlias(ty2, g2)) => {
+                // Trait object aliases must agree on their whole bound list,
+                // including auto traits and lifetime bounds, not just on the
+                // principal trait.
+                if !self.compatible_trait_object_bounds(ty1, ty2) {
+                    return false;
+                }
                 match (self.cx.opt_node_type(item1.id), self.cx.opt_node_type(item2.id)) {
                     (Some(ty1), Some(ty2)) => self.structural_eq_tys(ty1, ty2),
                     _ => {
@@ -688,11 +737,33 @@ impl<'a, 'tcx, 'b> TypeCompare<'a, 'tcx, 'b> {
 
             (Use(_), Use(_)) => panic!("We should have already handled the use statement case"),
 
+            (TraitAlias(g1, bounds1), TraitAlias(g2, bounds2)) => {
+                // Trait aliases have no type of their own, so compare them
+                // syntactically: same name, generics and set of bounds.
+                item1.ident.unnamed_equiv(&item2.ident)
+                    && g1.unnamed_equiv(g2)
+                    && equivalent_bound_sets(bounds1, bounds2)
+            }
+
+            (Fn(sig1, ..), Fn(sig2, ..)) => {
+                // Constness, asyncness and unsafety are part of a function's
+                // contract, so functions that differ in them are never
+                // interchangeable, even if their bodies are equivalent.
+                compatible_fn_headers(&sig1.header, &sig2.header) && item1.unnamed_equiv(item2)
+            }
+
             (Struct(variant1, _), Struct(variant2, _))
             | (Union(variant1, _), Union(variant2, _)) => {
                 if !item1.ident.unnamed_equiv(&item2.ident) {
                     return false;
                 }
+                // Field lists are compared pairwise below, so make sure an
+                // empty marker struct (`struct _IO_FILE;` or `struct _IO_FILE
+                // {}`, which are interchangeable) never matches a definition
+                // that actually has fields.
+                if variant1.fields().len() != variant2.fields().len() {
+                    return false;
+                }
                 if let Struct(..) = &item1.kind {
                     // Ensure all field names are equivalent
                     for (field1, field2) in variant1.fields().iter().zip(variant2.fields().iter()) {
@@ -805,7 +876,7 @@ impl<'a, 'tcx, 'b> TypeCompare<'a, 'tcx, 'b> {
             (Some(ty1), Some(ty2)) => return self.structural_eq_tys(ty1, ty2),
             _ => {}
         }
-        match (self.cx.try_resolve_ty(ty1), self.cx.try_resolve_ty(ty1)) {
+        match (self.cx.try_resolve_ty(ty1), self.cx.try_resolve_ty(ty2)) {
             (Some(did1), Some(did2)) => self.structural_eq_defs(did1, did2),
             _ => ty1.unnamed_equiv(ty2),
         }
@@ -1145,3 +1216,20 @@ impl<'a, 'tcx, 'b> TypeCompare<'a, 'tcx, 'b> {
         self.eq_tys(out_ty1, out_ty2)
     }
 }
+
+/// Check that two function headers agree on constness, asyncness and unsafety.
+fn compatible_fn_headers(header1: &FnHeader, header2: &FnHeader) -> bool {
+    header1.constness.node == header2.constness.node
+        && header1.asyncness.node.is_async() == header2.asyncness.node.is_async()
+        && header1.unsafety == header2.unsafety
+}
+
+/// Check that two bound lists contain equivalent bounds, ignoring their order.
+fn equivalent_bound_sets(bounds1: &GenericBounds, bounds2: &GenericBounds) -> bool {
+    let contains_all = |a: &GenericBounds, b: &GenericBounds| {
+        a.iter().all(|bound1| b.iter().any(|bound2| bound1.unnamed_equiv(bound2)))
+    };
+    bounds1.len() == bounds2.len()
+        && contains_all(bounds1, bounds2)
+        && contains_all(bounds2, bounds1)
+}
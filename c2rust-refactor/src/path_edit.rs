@@ -1,4 +1,6 @@
 //! `fold_resolved_paths` function, for rewriting paths based on their resolved `DefId`.
+use std::collections::HashSet;
+
 use rustc::hir;
 use rustc::hir::def::Res;
 use smallvec::SmallVec;
@@ -8,8 +10,8 @@ use syntax::ptr::P;
 use syntax::util::map_in_place::MapInPlace;
 use smallvec::smallvec;
 
-use crate::ast_manip::util::split_uses;
-use crate::ast_manip::MutVisit;
+use crate::ast_manip::util::{retain_use_leaves, split_uses};
+use crate::ast_manip::{AstEquiv, MutVisit};
 use crate::RefactorCtxt;
 
 struct ResolvedPathFolder<'a, 'tcx: 'a, F>
@@ -209,7 +211,11 @@ where
     //  - Visibility::Restricted.path
     //  - UseTree.prefix
     //
-    // We currently support the PatKind, ExprKind, and TyKind cases.  The rest are NYI.
+    // We currently support the PatKind, ExprKind, TyKind and TraitRef cases.  The rest are NYI.
+    // Note that `ExprKind` paths also cover those nested in `AnonConst`s, such as array lengths
+    // in `[u8; foo_h::SIZE]`, since `noop_visit_anon_const` walks into their `value` expression.
+    // `TraitRef`s cover trait bounds wherever they appear, including generic parameters and
+    // where-clauses, as well as the trait of an `impl`.
 
     fn visit_pat(&mut self, p: &mut P<Pat>) {
         if let Some(node) = self.cx.hir_map().find(p.id) {
@@ -245,14 +251,35 @@ where
         mut_visit::noop_visit_ty(t, self)
     }
 
+    fn visit_trait_ref(&mut self, tr: &mut TraitRef) {
+        if let Some(node) = self.cx.hir_map().find(tr.ref_id) {
+            let hir = expect!([node]
+                              hir::Node::TraitRef(tr) => tr);
+
+            let (new_qself, new_path) =
+                (self.callback)(tr.ref_id, None, tr.path.clone(), &[hir.path.res]);
+            assert!(
+                new_qself.is_none(),
+                "can't insert QSelf at this location (TraitRef)"
+            );
+            tr.path = new_path;
+        }
+
+        mut_visit::noop_visit_trait_ref(tr, self)
+    }
+
     fn flat_map_item(&mut self, item: P<Item>) -> SmallVec<[P<Item>; 1]> {
         let mut v = match item.kind {
             ItemKind::Use(..) => {
                 // We split nested uses into simple uses to make path rewriting
-                // of use statements simpler.
-                let mut uses = split_uses(item);
+                // of use statements simpler. The imports whose paths stay the
+                // same are put back into the original tree afterwards, so only
+                // the rewritten ones lose their nesting.
+                let mut uses = split_uses(item.clone());
+                let mut unchanged = HashSet::new();
                 for item in uses.iter_mut() {
                     let use_tree = expect!([&item.kind] ItemKind::Use(u) => u);
+                    let old_prefix = use_tree.prefix.clone();
                     let mut hir_nodes = vec![self.cx.hir_map().find(item.id)];
                     if let UseTreeKind::Simple(_, i, j) = &use_tree.kind {
                         hir_nodes.push(self.cx.hir_map().find(*i));
@@ -260,6 +287,35 @@ where
                     }
                     let hir_nodes: Vec<_> = hir_nodes.into_iter().flatten().collect();
                     self.alter_use_path(item, &hir_nodes);
+                    let use_tree = expect!([&item.kind] ItemKind::Use(u) => u);
+                    if use_tree.prefix.ast_equiv(&old_prefix) {
+                        unchanged.insert(item.id);
+                    }
+                }
+
+                // Leave uses that don't change at all exactly as they were
+                if unchanged.len() == uses.len() {
+                    return mut_visit::noop_flat_map_item(item, self);
+                }
+
+                let mut item = item;
+                let id = item.id;
+                let tree = expect!([&mut item.kind] ItemKind::Use(u) => u);
+                let nested = match tree.kind {
+                    UseTreeKind::Nested(..) => true,
+                    _ => false,
+                };
+                if nested && retain_use_leaves(tree, id, &|leaf| unchanged.contains(&leaf)) {
+                    // A group with a single import left is written as a
+                    // simple import again
+                    let kept = if unchanged.len() == 1 {
+                        let idx = uses.iter().position(|u| unchanged.contains(&u.id)).unwrap();
+                        uses.remove(idx)
+                    } else {
+                        item
+                    };
+                    uses.retain(|u| !unchanged.contains(&u.id));
+                    uses.insert(0, kept);
                 }
                 uses
             }
@@ -1,6 +1,7 @@
 //! Code for applying `TextRewrite`s to the actual source files.
 use diff;
 use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
 use std::io;
 use syntax::source_map::{SourceFile, SourceMap};
 use syntax_pos::{BytePos, FileName};
@@ -145,6 +146,11 @@ fn emit_chunk<F: FnMut(&str)>(cm: &SourceMap, lo: BytePos, hi: BytePos, mut call
 
 /// Print a unified diff between lines of `s1` and lines of `s2`.
 pub fn print_diff(s1: &str, s2: &str) {
+    print!("{}", unified_diff(s1, s2));
+}
+
+/// Build the hunks of a unified diff between lines of `s1` and lines of `s2`.
+pub fn unified_diff(s1: &str, s2: &str) -> String {
     enum State {
         /// We're not in a hunk, just keeping `buf` populated with `CONTEXT` lines of history.
         History,
@@ -159,6 +165,7 @@ pub fn print_diff(s1: &str, s2: &str) {
 
     const CONTEXT: usize = 3;
 
+    let mut out = String::new();
     let mut buf = VecDeque::new();
     let mut state = State::History;
 
@@ -206,7 +213,7 @@ pub fn print_diff(s1: &str, s2: &str) {
                         // End of the hunk
                         let end = buf.len() - CONTEXT;
                         let suffix = buf.split_off(end);
-                        print_hunk(&buf, l_start, r_start);
+                        write_hunk(&mut out, &buf, l_start, r_start);
                         buf = suffix;
                         state = State::History;
                     } else {
@@ -251,15 +258,16 @@ pub fn print_diff(s1: &str, s2: &str) {
                 let end = buf.len() - (CONTEXT - unchanged_limit);
                 buf.truncate(end);
             }
-            print_hunk(&buf, l_start, r_start);
+            write_hunk(&mut out, &buf, l_start, r_start);
         }
         _ => {}
     }
+    out
 }
 
-/// Print a single diff hunk, starting at line `l_start` in the left file and `r_start` in the
-/// right file.
-fn print_hunk(buf: &VecDeque<diff::Result<&str>>, l_start: usize, r_start: usize) {
+/// Write a single diff hunk to `out`, starting at line `l_start` in the left file and `r_start`
+/// in the right file.
+fn write_hunk(out: &mut String, buf: &VecDeque<diff::Result<&str>>, l_start: usize, r_start: usize) {
     let l_size = buf
         .iter()
         .filter(|r| match r {
@@ -276,9 +284,9 @@ fn print_hunk(buf: &VecDeque<diff::Result<&str>>, l_start: usize, r_start: usize
         })
         .count();
 
-    println!("@@ -{},{} +{},{} @@", l_start, l_size, r_start, r_size);
+    writeln!(out, "@@ -{},{} +{},{} @@", l_start, l_size, r_start, r_size).unwrap();
 
-    // Print all "left" lines immediately.  Keep all "right" lines and print them just before the
+    // Write all "left" lines immediately.  Keep all "right" lines and write them just before the
     // next unchanged line.  This way we get the usual output, with separate old and new blocks:
     //   unchanged
     //  -old1
@@ -290,20 +298,20 @@ fn print_hunk(buf: &VecDeque<diff::Result<&str>>, l_start: usize, r_start: usize
     for r in buf {
         match r {
             diff::Result::Left(s) => {
-                println!("-{}", s);
+                writeln!(out, "-{}", s).unwrap();
             }
             diff::Result::Right(s) => {
                 right_buf.push(s);
             }
             diff::Result::Both(s1, s2) => {
                 if s1 != s2 {
-                    println!("-{}", s1);
+                    writeln!(out, "-{}", s1).unwrap();
                     right_buf.push(s2);
                 } else {
                     for s in right_buf.drain(..) {
-                        println!("+{}", s);
+                        writeln!(out, "+{}", s).unwrap();
                     }
-                    println!(" {}", s1);
+                    writeln!(out, " {}", s1).unwrap();
                 }
             }
         }
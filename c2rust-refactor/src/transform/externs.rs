@@ -4,7 +4,9 @@ use rustc::hir::def::DefKind;
 use rustc::hir::def_id::{DefId};
 use rustc::ty::{Instance, TyCtxt, TyKind, Ty};
 use syntax::ast::*;
+use syntax::attr;
 use syntax::ptr::P;
+use syntax_pos::sym;
 
 use c2rust_ast_builder::mk;
 use crate::ast_manip::{MutVisitNodes, visit_nodes};
@@ -111,7 +113,13 @@ pub fn fix_users(
         }
     }
 
-    // Add casts to rewritten calls and exprs
+    // Add casts to rewritten calls and exprs. `#![no_std]` crates can't name
+    // `std`, so any transmutes we insert have to go through `core` instead.
+    let std_crate = if attr::contains_name(&krate.attrs, sym::no_std) {
+        "core"
+    } else {
+        "std"
+    };
 
     MutVisitNodes::visit(krate, |e: &mut P<Expr>| {
         if let Some(&old_did) = path_ids.get(&e.id) {
@@ -119,7 +127,7 @@ pub fn fix_users(
             // cast around the whole thing.  (This should only be true for statics.)
             if let Some(&(old_ty, _new_ty)) = ty_replace_map.get(&(old_did, TyLoc::Whole)) {
                 // The rewritten expr has type `new_ty`, but its context expects `old_ty`.
-                *e = make_cast(cx, &reflector, e.clone(), old_ty);
+                *e = make_cast(cx, &reflector, std_crate, e.clone(), old_ty);
             }
         }
 
@@ -144,7 +152,7 @@ pub fn fix_users(
                             }
                             // The new fn requires `new_ty`, where the old one needed `old_ty`.
                             // TODO: fix paths in new_ty based on replacement_map
-                            let new_arg = make_cast(cx, &reflector, args[i].clone(), new_ty);
+                            let new_arg = make_cast(cx, &reflector, std_crate, args[i].clone(), new_ty);
                             args[i] = new_arg;
                         });
                         info!("  arg {} - rewrote e = {:?}", i, e);
@@ -153,7 +161,7 @@ pub fn fix_users(
 
                 if let Some(&(old_ty, _new_ty)) = ty_replace_map.get(&(old_did, TyLoc::Ret)) {
                     // The new fn returns `new_ty`, where the old context requires `old_ty`.
-                    *e = make_cast(cx, &reflector, e.clone(), old_ty);
+                    *e = make_cast(cx, &reflector, std_crate, e.clone(), old_ty);
                     info!("  return - rewrote e = {:?}", e);
                 }
             },
@@ -166,6 +174,7 @@ pub fn fix_users(
 fn make_cast<'a, 'tcx>(
     cx: &RefactorCtxt<'a, 'tcx>,
     reflector: &Reflector<'a, 'tcx>,
+    std_crate: &str,
     expr: P<Expr>,
     ty: Ty<'tcx>
 ) -> P<Expr> {
@@ -183,7 +192,7 @@ fn make_cast<'a, 'tcx>(
     }
 
     if needs_transmute {
-        mk().call_expr(mk().path_expr(vec!["", "std", "mem", "transmute"]), vec![expr])
+        mk().call_expr(mk().path_expr(vec!["", std_crate, "mem", "transmute"]), vec![expr])
     } else {
         let expr = if let ExprKind::AddrOf(_, mutability, _) = expr.kind {
             // We have to cast to *T where &T is the type of expr before casting
@@ -24,12 +24,13 @@ pub trait Transform {
 pub struct TransformCommand<T: Transform>(pub T);
 
 impl<T: Transform> Command for TransformCommand<T> {
-    fn run(&mut self, state: &mut RefactorState) {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
         state
             .transform_crate(self.0.min_phase(), |st, cx| {
                 self.0.transform(&mut *st.krate_mut(), st, cx)
             })
             .expect("Failed to run compiler");
+        Ok(())
     }
 }
 
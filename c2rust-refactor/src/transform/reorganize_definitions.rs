@@ -1,39 +1,59 @@
 use derive_more::From;
 use indexmap::IndexMap;
+use regex::{Captures, Regex};
 use smallvec::SmallVec;
-use std::collections::{HashMap, HashSet, hash_map::Entry};
+use json::{self, JsonValue};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, hash_map::{DefaultHasher, Entry}};
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::iter;
 use std::mem;
 
-use crate::transform::Transform;
 use rustc::hir::def::{DefKind, Export, Namespace, PerNS, Res};
 use rustc::hir::def_id::DefId;
 use rustc::hir::{self, HirId, Node};
 use rustc::ty::{self, ParamEnv};
 use rustc_target::spec::abi::{self, Abi};
 use syntax::ast::*;
-use syntax::attr::HasAttrs;
+use syntax::attr::{self, HasAttrs};
 use syntax::util::comments::{Comment, CommentStyle};
 use syntax::ptr::P;
-use syntax::symbol::kw;
+use syntax::symbol::{kw, sym, Symbol};
+use syntax::token::{Token, TokenKind};
+use syntax::tokenstream::{TokenStream, TokenTree};
 use syntax::util::map_in_place::MapInPlace;
-use syntax_pos::{BytePos, DUMMY_SP};
+use syntax::source_map::SourceMap;
+use syntax::visit::{self, Visitor};
+use syntax_pos::{BytePos, Span, DUMMY_SP};
 use smallvec::smallvec;
 
 use crate::ast_manip::util::{is_relative_path, join_visibility, namespace, split_uses, is_exported, is_c2rust_attr};
-use crate::ast_manip::{visit_nodes, AstEquiv, FlatMapNodes, MutVisitNodes};
-use crate::command::{CommandState, Registry};
+use crate::ast_manip::{unnamed_hash, visit_nodes, AstEquiv, FlatMapNodes, MutVisitNodes};
+use crate::command::{Command, CommandState, RefactorState, Registry};
 use crate::driver::Phase;
 use crate::path_edit::fold_resolved_paths_with_id;
+use crate::rewrite::files::unified_diff;
 use crate::RefactorCtxt;
 use crate::util::Lone;
 use c2rust_ast_builder::mk;
-use c2rust_ast_printer::pprust::{item_to_string, foreign_item_to_string};
+use c2rust_ast_printer::pprust::{self, item_to_string, foreign_item_to_string, path_to_string, vis_to_string};
 
 use super::externs;
 
+pub mod placement;
+use self::placement::{
+    has_source_header, header_module_name, is_header_attr, normalize_module_name,
+    parse_header_attr, Placement, PlacementRules, DEFAULT_HEADER_ATTR,
+};
+
+#[cfg(test)]
+mod tests;
+
 /// # `reorganize_definitions` Command
 ///
-/// Usage: `reorganize_definitions`
+/// Usage: `reorganize_definitions [OPTION=VALUE]...`
 ///
 /// This refactoring operates on code transpiled with the
 /// `--reorganize-definitions` flag.
@@ -41,24 +61,1000 @@ use super::externs;
 /// This pass refactors a crate to de-duplicate declarations, move them into
 /// their relevant modules and import the items as needed, rather than using
 /// extern forward declarations for all types and functions in headers.
-pub struct ReorganizeDefinitions;
+/// Declarations from system headers are collected into a `stdlib` module; in
+/// `#![no_std]` crates that module is called `system` instead, and any code
+/// the pass generates refers to `core` rather than `std`.
+///
+/// Problems the pass cannot resolve, such as malformed `header_src`
+/// attributes or two different items with the same name in one destination
+/// module, are reported with their source locations and fail the command.
+/// Either way, the pass ends with a note counting the declarations it moved,
+/// merged into an equivalent copy, used to replace existing extern
+/// declarations and imports, and dropped.
+///
+/// When a declaration could go into more than one existing module, a
+/// `#[c2rust::prefer_module = "a::foo"]` attribute on it selects one of them,
+/// by path or by name.
+///
+/// A header module with several `header_src` attributes for different
+/// headers (e.g. left by an earlier merge) gets a warning. Its declarations
+/// go to the existing module of the first of those headers, in attribute
+/// order, that has one, and otherwise to a new module for the first header.
+/// Other header-based options, like `module_rules` and `move_std`, only
+/// look at the first header.
+///
+/// The pass works on the macro-expanded crate, where `#[cfg]` and
+/// `#[cfg_attr]` have already been evaluated. Declarations gated on a
+/// configuration other than the current one (e.g. a struct laid out
+/// differently for 32-bit targets) are not seen, so they can't be merged or
+/// kept apart by their conditions; run the pass with the matching `--cfg`
+/// flags, or reorganize such headers by hand.
+///
+/// Options:
+///
+///  * `rewrite_doclinks=1`: also rewrite intra-doc links to moved items, e.g.
+///    `[foo_h::Bar]` becomes `[foo::Bar]`.
+///  * `max_module_depth=N`: leave the crate unchanged and fail with an error
+///    if modules are nested more than `N` levels deep. Defaults to 256.
+///  * `provenance_out=FILE`: write a JSON object to `FILE` mapping each
+///    destination module to the sorted list of headers whose declarations
+///    were moved into it.
+///  * `collapse_single=1`: if a newly created module ends up containing a
+///    single item, hoist that item into the parent module (the crate root,
+///    for most new modules) and remove the module, rewriting paths that went
+///    through it. An item whose name is taken in the parent is hoisted as
+///    `module_item`, unless it is exported under its name. Modules that are
+///    imported themselves, or named in relative paths or macro arguments,
+///    are kept.
+///  * `prefer_relative=1`: shorten absolute paths to items of the module they
+///    are used in, e.g. `crate::buffer::Foo` inside `buffer` becomes `Foo`,
+///    when the shortened name is bound exactly once in that module.
+///  * `use_style=flat|grouped|sorted`: how to lay out the simple imports of
+///    each module once the pass is done. `flat` (the default) leaves one
+///    import per item, `grouped` merges imports that share a parent path into
+///    a single `use parent::{a, b};`, and `sorted` keeps one import per item
+///    but sorts them by path.
+///  * `prune_uses=0`: keep private imports in destination modules that
+///    nothing in the module uses anymore. By default, once paths to moved
+///    declarations are rewritten, such imports are removed. An import is
+///    kept if a path in the module or its child modules refers to what it
+///    imports, e.g. as `self::Bar` or through a child's `use super::Bar;`.
+///    Imports of traits and macros are always kept, since they can be used
+///    without naming them.
+///  * `skeleton_out=FILE`: write an overview of the reorganized crate to
+///    `FILE`: the module tree and item signatures, with function bodies and
+///    initializers elided.
+///  * `prefix_map=PREFIX=>MODULE;...`: move header declarations whose name
+///    starts with `PREFIX` into the module named `MODULE` (created at the
+///    crate root if needed) instead of the module picked from their header,
+///    e.g. `prefix_map=gfx_=>graphics;net_=>network`. The longest matching
+///    prefix wins.
+///  * `module_rules=FILE`: move the declarations of each header matching one
+///    of the rules in `FILE` into the module the rule names (created at the
+///    crate root if needed) instead of the module picked from the header's
+///    name. Each line of `FILE` holds a rule of the form `REGEX => MODULE`,
+///    e.g. `^net_.*\.h$ => net`; blank lines and lines starting with `#`
+///    are ignored. A rule matches a header if its regex matches either the
+///    header's file name or its full path, and the first matching rule wins.
+///    Only `plan_in` and `map_in` take precedence over these rules.
+///  * `group_comments=1`: order the declarations moved into each module by
+///    the header they came from, in include order, and start each group with
+///    a `// from foo.h` comment instead of the usual `BEGIN`/`END` banners.
+///  * `kind_order=1`: order the items of each destination module by kind:
+///    imports first, then types, constants and statics, functions (including
+///    extern blocks) and finally impls. Items of the same kind keep their
+///    relative order.
+///  * `report_unreachable=1`: once the pass is done, print a warning for
+///    each item of a destination module that is no longer referenced by name
+///    anywhere in the crate and cannot be reached from outside it either,
+///    e.g. because the only path to it went through a removed header module.
+///  * `dedup_extern_crates=1`: collapse repeated `extern crate` items of the
+///    same crate within a module, such as the `extern crate libc;` of each
+///    translated module once they are merged. If the crate is also imported
+///    under another name there (`extern crate libc as c;`), the plain ones
+///    are dropped in favor of that. Attributes and visibility of the dropped
+///    items are carried over to the kept one.
+///  * `dedup_across_modules=1`: once declarations are moved, remove public
+///    types and constants that are defined identically in more than one
+///    destination module, keeping the first copy in crate order and pointing
+///    paths to the other copies at it. Items of the same name that differ
+///    are left alone.
+///  * `move_std=0`: leave modules for system headers (see `std_include_dirs`)
+///    where they are instead of moving their declarations into `stdlib`.
+///    Declarations from other headers are still reorganized.
+///  * `std_include_dirs=DIR:...`: treat headers under any of these
+///    directories as system headers, e.g.
+///    `std_include_dirs=/usr/include:/opt/sysroot/include`. Without it, any
+///    header whose path contains `/usr/include` is a system header.
+///  * `std_module=NAME`: move declarations from system headers into the
+///    module named `NAME` instead of `stdlib` (or `system` in `#![no_std]`
+///    crates). If the crate already has a module with that name, they are
+///    added to it.
+///  * `std_by_header=1`: instead of putting all declarations from system
+///    headers into one module, give each system header its own module
+///    inside it, named after the header file, e.g. `stdlib::stdio` for
+///    `/usr/include/stdio.h` and `stdlib::string` for `string.h`.
+///  * `keep_empty_headers=1`: leave header modules whose declarations were
+///    all moved out in place as empty modules, keeping their `header_src`
+///    attribute, instead of deleting them, for tools that look for the
+///    original `foo_h` modules.
+///  * `emit_shims=1`: like `keep_empty_headers`, but also add a
+///    `pub use crate::foo::Bar;` to each header module for every public
+///    declaration moved out of it, so that outside code using the old
+///    `foo_h::Bar` paths keeps compiling.
+///  * `include=FILE`: only reorganize the top-level modules named in `FILE`,
+///    one per line (blank lines and lines starting with `#` are ignored).
+///    Header modules inside other top-level modules are left untouched, and
+///    those modules are not considered as destinations.
+///  * `merge_foreign_by_name=1`: merge extern declarations with the same name
+///    and ABI even if their signatures differ (e.g. `c_int` and `i32` after
+///    retyping), keeping the first one, and print a warning showing both
+///    signatures for each mismatch, so they can be reconciled by hand.
+///    Without it, such declarations are kept apart and usually collide.
+///  * `on_conflict=error|rename`: what to do when two different header
+///    declarations with the same name would go into the same module.
+///    `error` (the default) reports both headers they came from and fails
+///    the command. `rename` keeps the first one as is and renames each later
+///    one by appending the name of its header, e.g. `config_net`, updating
+///    all paths to it; renamed extern functions and statics keep their
+///    symbol name through a `#[link_name]` attribute.
+///  * `only=NAME,...`: only reorganize the header modules with these names,
+///    e.g. `only=foo_h,bar_h`. Other header modules are left untouched:
+///    nothing is moved out of them and they are not removed.
+///  * `exclude=NAME,...`: leave the header modules with these names
+///    untouched. Takes precedence over `only`.
+///  * `emit_prelude=1`: add a `prelude` module to the crate root with a
+///    `pub use` of every public item moved out of a (non-system) header, so
+///    that users can write `use crate::prelude::*;` instead of updating each
+///    path. Names moved into more than one module are left out, since a glob
+///    import could not tell them apart.
+///  * `report_type_cycles=1`: once the pass is done, print a warning for
+///    each group of moved types that refer to each other (e.g. two structs
+///    with pointers to one another) but were moved into different modules,
+///    since such types usually belong together.
+///  * `ffi_submodule_threshold=N`: if more than `N` foreign items are moved
+///    into a module, put their extern blocks into a `ffi` submodule and
+///    re-export its contents with `pub use self::ffi::*;`. Private foreign
+///    items become `pub(super)` so the parent module can still use them.
+///  * `selfcheck=1`: after the pass, run it a second time on its own output
+///    and fail, listing the items that changed, if that run changes
+///    anything. Meant for CI, to catch changes that make the pass
+///    non-idempotent.
+///  * `internal_module=NAME`: move header declarations with translator-internal
+///    names (see `internal_patterns`) into the module named `NAME`, created
+///    at the crate root if needed, instead of the module picked from their
+///    header. Declarations from system headers still go to `stdlib`.
+///  * `internal_patterns=PREFIX;...`: name prefixes that mark a declaration
+///    as internal for `internal_module`. Defaults to `C2RustUnnamed;__`.
+///  * `plan_out=FILE`: write the destination module picked for each header
+///    declaration to `FILE` as a JSON object, e.g.
+///    `{"crate::foo::foo_h::Bar": "crate::foo"}`, keyed by the declaration's
+///    original path. The file can be reviewed and edited before applying it
+///    with `plan_in`.
+///  * `pin=NAME:MODULE,...`: move the header declarations named `NAME` into
+///    the module `MODULE` (e.g. `pin=buffer_t:buffer,errno:sys::err`),
+///    whatever the other options and heuristics would pick, creating the
+///    module if needed. Unlike `plan_in`, this matches declarations by
+///    name alone, wherever they are declared.
+///  * `plan_in=FILE`: move each declaration listed in the JSON object in
+///    `FILE` (as written by `plan_out`) into the module given there instead
+///    of the one the usual heuristics (including `move_std`,
+///    `internal_module` and `prefix_map`) would pick. Modules named in the
+///    plan are created if needed, including nested ones like
+///    `crate::sys::io` and their parents. Declarations the plan doesn't
+///    mention are routed as usual. A plan that can't be read is reported as
+///    an error before the crate is touched.
+///  * `map_out=FILE`: write a JSON object to `FILE` mapping each header module
+///    to the module its declarations were moved into, e.g.
+///    `{"crate::foo::foo_h": "crate::foo"}`. Header modules whose
+///    declarations were split across several modules are left out with a
+///    warning; use `plan_out` to pin those down per declaration.
+///  * `path_report=FILE`: write a JSON object to `FILE` describing every
+///    path rewrite, keyed by the original path of each moved (or merged)
+///    declaration, e.g. `{"crate::foo::foo_h::Bar": {"old_path":
+///    "crate::foo::foo_h::Bar", "new_path": "crate::foo::Bar", "module":
+///    "crate::foo"}}`. `module` is `null` for declarations replaced by an
+///    item outside of the reorganized modules, such as one from another
+///    crate.
+///  * `map_in=FILE`: move the declarations of each header module listed in
+///    the JSON object in `FILE` (as written by `map_out`) into the module
+///    given there, instead of the one the usual heuristics would pick.
+///    Routes of single declarations in `plan_in` take precedence. Header
+///    modules the map doesn't mention are routed as usual.
+///  * `dry_run=1`: print the destination module picked for each header
+///    declaration, as `crate::foo::foo_h::Bar -> crate::foo`, and leave the
+///    crate unchanged. Errors are still reported.
+///  * `diff=1`: pretty-print the crate before and after reorganizing it and
+///    print a unified diff of the two, leaving the crate unchanged, so that
+///    the changes can be reviewed before applying them.
+///  * `diff_out=FILE`: like `diff=1`, but write the diff to `FILE` instead.
+///  * `header_attr=NAME`: recognize header modules by the attribute
+///    `#[c2rust::NAME = "path:line"]` instead of `#[c2rust::header_src]`,
+///    for crates whose translator marks header provenance differently.
+///    `NAME` can also be a full path, e.g. `header_attr=my_tool::origin`
+///    for `#[my_tool::origin = "path:line"]`.
+///  * `verbose=1`: for each header declaration, print the module it is
+///    moved into and which rule picked that module, e.g. a `plan_in` route,
+///    a system header, or the existing modules named after its header that
+///    were considered. Meant for finding out why a declaration ended up in
+///    an unexpected module.
+///  * `phase=N`: run the transform at compiler phase `N` (2 or 3) instead of
+///    phase 3. Phase 2 only needs name resolution, so it is faster and works
+///    on crates that don't typecheck yet, e.g. in the middle of a
+///    translation. Declarations are then matched by comparing their syntax
+///    rather than their types, so copies that only differ in how they spell
+///    a type are kept apart, and declarations are not matched against the
+///    exports of other crates. The command refuses to start if any selected
+///    option needs typeck results, and always at phase 1, which has no name
+///    resolution.
+pub struct ReorganizeDefinitions {
+    options: Options,
+}
+
+/// Optional `KEY=VALUE` arguments to `reorganize_definitions`. A bare `KEY` is
+/// shorthand for `KEY=1`.
+#[derive(Clone, Debug)]
+struct Options {
+    /// Rewrite paths to moved items in intra-doc links
+    rewrite_doclinks: bool,
+
+    /// Maximum module nesting depth we are willing to process
+    max_module_depth: usize,
+
+    /// File to write the destination module -> source headers manifest to
+    provenance_out: Option<String>,
+
+    /// Hoist the contents of new single-item modules into the crate root
+    collapse_single: bool,
+
+    /// Shorten absolute paths to items in the current module
+    prefer_relative: bool,
+
+    /// Layout of the imports in each module
+    use_style: UseStyle,
+
+    /// Remove private imports that are no longer used
+    prune_uses: bool,
+
+    /// File to write the body-less outline of the reorganized crate to
+    skeleton_out: Option<String>,
+
+    /// Item name prefixes and the modules items with that prefix go to
+    prefix_map: Vec<(String, Ident)>,
+
+    /// Header path patterns and the modules their declarations go to
+    module_rules: Vec<(Regex, Ident)>,
+
+    /// Separate moved items by source header with `// from` comments
+    group_comments: bool,
+
+    /// Order the items of destination modules by kind
+    kind_order: bool,
+
+    /// Warn about items orphaned by the reorganization
+    report_unreachable: bool,
+
+    /// Collapse repeated `extern crate` items within a module
+    dedup_extern_crates: bool,
+
+    /// Keep a single copy of definitions duplicated across modules
+    dedup_across_modules: bool,
+
+    /// Move declarations from system headers into the `stdlib` module
+    move_std: bool,
+
+    /// Directories containing system headers, if not the default
+    std_include_dirs: Option<Vec<String>>,
+
+    /// Module system header declarations go to, if not the default
+    std_module: Option<Ident>,
+
+    /// Give each system header its own module inside the `std_module`
+    std_by_header: bool,
+
+    /// Keep emptied header modules instead of deleting them
+    keep_empty_headers: bool,
+
+    /// Re-export moved declarations from their old header modules
+    emit_shims: bool,
+
+    /// Names of the only top-level modules to reorganize, if restricted
+    include: Option<HashSet<String>>,
+
+    /// Merge same-named extern declarations regardless of their signatures
+    merge_foreign_by_name: bool,
+
+    /// How to handle different declarations with the same name in a module
+    on_conflict: OnConflict,
+
+    /// Names of the only header modules to reorganize, if restricted
+    only_headers: Option<HashSet<String>>,
+
+    /// Names of header modules to leave untouched
+    exclude_headers: HashSet<String>,
+
+    /// Re-export the public moved items from a `prelude` module
+    emit_prelude: bool,
+
+    /// Warn about mutually-referential types split across modules
+    report_type_cycles: bool,
+
+    /// Number of moved foreign items above which they go into a submodule
+    ffi_submodule_threshold: Option<usize>,
+
+    /// Check that a second run leaves the crate unchanged
+    selfcheck: bool,
+
+    /// Module translator-internal items go to, if any
+    internal_module: Option<Ident>,
+
+    /// Name prefixes of translator-internal items
+    internal_patterns: Vec<String>,
+
+    /// File to write the computed declaration -> module routing to
+    plan_out: Option<String>,
+
+    /// File with the declaration -> module routing to apply instead of the
+    /// heuristics
+    plan_in: Option<String>,
+
+    /// Routing read from `plan_in` once the command starts
+    routes: Option<HashMap<String, String>>,
+
+    /// Declaration name -> module routing that overrides everything else
+    pins: HashMap<Ident, String>,
+
+    /// File to write the computed header module -> module mapping to
+    map_out: Option<String>,
+
+    /// Header module -> module mapping to apply instead of the heuristics
+    map_in: Option<HashMap<String, String>>,
+
+    /// File to write the old and new path of each moved declaration to
+    path_report: Option<String>,
+
+    /// Only print the routing, without changing the crate
+    dry_run: bool,
+
+    /// Print a diff of the reorganized crate instead of applying it
+    diff: bool,
+
+    /// File to write the diff of the reorganized crate to
+    diff_out: Option<String>,
+
+    /// Print why each declaration was moved where it was
+    verbose: bool,
+
+    /// Attribute marking header modules
+    header_attr: String,
+
+    /// Compiler phase to run at
+    phase: Phase,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            rewrite_doclinks: false,
+            max_module_depth: 256,
+            provenance_out: None,
+            collapse_single: false,
+            prefer_relative: false,
+            use_style: UseStyle::Flat,
+            prune_uses: true,
+            skeleton_out: None,
+            prefix_map: vec![],
+            module_rules: vec![],
+            group_comments: false,
+            kind_order: false,
+            report_unreachable: false,
+            dedup_across_modules: false,
+            dedup_extern_crates: false,
+            move_std: true,
+            std_include_dirs: None,
+            std_module: None,
+            std_by_header: false,
+            keep_empty_headers: false,
+            emit_shims: false,
+            include: None,
+            merge_foreign_by_name: false,
+            on_conflict: OnConflict::Error,
+            only_headers: None,
+            exclude_headers: HashSet::new(),
+            emit_prelude: false,
+            report_type_cycles: false,
+            ffi_submodule_threshold: None,
+            selfcheck: false,
+            internal_module: None,
+            internal_patterns: vec!["C2RustUnnamed".to_string(), "__".to_string()],
+            plan_out: None,
+            plan_in: None,
+            routes: None,
+            pins: HashMap::new(),
+            map_out: None,
+            map_in: None,
+            path_report: None,
+            dry_run: false,
+            diff: false,
+            diff_out: None,
+            verbose: false,
+            header_attr: DEFAULT_HEADER_ATTR.to_string(),
+            phase: Phase::Phase3,
+        }
+    }
+}
+
+impl Options {
+    /// Should the pass only show a diff of its changes?
+    fn previews_diff(&self) -> bool {
+        self.diff || self.diff_out.is_some()
+    }
+
+    fn from_args(args: &[String]) -> Self {
+        let mut options = Options::default();
+        for arg in args {
+            let (key, value) = match arg.find('=') {
+                Some(idx) => (&arg[..idx], &arg[idx + 1..]),
+                None => (&arg[..], "1"),
+            };
+            match key {
+                "rewrite_doclinks" => options.rewrite_doclinks = parse_flag(key, value),
+                "max_module_depth" => options.max_module_depth = parse_number(key, value),
+                "provenance_out" => options.provenance_out = Some(value.to_string()),
+                "collapse_single" => options.collapse_single = parse_flag(key, value),
+                "prefer_relative" => options.prefer_relative = parse_flag(key, value),
+                "skeleton_out" => options.skeleton_out = Some(value.to_string()),
+                "prune_uses" => options.prune_uses = parse_flag(key, value),
+                "use_style" => options.use_style = match value {
+                    "flat" => UseStyle::Flat,
+                    "grouped" => UseStyle::Grouped,
+                    "sorted" => UseStyle::Sorted,
+                    _ => panic!("Expected flat, grouped or sorted for reorganize_definitions option {}, found {:?}", key, value),
+                },
+                "prefix_map" => options.prefix_map = parse_prefix_map(key, value),
+                "module_rules" => options.module_rules = parse_module_rules(key, value),
+                "group_comments" => options.group_comments = parse_flag(key, value),
+                "kind_order" => options.kind_order = parse_flag(key, value),
+                "report_unreachable" => options.report_unreachable = parse_flag(key, value),
+                "dedup_across_modules" => options.dedup_across_modules = parse_flag(key, value),
+                "dedup_extern_crates" => options.dedup_extern_crates = parse_flag(key, value),
+                "move_std" => options.move_std = parse_flag(key, value),
+                "std_include_dirs" => {
+                    options.std_include_dirs = Some(
+                        value
+                            .split(':')
+                            .filter(|dir| !dir.is_empty())
+                            .map(|dir| dir.to_string())
+                            .collect()
+                    )
+                }
+                "std_module" => options.std_module = Some(Ident::from_str(value)),
+                "std_by_header" => options.std_by_header = parse_flag(key, value),
+                "keep_empty_headers" => options.keep_empty_headers = parse_flag(key, value),
+                "emit_shims" => options.emit_shims = parse_flag(key, value),
+                "include" => options.include = Some(parse_module_list(key, value)),
+                "merge_foreign_by_name" => options.merge_foreign_by_name = parse_flag(key, value),
+                "on_conflict" => options.on_conflict = match value {
+                    "error" => OnConflict::Error,
+                    "rename" => OnConflict::Rename,
+                    _ => panic!("Expected error or rename for reorganize_definitions option {}, found {:?}", key, value),
+                },
+                "only" => options.only_headers = Some(parse_name_list(value)),
+                "exclude" => options.exclude_headers = parse_name_list(value),
+                "emit_prelude" => options.emit_prelude = parse_flag(key, value),
+                "report_type_cycles" => options.report_type_cycles = parse_flag(key, value),
+                "ffi_submodule_threshold" => {
+                    options.ffi_submodule_threshold = Some(parse_number(key, value))
+                }
+                "selfcheck" => options.selfcheck = parse_flag(key, value),
+                "internal_module" => options.internal_module = Some(Ident::from_str(value)),
+                "internal_patterns" => {
+                    options.internal_patterns = value
+                        .split(';')
+                        .filter(|prefix| !prefix.is_empty())
+                        .map(|prefix| prefix.to_string())
+                        .collect()
+                }
+                "plan_out" => options.plan_out = Some(value.to_string()),
+                "plan_in" => options.plan_in = Some(value.to_string()),
+                "pin" => options.pins = parse_pins(key, value),
+                "map_out" => options.map_out = Some(value.to_string()),
+                "map_in" => options.map_in = Some(parse_module_map(key, value)),
+                "path_report" => options.path_report = Some(value.to_string()),
+                "dry_run" => options.dry_run = parse_flag(key, value),
+                "diff" => options.diff = parse_flag(key, value),
+                "diff_out" => options.diff_out = Some(value.to_string()),
+                "verbose" => options.verbose = parse_flag(key, value),
+                "header_attr" => options.header_attr = value.to_string(),
+                "phase" => options.phase = parse_phase(key, value),
+                _ => panic!("Unknown reorganize_definitions option: {}", arg),
+            }
+        }
+        options
+    }
+
+    /// Names of the parts of the transform that need results (typeck tables,
+    /// def paths) only available in phase 3.
+    fn phase3_requirements(&self) -> Vec<&'static str> {
+        let mut reqs = vec![];
+        if self.rewrite_doclinks {
+            reqs.push("rewrite_doclinks");
+        }
+        if self.emit_prelude {
+            reqs.push("emit_prelude");
+        }
+        if self.report_type_cycles {
+            reqs.push("report_type_cycles");
+        }
+        reqs
+    }
+
+    /// Module that items named `ident` should be moved to according to
+    /// `prefix_map`, if any
+    fn prefix_module(&self, ident: Ident) -> Option<Ident> {
+        let name = ident.as_str();
+        self.prefix_map
+            .iter()
+            .filter(|(prefix, _)| name.starts_with(&prefix[..]))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, module)| *module)
+    }
+
+    /// Module that declarations from the header at `path` should be moved to
+    /// according to `module_rules`, if any
+    fn rule_module(&self, path: &str) -> Option<Ident> {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        self.module_rules
+            .iter()
+            .find(|(regex, _)| regex.is_match(file_name) || regex.is_match(path))
+            .map(|(_, module)| *module)
+    }
+
+    /// Module that items named `ident` should be moved to according to
+    /// `internal_module` and `internal_patterns`, if any
+    fn internal_module(&self, ident: Ident) -> Option<Ident> {
+        let module = self.internal_module?;
+        let name = ident.as_str();
+        if self.internal_patterns.iter().any(|prefix| name.starts_with(&prefix[..])) {
+            Some(module)
+        } else {
+            None
+        }
+    }
+
+    /// Should the header module named `ident` be reorganized, according to
+    /// `only` and `exclude`?
+    fn is_header_selected(&self, ident: Ident) -> bool {
+        let name = &*ident.as_str();
+        !self.exclude_headers.contains(name)
+            && self.only_headers.as_ref().map_or(true, |only| only.contains(name))
+    }
+
+    /// Is `path` the path of a system header?
+    fn is_std_header(&self, path: &str) -> bool {
+        placement::is_std_header(path, self.std_include_dirs.as_ref().map(|dirs| &dirs[..]))
+    }
+
+    /// The rules for classifying header modules set by `header_attr` and
+    /// `std_include_dirs`
+    fn placement_rules(&self) -> PlacementRules {
+        PlacementRules {
+            header_attr: self.header_attr.clone(),
+            std_include_dirs: self.std_include_dirs.clone(),
+        }
+    }
+
+    /// Refuse to run at a phase that lacks results the selected options
+    /// depend on
+    fn check_phase(&self) -> Result<(), String> {
+        if self.phase >= Phase::Phase3 {
+            return Ok(());
+        }
+        // Header declarations and their users are tied together through
+        // name resolution
+        if self.phase < Phase::Phase2 {
+            return Err(format!(
+                "reorganize_definitions: {:?} is too early; the pass requires phase 2 or later",
+                self.phase,
+            ));
+        }
+        let reqs = self.phase3_requirements();
+        if !reqs.is_empty() {
+            return Err(format!(
+                "reorganize_definitions: {:?} is too early; {} requires phase 3",
+                self.phase,
+                reqs.join(", "),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Read the routes of `plan_in`, if given
+    fn load_plan(&mut self) -> Result<(), String> {
+        if let Some(path) = &self.plan_in {
+            self.routes = Some(read_plan(path)?);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UseStyle {
+    /// One import per item, in the order they were produced
+    Flat,
+    /// Imports sharing a parent path merged into one nested import
+    Grouped,
+    /// One import per item, sorted by path
+    Sorted,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OnConflict {
+    /// Report the conflicting declarations and fail
+    Error,
+    /// Rename all but the first of the conflicting declarations
+    Rename,
+}
+
+fn parse_flag(key: &str, value: &str) -> bool {
+    match value {
+        "1" | "true" | "yes" => true,
+        "0" | "false" | "no" => false,
+        _ => panic!("Expected a boolean for reorganize_definitions option {}, found {:?}", key, value),
+    }
+}
+
+fn parse_phase(key: &str, value: &str) -> Phase {
+    match value {
+        "1" => Phase::Phase1,
+        "2" => Phase::Phase2,
+        "3" => Phase::Phase3,
+        _ => panic!("Expected 1, 2 or 3 for reorganize_definitions option {}, found {:?}", key, value),
+    }
+}
+
+fn parse_prefix_map(key: &str, value: &str) -> Vec<(String, Ident)> {
+    value
+        .split(';')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let idx = entry.find("=>").unwrap_or_else(|| {
+                panic!("Expected PREFIX=>MODULE for reorganize_definitions option {}, found {:?}", key, entry)
+            });
+            let (prefix, module) = (&entry[..idx], &entry[idx + 2..]);
+            if prefix.is_empty() || module.is_empty() {
+                panic!("Expected PREFIX=>MODULE for reorganize_definitions option {}, found {:?}", key, entry);
+            }
+            (prefix.to_string(), Ident::from_str(module))
+        })
+        .collect()
+}
+
+/// Parse the `NAME:MODULE,...` routes of `pin`
+fn parse_pins(key: &str, value: &str) -> HashMap<Ident, String> {
+    value
+        .split(',')
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (name, module) = match entry.find(':') {
+                Some(idx) => (&entry[..idx], &entry[idx + 1..]),
+                None => ("", ""),
+            };
+            if name.is_empty() || module.is_empty() {
+                panic!("Expected NAME:MODULE for reorganize_definitions option {}, found {:?}", key, entry);
+            }
+            (Ident::from_str(name), module.to_string())
+        })
+        .collect()
+}
+
+/// Read the `REGEX => MODULE` rules of a `module_rules` file
+fn parse_module_rules(key: &str, value: &str) -> Vec<(Regex, Ident)> {
+    let contents = fs::read_to_string(value).unwrap_or_else(|e| {
+        panic!("Could not read {} for reorganize_definitions option {}: {}", value, key, e)
+    });
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let rule = line.rfind("=>").map(|idx| (line[..idx].trim(), line[idx + 2..].trim()));
+            let (pattern, module) = match rule {
+                Some((pattern, module)) if !pattern.is_empty() && !module.is_empty() => (pattern, module),
+                _ => panic!(
+                    "Expected REGEX => MODULE in {} for reorganize_definitions option {}, found {:?}",
+                    value, key, line,
+                ),
+            };
+            let regex = Regex::new(pattern).unwrap_or_else(|e| {
+                panic!(
+                    "Invalid pattern {:?} in {} for reorganize_definitions option {}: {}",
+                    pattern, value, key, e,
+                )
+            });
+            (regex, Ident::from_str(module))
+        })
+        .collect()
+}
+
+fn parse_module_list(key: &str, value: &str) -> HashSet<String> {
+    let contents = fs::read_to_string(value).unwrap_or_else(|e| {
+        panic!("Could not read {} for reorganize_definitions option {}: {}", value, key, e)
+    });
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Split a comma-separated list of names
+fn parse_name_list(value: &str) -> HashSet<String> {
+    value
+        .split(',')
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Read a plan written by `plan_out`: a JSON object mapping the original
+/// paths of header declarations to destination module paths
+fn read_plan(path: &str) -> Result<HashMap<String, String>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read reorganization plan {}: {}", path, e))?;
+    let plan = json::parse(&contents)
+        .map_err(|e| format!("Could not parse reorganization plan {}: {}", path, e))?;
+    if !plan.is_object() {
+        return Err(format!("Expected a JSON object in reorganization plan {}", path));
+    }
+    plan.entries()
+        .map(|(item, module)| match module.as_str() {
+            Some(module) => Ok((item.to_string(), module.to_string())),
+            None => Err(format!(
+                "Expected a module path for {:?} in reorganization plan {}, found {}",
+                item, path, module,
+            )),
+        })
+        .collect()
+}
+
+/// Read a JSON object mapping header module paths to destination module
+/// paths, as written by `map_out`
+fn parse_module_map(key: &str, value: &str) -> HashMap<String, String> {
+    let contents = fs::read_to_string(value).unwrap_or_else(|e| {
+        panic!("Could not read {} for reorganize_definitions option {}: {}", value, key, e)
+    });
+    let map = json::parse(&contents).unwrap_or_else(|e| {
+        panic!("Could not parse {} for reorganize_definitions option {}: {}", value, key, e)
+    });
+    if !map.is_object() {
+        panic!("Expected a JSON object in {} for reorganize_definitions option {}", value, key);
+    }
+    map.entries()
+        .map(|(source, dest)| {
+            let dest = dest.as_str().unwrap_or_else(|| {
+                panic!(
+                    "Expected a module path for {:?} in {} for reorganize_definitions option {}, found {}",
+                    source, value, key, dest,
+                )
+            });
+            (source.to_string(), dest.to_string())
+        })
+        .collect()
+}
+
+fn parse_number(key: &str, value: &str) -> usize {
+    value.parse().unwrap_or_else(|_| {
+        panic!("Expected a number for reorganize_definitions option {}, found {:?}", key, value)
+    })
+}
+
+/// Problems that keep `reorganize_definitions` from producing a correct crate.
+/// The pass records these and carries on with the remaining items; they are
+/// reported together, with their source locations, when it finishes.
+#[derive(Debug)]
+enum ReorganizeError {
+    /// A `header_src` attribute is missing or is not of the form `path:line`.
+    MalformedHeaderSrc(Span),
+
+    /// A `header_src` attribute is attached to something other than a module.
+    UnexpectedHeaderItem(Ident, Span),
+
+    /// No destination module could be found for a header declaration.
+    UnresolvedDestination(Ident, Span),
+
+    /// A definition would be placed in a module (the second `Ident`) that
+    /// already has a different item with the same name.
+    NameCollision(Ident, Ident, Span),
+
+    /// Two different header declarations with the same name (the first
+    /// `Ident`), from the headers at the two paths, would go into the same
+    /// module (the second `Ident`).
+    ConflictingDeclarations(Ident, Ident, String, String, Span),
+
+    /// Two impls of the same (now deduplicated) type define an associated
+    /// item with the same name but different definitions.
+    ConflictingImplItem(Ident, Span),
+
+    /// A module has the same name as an earlier sibling module.
+    DuplicateModule(Ident, Span),
+
+    /// Modules are nested deeper (the first `usize`) than `max_module_depth`
+    /// (the second one) allows.
+    ModuleTooDeep(usize, usize, Span),
+
+    /// An output file (described by the `&str`) could not be written to
+    /// the path given by the first `String`, with the I/O error in the second.
+    WriteFailed(&'static str, String, String),
+}
+
+impl ReorganizeError {
+    fn span(&self) -> Span {
+        match *self {
+            ReorganizeError::MalformedHeaderSrc(span)
+            | ReorganizeError::UnexpectedHeaderItem(_, span)
+            | ReorganizeError::UnresolvedDestination(_, span)
+            | ReorganizeError::NameCollision(_, _, span)
+            | ReorganizeError::ConflictingDeclarations(_, _, _, _, span)
+            | ReorganizeError::ConflictingImplItem(_, span)
+            | ReorganizeError::DuplicateModule(_, span)
+            | ReorganizeError::ModuleTooDeep(_, _, span) => span,
+            ReorganizeError::WriteFailed(..) => DUMMY_SP,
+        }
+    }
+
+    /// Describe the error, after its source location if it has one
+    fn to_message(&self, source_map: &SourceMap) -> String {
+        if self.span().is_dummy() {
+            self.to_string()
+        } else {
+            format!("{}: {}", source_map.span_to_string(self.span()), self)
+        }
+    }
+}
+
+impl fmt::Display for ReorganizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReorganizeError::MalformedHeaderSrc(_) => {
+                write!(f, "expected a header_src attribute of the form \"path:line\"")
+            }
+            ReorganizeError::UnexpectedHeaderItem(ident, _) => {
+                write!(f, "header_src attribute on {}, which is not a module", ident)
+            }
+            ReorganizeError::UnresolvedDestination(ident, _) => {
+                write!(f, "could not find a destination module for {}", ident)
+            }
+            ReorganizeError::NameCollision(ident, module, _) => {
+                write!(f, "module {} already contains a different item named {}", module, ident)
+            }
+            ReorganizeError::ConflictingDeclarations(ident, module, first, second, _) => {
+                write!(
+                    f,
+                    "module {} would get two different declarations of {}, from {} and {}",
+                    module,
+                    ident,
+                    first,
+                    second,
+                )
+            }
+            ReorganizeError::ConflictingImplItem(ident, _) => {
+                write!(f, "conflicting definitions of {} in impls of the same type", ident)
+            }
+            ReorganizeError::DuplicateModule(ident, _) => {
+                write!(f, "module {} is defined more than once in the same parent module", ident)
+            }
+            ReorganizeError::ModuleTooDeep(depth, max, _) => {
+                write!(f, "modules are nested {} levels deep, exceeding max_module_depth={}", depth, max)
+            }
+            ReorganizeError::WriteFailed(what, path, err) => {
+                write!(f, "could not write {} {}: {}", what, path, err)
+            }
+        }
+    }
+}
 
 /// Holds the information of the current `Crate`, which includes a `HashMap` to look up Items
 /// quickly, as well as other members that hold important information.
 pub struct Reorganizer<'a, 'tcx: 'a> {
     cx: &'a RefactorCtxt<'a, 'tcx>,
     st: &'a CommandState,
+    options: &'a Options,
 
     modules: IndexMap<NodeId, ModuleInfo>,
 
+    // Module for system header declarations, or `DUMMY_NODE_ID` until one
+    // is needed
     stdlib_id: NodeId,
 
+    // Name of the module for system header declarations
+    stdlib_ident: Ident,
+
     // Mapping from replaced item DefId to the path of its replacement and the
     // replacements parent module NodeId
     path_mapping: HashMap<DefId, Replacement>,
 
+    // Destination module of the first moved type alias of each anonymous
+    // type, keyed by the type's DefId, so that both end up together
+    typedef_dests: HashMap<DefId, NodeId>,
+
     // Counter used by `unique_ident`
     ident_counter: HashMap<Ident, usize>,
+
+    // Set of header paths that contributed moved declarations to each
+    // destination module
+    provenance: HashMap<NodeId, BTreeSet<String>>,
+
+    // `pub use self::...` re-exports removed along with their header modules
+    self_reexports: Vec<SelfReexport>,
+
+    // Top-level modules left out by the `include` option
+    excluded_mods: HashSet<NodeId>,
+
+    // Destination module path of each moved declaration, keyed by its
+    // original path, for `plan_out` and `dry_run`
+    plan: BTreeMap<String, String>,
+
+    // Destination module paths of the declarations of each header module,
+    // keyed by the header module's path, for `map_out`
+    module_routes: BTreeMap<String, BTreeSet<String>>,
+
+    // Counts of moved, merged and dropped declarations
+    summary: Summary,
+
+    // Errors encountered so far
+    errors: RefCell<Vec<ReorganizeError>>,
+}
+
+/// Counts of what happened to header declarations, reported when
+/// `reorganize_definitions` finishes.
+#[derive(Clone, Copy, Debug, Default)]
+struct Summary {
+    /// Declarations moved into a destination module
+    moved: usize,
+
+    /// Declarations (and imports of them) merged into an equivalent copy
+    merged: usize,
+
+    /// Extern declarations and imports in destination modules replaced by
+    /// the moved declarations
+    replaced: usize,
+
+    /// Declarations left out because they had no valid destination
+    dropped: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "reorganize_definitions: moved {} declarations, merged {} duplicates, \
+             replaced {} extern declarations and imports, dropped {} declarations",
+            self.moved,
+            self.merged,
+            self.replaced,
+            self.dropped,
+        )
+    }
+}
+
+/// A `pub use self::...` import found in a header module, which has to be
+/// recreated in the module containing the header once the item it goes
+/// through has been moved.
+struct SelfReexport {
+    /// NodeId of the module the header module was declared in
+    parent: NodeId,
+
+    /// DefId of the header item named by the segment after `self`
+    def_id: DefId,
+
+    /// Visibility of the original import
+    vis: Visibility,
+
+    /// The original import tree, still starting with `self::`
+    tree: UseTree,
 }
 
 #[derive(Clone)]
@@ -66,8 +1062,11 @@ struct Replacement {
     /// Path to replacement item
     path: Path,
 
-    /// NodeId of replacement's parent module
-    parent: NodeId,
+    /// NodeId of replacement's parent module, or `None` if the replacement
+    /// does not live in a module known to the reorganizer (e.g. a foreign
+    /// declaration that was matched against an external definition). An
+    /// unresolved parent never compares equal to any real module.
+    parent: Option<NodeId>,
 
     /// DefId of replacement if available
     def: Option<DefId>,
@@ -86,6 +1085,9 @@ struct ModuleInfo {
     /// Is this module a newly created module (or an existing module)?
     new: bool,
 
+    /// The new module's parent module, if it isn't created at the crate root
+    parent: Option<NodeId>,
+
     /// Does this module have a main function
     has_main: bool,
 
@@ -100,19 +1102,59 @@ struct ModuleInfo {
 }
 
 impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
-    fn new(st: &'a CommandState, cx: &'a RefactorCtxt<'a, 'tcx>) -> Self {
+    fn new(st: &'a CommandState, cx: &'a RefactorCtxt<'a, 'tcx>, options: &'a Options) -> Self {
         Reorganizer {
             st,
             cx,
+            options,
             modules: IndexMap::new(),
             path_mapping: HashMap::new(),
+            typedef_dests: HashMap::new(),
             stdlib_id: DUMMY_NODE_ID,
+            stdlib_ident: Ident::from_str("stdlib"),
             ident_counter: HashMap::new(),
+            provenance: HashMap::new(),
+            self_reexports: Vec::new(),
+            excluded_mods: HashSet::new(),
+            plan: BTreeMap::new(),
+            module_routes: BTreeMap::new(),
+            summary: Summary::default(),
+            errors: RefCell::new(Vec::new()),
         }
     }
 
+    /// Record an error to be reported once the pass finishes
+    fn report(&self, error: ReorganizeError) {
+        self.errors.borrow_mut().push(error);
+    }
+
     /// Run the reorganization pass
     pub fn run(&mut self, krate: &mut Crate) {
+        // The passes below walk the AST recursively, so refuse to touch
+        // pathologically deep module trees rather than overflowing the stack.
+        let depth = module_depth(krate);
+        if depth > self.options.max_module_depth {
+            self.report(ReorganizeError::ModuleTooDeep(depth, self.options.max_module_depth, krate.span));
+            return;
+        }
+
+        if let Some(include) = &self.options.include {
+            self.excluded_mods = krate.module.items
+                .iter()
+                .filter(|item| match item.kind {
+                    ItemKind::Mod(..) => !include.contains(&*item.ident.as_str()),
+                    _ => false,
+                })
+                .map(|item| item.id)
+                .collect();
+        }
+
+        // A crate the pass has already run on has no header modules left.
+        // Don't touch it at all, so that running the pass again is a no-op.
+        if !self.has_header_modules(krate) {
+            return;
+        }
+
         self.find_destination_modules(&krate);
 
         // let mut module_items = HashMap::new();
@@ -123,91 +1165,407 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
 
         self.move_items(header_decls, krate);
 
-        self.update_paths(krate)
-    }
+        if self.options.dedup_across_modules {
+            self.dedup_across_modules(krate);
+        }
 
-    /// Return a new unique identifier with the given prefix
-    fn unique_ident(&mut self, ident: Ident) -> Ident {
-        match self.ident_counter.entry(ident) {
-            Entry::Vacant(e) => {
-                e.insert(0);
-                ident
-            }
-            Entry::Occupied(mut e) => {
-                let ev = e.get_mut();
-                let res = format!("{}_{}", ident.as_str(), *ev);
-                *ev += 1;
-                Ident::from_str(&res)
-            }
+        self.update_paths(krate);
+
+        self.rewrite_header_globs(krate);
+
+        self.rewrite_token_paths(krate);
+
+        self.restore_self_reexports(krate);
+
+        if self.options.emit_shims {
+            self.emit_shims(krate);
         }
-    }
 
-    /// Iterate through the Crate and enumerate potentential destination modules.
-    fn find_destination_modules(&mut self, krate: &Crate) {
-        visit_nodes(krate, |i: &Item| {
-            if let ItemKind::Mod(m) = &i.kind {
-                if !has_source_header(&i.attrs)
-                    && m.items.iter().any(|child| {
-                        if let ItemKind::Mod(_) = child.kind {
-                            false
-                        } else {
-                            true
-                        }
-                    })
-                {
-                    self.modules.insert(i.id, ModuleInfo::from_item(i, self.cx));
-                }
-            }
-        });
+        self.dedup_impl_items(krate);
 
-        // Create a new module for standard library headers
-        let stdlib_ident = Ident::from_str("stdlib");
-        match self.modules.values().find(|mod_info| mod_info.orig_ident == stdlib_ident) {
-            Some(info) => self.stdlib_id = info.id,
-            None => {
-                self.stdlib_id = self.st.next_node_id();
-                let unique_ident = self.unique_ident(stdlib_ident);
-                // TODO: this builds a `ModuleInfo` with an empty `headers`,
-                // which is fine because that doesn't ever get checked below
-                // in `find_destination_id` if `is_std() == true`; if that ever
-                // changes, we need to fix it here
-                self.modules.entry(self.stdlib_id)
-                    .or_insert(ModuleInfo::new(stdlib_ident, unique_ident, self.stdlib_id));
-            }
+        self.merge_extern_blocks(krate);
+
+        if self.options.prune_uses {
+            self.remove_unused_uses(krate);
         }
-    }
 
-    /// Pick a destination module for a header item
-    fn find_destination_id(&mut self, declaration: &MovedDecl) -> NodeId {
-        if declaration.parent_header.is_std() {
-            let mod_info = self.modules.get(&self.stdlib_id).unwrap();
-            return mod_info.id;
+        if self.options.emit_prelude {
+            self.emit_prelude(krate);
         }
 
-        // Try to find an existing module to put this item in
-        let dest_module = self.modules.values().find(|dest_module_info| {
-            if dest_module_info.has_main {
-                return false;
-            }
-            // TODO: This is a simple naive heuristic,
-            // and should be improved upon.
-            if !dest_module_info.headers.contains(&declaration.parent_header.path) {
-                return false;
-            }
+        if self.options.rewrite_doclinks {
+            self.rewrite_doc_links(krate);
+        }
 
-            if dest_module_info.items[declaration.namespace].contains(&declaration.ident()) {
-                return false;
-            }
+        if self.options.collapse_single {
+            self.collapse_single_item_modules(krate);
+        }
 
-            let header_ident = declaration.parent_header.ident.as_str();
-            let module_ident = dest_module_info.orig_ident.as_str();
-            if header_ident.len() >= module_ident.len() {
-                let (base, ext) = header_ident.split_at(module_ident.len());
-                base == &*module_ident && (ext.is_empty() || ext == "_h")
-            } else {
-                false
+        if self.options.prefer_relative {
+            self.relativize_paths(krate);
+        }
+
+        if self.options.use_style != UseStyle::Flat {
+            self.restyle_uses(krate);
+        }
+
+        if self.options.kind_order {
+            self.order_items_by_kind(krate);
+        }
+
+        if self.options.dedup_extern_crates {
+            dedup_extern_crates(&mut krate.module.items);
+            FlatMapNodes::visit(krate, |mut item: P<Item>| {
+                if let ItemKind::Mod(m) = &mut item.kind {
+                    dedup_extern_crates(&mut m.items);
+                }
+                smallvec![item]
+            });
+        }
+
+        self.check_module_names(krate);
+
+        if self.options.report_unreachable {
+            self.report_unreachable_items(krate);
+        }
+
+        if self.options.report_type_cycles {
+            self.report_type_cycles();
+        }
+
+        if let Some(path) = &self.options.provenance_out {
+            self.write_provenance(path);
+        }
+
+        if let Some(path) = &self.options.skeleton_out {
+            self.write_output("skeleton", path, crate_skeleton(krate));
+        }
+
+        if let Some(path) = &self.options.plan_out {
+            self.write_plan(path);
+        }
+
+        if let Some(path) = &self.options.map_out {
+            self.write_module_map(path);
+        }
+
+        if let Some(path) = &self.options.path_report {
+            self.write_path_report(path);
+        }
+    }
+
+    /// Destination module of each header declaration, by the `NodeId` the
+    /// declaration had in the original crate (including copies that were
+    /// merged into another one). Only meaningful once `run` has finished.
+    pub fn resolved_mapping(&self) -> HashMap<NodeId, Ident> {
+        let hir_map = self.cx.hir_map();
+        self.path_mapping
+            .iter()
+            .filter_map(|(def_id, replacement)| {
+                let mod_info = self.modules.get(&replacement.parent?)?;
+                let node_id = hir_map.as_local_node_id(*def_id)?;
+                Some((node_id, mod_info.unique_ident))
+            })
+            .collect()
+    }
+
+    /// Return a new unique identifier with the given prefix
+    fn unique_ident(&mut self, ident: Ident) -> Ident {
+        match self.ident_counter.entry(ident) {
+            Entry::Vacant(e) => {
+                e.insert(0);
+                ident
+            }
+            Entry::Occupied(mut e) => {
+                let ev = e.get_mut();
+                let res = format!("{}_{}", ident.as_str(), *ev);
+                *ev += 1;
+                Ident::from_str(&res)
+            }
+        }
+    }
+
+    /// Return the existing module named `ident` (other than one with a
+    /// `main` function), or a new root module with that name
+    fn named_module_id(&mut self, ident: Ident) -> NodeId {
+        let existing = self.modules.values().find(|mod_info| {
+            mod_info.orig_ident == ident && !mod_info.has_main
+        });
+        if let Some(mod_info) = existing {
+            return mod_info.id;
+        }
+        let new_node_id = self.st.next_node_id();
+        let unique_ident = self.unique_ident(ident);
+        self.modules
+            .entry(new_node_id)
+            .or_insert(ModuleInfo::new(ident, unique_ident, new_node_id));
+        new_node_id
+    }
+
+    /// Does the crate still have a non-empty header module for the pass to
+    /// reorganize, taking `include`, `only` and `exclude` into account?
+    fn has_header_modules(&self, krate: &Crate) -> bool {
+        let mut found = false;
+        visit_nodes(krate, |item: &Item| {
+            if let ItemKind::Mod(m) = &item.kind {
+                if !m.items.is_empty()
+                    && has_source_header(&item.attrs, &self.options.header_attr)
+                    && self.options.is_header_selected(item.ident)
+                    && !self.is_excluded(item.id)
+                {
+                    found = true;
+                }
+            }
+        });
+        found
+    }
+
+    /// Is the original AST node `id` inside a top-level module left out by
+    /// the `include` option?
+    fn is_excluded(&self, id: NodeId) -> bool {
+        if self.excluded_mods.is_empty() {
+            return false;
+        }
+        let mut hir_id = self.cx.hir_map().node_to_hir_id(id);
+        loop {
+            if self.excluded_mods.contains(&self.cx.hir_map().hir_to_node_id(hir_id)) {
+                return true;
+            }
+            if hir_id == hir::CRATE_HIR_ID {
+                return false;
+            }
+            hir_id = self.cx.hir_map().get_module_parent_node(hir_id);
+        }
+    }
+
+    /// Iterate through the Crate and enumerate potentential destination
+    /// modules, at any depth. Modules that only contain other modules are
+    /// left out, unless some of those are header modules, which will be
+    /// replaced by their declarations.
+    fn find_destination_modules(&mut self, krate: &Crate) {
+        visit_nodes(krate, |i: &Item| {
+            if let ItemKind::Mod(m) = &i.kind {
+                if !has_source_header(&i.attrs, &self.options.header_attr)
+                    && !self.is_excluded(i.id)
+                    && m.items.iter().any(|child| {
+                        if let ItemKind::Mod(_) = child.kind {
+                            has_source_header(&child.attrs, &self.options.header_attr)
+                        } else {
+                            true
+                        }
+                    })
+                {
+                    match ModuleInfo::from_item(i, self.cx, &self.options.header_attr) {
+                        Ok(info) => {
+                            self.modules.insert(i.id, info);
+                        }
+                        Err(e) => self.report(e),
+                    }
+                }
             }
         });
+
+        // Create a new module for standard library headers, unless the user
+        // named one. `#![no_std]` crates get a neutral name so the module
+        // doesn't suggest a dependency on `std`.
+        let stdlib_ident = if let Some(ident) = self.options.std_module {
+            ident
+        } else if attr::contains_name(&krate.attrs, sym::no_std) {
+            Ident::from_str("system")
+        } else {
+            Ident::from_str("stdlib")
+        };
+        self.stdlib_ident = stdlib_ident;
+        if let Some(info) = self.modules.values().find(|mod_info| mod_info.orig_ident == stdlib_ident) {
+            self.stdlib_id = info.id;
+        }
+    }
+
+    /// Return the module for system header declarations, creating a new one
+    /// the first time it is needed, so that crates without any system
+    /// header declarations to move don't get one
+    fn stdlib_module_id(&mut self) -> NodeId {
+        if self.stdlib_id == DUMMY_NODE_ID {
+            // TODO: this builds a `ModuleInfo` with an empty `headers`,
+            // which is fine because that doesn't ever get checked below in
+            // `find_destination_id` for system headers; if that ever
+            // changes, we need to fix it here
+            let stdlib_ident = self.stdlib_ident;
+            self.stdlib_id = self.named_module_id(stdlib_ident);
+        }
+        self.stdlib_id
+    }
+
+    /// Return the module inside the system header module for the header
+    /// `declaration` came from, for `std_by_header`, creating it the first
+    /// time it is needed
+    fn std_header_module_id(&mut self, declaration: &MovedDecl) -> NodeId {
+        let stdlib_id = self.stdlib_module_id();
+        let mut name = declaration.parent_header.module_name();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name = format!("h_{}", name);
+        }
+        if Ident::from_str(&name).is_reserved() {
+            name.push_str("_h");
+        }
+        self.child_module_id(stdlib_id, Ident::from_str(&name))
+    }
+
+    /// Return the module named `ident` inside the module `parent_id`,
+    /// creating it the first time it is needed
+    fn child_module_id(&mut self, parent_id: NodeId, ident: Ident) -> NodeId {
+        let parent_info = &self.modules[&parent_id];
+        let mut path = parent_info.path.clone();
+        path.push(mk().path_segment(ident.name));
+        let path = segments_to_string(&path);
+        let existing = self.modules.values().find(|mod_info| segments_to_string(&mod_info.path) == path);
+        if let Some(mod_info) = existing {
+            return mod_info.id;
+        }
+        let new_node_id = self.st.next_node_id();
+        let mod_info = ModuleInfo::new_child(ident, new_node_id, parent_info);
+        self.modules.insert(new_node_id, mod_info);
+        new_node_id
+    }
+
+    /// Return the module at `path` (`foo::bar`, from the crate root),
+    /// creating it and any of its parents that don't exist yet. Returns
+    /// `None` if the path has an empty segment.
+    fn module_path_id(&mut self, path: &str) -> Option<NodeId> {
+        let mut names = path.split("::");
+        let first = names.next().filter(|name| !name.is_empty())?;
+        let mut mod_id = self.named_module_id(Ident::from_str(first));
+        for name in names {
+            if name.is_empty() {
+                return None;
+            }
+            mod_id = self.child_module_id(mod_id, Ident::from_str(name));
+        }
+        Some(mod_id)
+    }
+
+    /// Pick a destination module for a header item. The result is always
+    /// the id of an entry of `self.modules`; if no module can be picked,
+    /// the error is reported and `None` returned, so the item is left out
+    /// instead of being attached to a module that doesn't exist.
+    fn find_destination_id(&mut self, declaration: &MovedDecl) -> Option<NodeId> {
+        let (dest, reason) = self.pick_destination(declaration);
+        if self.options.verbose {
+            self.trace_destination(declaration, dest, &reason);
+        }
+        dest
+    }
+
+    /// Body of `find_destination_id`, which also returns why it picked the
+    /// module, for `verbose`
+    fn pick_destination(&mut self, declaration: &MovedDecl) -> (Option<NodeId>, String) {
+        // Pinned names override everything, including `plan_in`
+        let options = self.options;
+        if let Some(dest) = options.pins.get(&declaration.ident()) {
+            return (self.planned_module_id(declaration, dest), "pinned by pin".to_string());
+        }
+
+        // A plan given in `plan_in` overrides all of the heuristics below
+        if let Some(plan) = &options.routes {
+            let item_path = segments_to_string(&self.cx.def_path(declaration.def_id).segments);
+            if let Some(dest) = plan.get(&item_path) {
+                return (self.planned_module_id(declaration, dest), "routed by plan_in".to_string());
+            }
+        }
+        if let Some(map) = &options.map_in {
+            let header_path = header_module_path(&self.cx.def_path(declaration.def_id));
+            if let Some(dest) = map.get(&header_path) {
+                let reason = format!("{} is routed by map_in", header_path);
+                return (self.planned_module_id(declaration, dest), reason);
+            }
+        }
+
+        // Methods go wherever their type went
+        if let Some(dest) = self.impl_destination_id(declaration) {
+            return (Some(dest), "impl follows its self type".to_string());
+        }
+
+        // Grouping rules given in `module_rules` override everything else we
+        // would pick from the header or the declaration's name
+        if let Some(module_ident) = options.rule_module(&declaration.parent_header.path) {
+            let reason = format!("header {} matches a module_rules rule", declaration.parent_header.path);
+            return (Some(self.named_module_id(module_ident)), reason);
+        }
+
+        if self.options.move_std && self.options.is_std_header(&declaration.parent_header.path) {
+            let reason = format!("{} is a system header", declaration.parent_header.path);
+            if self.options.std_by_header {
+                return (Some(self.std_header_module_id(declaration)), reason);
+            }
+            return (Some(self.stdlib_module_id()), reason);
+        }
+
+        // Naming conventions given in `internal_module` and `prefix_map` take
+        // precedence over the header the item came from
+        if let Some(module_ident) = self.options.internal_module(declaration.ident()) {
+            let reason = "name matches internal_patterns".to_string();
+            return (Some(self.named_module_id(module_ident)), reason);
+        }
+        if let Some(module_ident) = self.options.prefix_module(declaration.ident()) {
+            let reason = "name matches a prefix_map prefix".to_string();
+            return (Some(self.named_module_id(module_ident)), reason);
+        }
+
+        // Anonymous types (`C2RustUnnamed`) go wherever the typedef naming
+        // them went
+        if let Some(&dest) = self.typedef_dests.get(&declaration.def_id) {
+            return (Some(dest), "anonymous type follows the typedef naming it".to_string());
+        }
+
+        // Try to find an existing module to put this item in: one that
+        // includes the header and is named after it, so `foo.h` goes into
+        // `foo`. If the header module was marked with several headers, the
+        // first one (in source order) with such a module wins.
+        let candidates: Vec<&ModuleInfo> = declaration.parent_header
+            .paths()
+            .map(|header_path| {
+                let header_name = declaration.parent_header.module_name_of(header_path);
+                self.modules.values().filter(|dest_module_info| {
+                    if dest_module_info.has_main {
+                        return false;
+                    }
+                    if !dest_module_info.headers.contains(header_path) {
+                        return false;
+                    }
+
+                    if dest_module_info.items[declaration.namespace].contains(&declaration.ident()) {
+                        return false;
+                    }
+
+                    normalize_module_name(&dest_module_info.orig_ident.as_str()) == header_name
+                }).collect::<Vec<_>>()
+            })
+            .find(|candidates| !candidates.is_empty())
+            .unwrap_or_default();
+        // A `prefer_module` hint on the declaration breaks ties between
+        // otherwise equally good modules
+        let preferred = declaration.preferred_module();
+        let dest_module = candidates
+            .iter()
+            .find(|info| preferred.map_or(false, |name| info.is_named(&name.as_str())))
+            .or_else(|| candidates.first())
+            .cloned();
+        let reason = if candidates.is_empty() {
+            format!(
+                "no existing module is named after header {}, creating one",
+                declaration.parent_header.path,
+            )
+        } else {
+            let names = candidates
+                .iter()
+                .map(|info| segments_to_string(&info.path))
+                .collect::<Vec<_>>()
+                .join(", ");
+            match preferred {
+                Some(name) => format!("candidates for its header: {}; prefer_module = {}", names, name),
+                None => format!("candidates for its header: {}; taking the first", names),
+            }
+        };
         let dest_module = match dest_module {
             Some(m) => m,
             None => {
@@ -226,7 +1584,96 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
             }
         };
 
-        dest_module.id
+        (Some(dest_module.id), reason)
+    }
+
+    /// Print the module `find_destination_id` picked for `declaration`, and
+    /// why, for `verbose`
+    fn trace_destination(&self, declaration: &MovedDecl, dest: Option<NodeId>, reason: &str) {
+        let item_path = segments_to_string(&self.cx.def_path(declaration.def_id).segments);
+        let dest_path = match dest.and_then(|id| self.modules.get(&id)) {
+            Some(info) => segments_to_string(&info.path),
+            None => "(dropped)".to_string(),
+        };
+        self.cx.session().span_note_without_error(
+            declaration.span(),
+            &format!("{} -> {}: {}", item_path, dest_path, reason),
+        );
+    }
+
+    /// Destination of an `impl` block: the module its self type was moved
+    /// into, or the destination module the type is defined in. Named items
+    /// are routed before unnamed ones, so the type's route is known here.
+    fn impl_destination_id(&self, declaration: &MovedDecl) -> Option<NodeId> {
+        let item = match_or!([&declaration.kind] DeclKind::Item(item) => item; return None);
+        let self_ty = match_or!([&item.kind] ItemKind::Impl(.., self_ty, _) => self_ty; return None);
+        let def_id = self.cx.try_resolve_ty(self_ty)?;
+        let dest = match self.path_mapping.get(&def_id) {
+            Some(replacement) => replacement.parent?,
+            None => {
+                let hir_map = self.cx.hir_map();
+                let hir_id = hir_map.as_local_hir_id(def_id)?;
+                hir_map.hir_to_node_id(hir_map.get_module_parent_node(hir_id))
+            }
+        };
+        if self.modules.contains_key(&dest) {
+            Some(dest)
+        } else {
+            None
+        }
+    }
+
+    /// The anonymous type a type alias declaration names, if any
+    fn alias_target(&self, declaration: &MovedDecl) -> Option<DefId> {
+        let item = match_or!([&declaration.kind] DeclKind::Item(item) => item; return None);
+        let ty = match_or!([&item.kind] ItemKind::TyAlias(ty, _) => ty; return None);
+        let def_id = self.cx.try_resolve_ty(ty)?;
+        let hir_id = self.cx.hir_map().as_local_hir_id(def_id)?;
+        let name = self.cx.hir_map().name(hir_id);
+        if name.as_str().contains("C2RustUnnamed") {
+            Some(def_id)
+        } else {
+            None
+        }
+    }
+
+    /// Find the module `dest` of a `pin` or `plan_in` route. Modules that
+    /// don't exist yet are created, along with any missing parent modules.
+    fn planned_module_id(&mut self, declaration: &MovedDecl, dest: &str) -> Option<NodeId> {
+        if let Some(mod_info) = self.modules.values().find(|info| info.is_named(dest)) {
+            return Some(mod_info.id);
+        }
+        let mod_id = match self.module_path_id(dest.trim_start_matches("crate::")) {
+            Some(id) => id,
+            None => {
+                self.report(ReorganizeError::UnresolvedDestination(
+                    declaration.ident(),
+                    declaration.span(),
+                ));
+                return None;
+            }
+        };
+        if let Some(mod_info) = self.modules.get_mut(&mod_id) {
+            mod_info.headers.insert(declaration.parent_header.path.clone());
+        }
+        Some(mod_id)
+    }
+
+    /// Record the route of `declaration` into module `dest` for `plan_out`,
+    /// `map_out` and `dry_run`
+    fn record_route(&mut self, declaration: &MovedDecl, dest: NodeId) {
+        if self.options.plan_out.is_none() && self.options.map_out.is_none() && !self.options.dry_run {
+            return;
+        }
+        if let Some(mod_info) = self.modules.get(&dest) {
+            let item_path = self.cx.def_path(declaration.def_id);
+            let dest_path = segments_to_string(&mod_info.path);
+            self.module_routes
+                .entry(header_module_path(&item_path))
+                .or_default()
+                .insert(dest_path.clone());
+            self.plan.insert(segments_to_string(&item_path.segments), dest_path);
+        }
     }
 
     /// Drop all header modules, storing their items into the `module_items`
@@ -238,36 +1685,58 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
 
         // Decide which items we should keep in the header. This is currently
         // all functions, static globals, and any uses they reference.
-        fn keep_items(module: &Mod) -> HashSet<NodeId> {
+        // Functions whose signature mentions a type defined in the header
+        // (e.g. `fn make_foo() -> Foo`) are moved along with that type
+        // instead, so that a type and its API end up in the same module.
+        fn keep_items(module: &Mod, cx: &RefactorCtxt) -> HashSet<NodeId> {
+            let header_types: HashSet<DefId> = module.items
+                .iter()
+                .filter(|item| match item.kind {
+                    ItemKind::Struct(..) | ItemKind::Union(..) | ItemKind::Enum(..)
+                        | ItemKind::TyAlias(..) => true,
+                    _ => false,
+                })
+                .map(|item| cx.node_def_id(item.id))
+                .collect();
+
             let mut keep_items = HashSet::new();
             let mut used_idents = HashSet::new();
             for item in &module.items {
                 match &item.kind {
-                    ItemKind::Fn(_, _, body) => {
-                        keep_items.insert(item.id);
-                        visit_nodes(&**body, |path: &Path| {
-                            if path.segments.len() == 1 {
-                                used_idents.insert(path.segments[0].ident);
-                            }
-                        });
-                    }
-
-                    ItemKind::Static(_, _, init) if !is_exported(item) => {
-                        keep_items.insert(item.id);
-                        visit_nodes(&**init, |path: &Path| {
-                            if path.segments.len() == 1 {
-                                used_idents.insert(path.segments[0].ident);
-                            }
-                        });
+                    ItemKind::Fn(sig, ..) => {
+                        let mut finder = TypeRefFinder {
+                            cx,
+                            types: &header_types,
+                            found: false,
+                        };
+                        visit::walk_fn_decl(&mut finder, &sig.decl);
+                        if finder.found {
+                            continue;
+                        }
                     }
-
-                    _ => {}
+                    ItemKind::Static(..) if !is_exported(item) => {}
+                    _ => continue,
                 }
+                keep_items.insert(item.id);
+                // Visit the whole item rather than just the body or
+                // initializer, so that paths in signatures and types
+                // (including array lengths such as `[u8; SIZE]`) keep their
+                // imports alive too.
+                visit_nodes(&**item, |path: &Path| {
+                    if path.segments.len() == 1 {
+                        used_idents.insert(path.segments[0].ident);
+                    }
+                });
             }
 
-            // This assume the complex uses have been split apart already
+            // This assume the complex uses have been split apart already.
+            // Glob imports can't be matched by name, so they are dropped
+            // along with the header.
             for item in &module.items {
                 if let ItemKind::Use(tree) = &item.kind {
+                    if let UseTreeKind::Glob = tree.kind {
+                        continue;
+                    }
                     if used_idents.contains(&tree.ident()) {
                         keep_items.insert(item.id);
                         continue;
@@ -278,9 +1747,47 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
         }
 
         let mut declarations = HeaderDeclarations::new(self.cx);
+        declarations.merge_foreign_by_name = self.options.merge_foreign_by_name;
+        let mut self_reexports = vec![];
         FlatMapNodes::visit(krate, |mut item: P<Item>| {
-            if let Some((path, include_line)) = parse_source_header(&item.attrs) {
-                let header_item = item.clone();
+            let headers = match parse_source_headers(&item.attrs, &self.options.header_attr) {
+                Ok(headers) => headers,
+                Err(e) => {
+                    self.report(e);
+                    vec![]
+                }
+            };
+            if let Some((path, include_line)) = headers.first().cloned() {
+                let mut other_paths: Vec<String> = vec![];
+                for (other_path, _) in &headers[1..] {
+                    if *other_path != path && !other_paths.contains(other_path) {
+                        other_paths.push(other_path.clone());
+                    }
+                }
+                if !other_paths.is_empty() {
+                    let msg = format!(
+                        "header module {} comes from several headers ({}, {})",
+                        item.ident,
+                        path,
+                        other_paths.join(", "),
+                    );
+                    self.cx.session()
+                        .struct_span_warn(item.span, &msg)
+                        .note(&format!(
+                            "its declarations go to the module of the first of them that has one, or to a new one for {}",
+                            path,
+                        ))
+                        .emit();
+                }
+                let placement = self.options.placement_rules().classify(&item.attrs, item.ident);
+                if !self.options.move_std && matches!([placement] Placement::System { .. }) {
+                    // Leave system headers alone
+                    return smallvec![item];
+                }
+                if self.is_excluded(item.id) || !self.options.is_header_selected(item.ident) {
+                    return smallvec![item];
+                }
+                let (item_ident, item_id) = (item.ident, item.id);
                 if let ItemKind::Mod(module) = &mut item.kind {
                     // Split complex uses before iterating over the items
                     module.items.flat_map_in_place(|item| {
@@ -290,14 +1797,72 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                         }
                     });
 
-                    let needed_items = keep_items(&module);
+                    let needed_items = keep_items(&module, self.cx);
+
+                    // Header modules without a usable name get one derived
+                    // from their contents, so that each header still gets a
+                    // distinct, deterministic destination.
+                    let header_ident = if item_ident.as_str().is_empty() {
+                        derive_module_ident(&module.items)
+                    } else {
+                        item_ident
+                    };
+
+                    // Public re-exports through items of this header that we
+                    // are about to move (`pub use self::Foo::Bar;`) would
+                    // point nowhere once the header is gone, so take them out
+                    // here and recreate them after paths are updated.
+                    let moved_defs: HashMap<Ident, DefId> = module.items
+                        .iter()
+                        .filter(|item| match item.kind {
+                            ItemKind::Use(..) | ItemKind::ForeignMod(..) | ItemKind::Impl(..) => false,
+                            _ => !needed_items.contains(&item.id),
+                        })
+                        .map(|item| (item.ident, self.cx.node_def_id(item.id)))
+                        .collect();
+                    let hir_id = self.cx.hir_map().node_to_hir_id(item_id);
+                    let parent = self.cx.hir_map().hir_to_node_id(
+                        self.cx.hir_map().get_module_parent_node(hir_id)
+                    );
+                    module.items.retain(|item| {
+                        let tree = match_or!([&item.kind] ItemKind::Use(tree) => tree; return true);
+                        let is_pub = match item.vis.node {
+                            VisibilityKind::Public => true,
+                            _ => false,
+                        };
+                        if !is_pub
+                            || tree.prefix.segments.len() < 2
+                            || tree.prefix.segments[0].ident.name != kw::SelfLower
+                        {
+                            return true;
+                        }
+                        match moved_defs.get(&tree.prefix.segments[1].ident) {
+                            Some(&def_id) => {
+                                self_reexports.push(SelfReexport {
+                                    parent,
+                                    def_id,
+                                    vis: item.vis.clone(),
+                                    tree: tree.clone(),
+                                });
+                                false
+                            }
+                            None => true,
+                        }
+                    });
 
                     module.items.retain(|item| {
                         if needed_items.contains(&item.id) {
                             return true;
                         }
 
-                        if let ItemKind::Use(_) = &item.kind {
+                        if let ItemKind::Use(tree) = &item.kind {
+                            // Paths to the items glob imports brought into
+                            // the header become absolute, so they aren't
+                            // needed anymore.
+                            if let UseTreeKind::Glob = tree.kind {
+                                return false;
+                            }
+
                             // Don't add unused uses of non-exported parent
                             // items. These won't get merged with anything and
                             // will violate visibility if we move them.
@@ -311,17 +1876,19 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                             }
                         }
 
-                        let header_info = HeaderInfo::new(
-                            header_item.ident,
+                        let mut header_info = HeaderInfo::new(
+                            header_ident,
                             path.clone(),
                             include_line,
                         );
+                        header_info.other_paths = other_paths.clone();
                         let inserted = declarations.insert_item(item.clone(), header_info);
                         // Keep the item if we are not collapsing it
                         !inserted
                     });
 
-                    if module.items.is_empty() {
+                    let keep_empty = self.options.keep_empty_headers || self.options.emit_shims;
+                    if module.items.is_empty() && !keep_empty {
                         // Delete the header module
                         smallvec![]
                     } else {
@@ -330,12 +1897,14 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                         smallvec![item]
                     }
                 } else {
-                    panic!("Unexpected Item kind with header_src attribute");
+                    self.report(ReorganizeError::UnexpectedHeaderItem(item.ident, item.span));
+                    smallvec![item]
                 }
             } else {
                 smallvec![item]
             }
         });
+        self.self_reexports = self_reexports;
         declarations
     }
 
@@ -360,7 +1929,7 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
 
             let decl_ids = declarations.remove_matching_defs(ns, item.ident, |decl| {
                 match decl {
-                    DeclKind::Item(decl) => self.cx.compatible_types(&decl, item),
+                    DeclKind::Item(decl) => compatible_items(self.cx, &decl, item),
                     DeclKind::ForeignItem(foreign, _) => foreign_equiv(&foreign, item),
                 }
             });
@@ -376,7 +1945,7 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                             decl_id,
                             Replacement {
                                 path: dest_path.clone(),
-                                parent: mod_id,
+                                parent: Some(mod_id),
                                 def: Some(def_id),
                             }
                         );
@@ -384,6 +1953,10 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
             }
         });
 
+        // Other crates' exports can only be compared with typeck results
+        if !self.cx.has_ty_ctxt() {
+            return;
+        }
         for crate_def in &self.cx.crate_defs() {
             if crate_def.is_local() {
                 continue;
@@ -506,7 +2079,7 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                         decl_id,
                         Replacement {
                             path: path.clone(),
-                            parent: DUMMY_NODE_ID,
+                            parent: None,
                             def: Some(def_id),
                         }
                     );
@@ -544,18 +2117,105 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
     /// Choose destinations for items remaining in `declarations`, add these
     /// items to their destination module, and create any new modules.
     fn move_items(&mut self, declarations: HeaderDeclarations, krate: &mut Crate) {
-        let HeaderDeclarations {idents, unnamed_items, matching_defs, ..} = declarations;
+        let HeaderDeclarations {idents, unnamed_items, matching_defs, derives, ..} = declarations;
+        let mut summary = Summary {
+            merged: matching_defs.len(),
+            ..Summary::default()
+        };
+
+        // Copies of a type merged from different headers may derive
+        // different traits. The surviving copy derives the union of them, in
+        // sorted order so that the output doesn't depend on which copy won.
+        let mut merged_derives: HashMap<DefId, BTreeSet<String>> = HashMap::new();
+        for (def_id, names) in &derives {
+            let mut target = def_id;
+            while let Some(other) = matching_defs.get(target) {
+                target = other;
+            }
+            merged_derives.entry(*target).or_default().extend(names.iter().cloned());
+        }
+        let union_derives = |item: &mut MovedDecl| {
+            if let Some(names) = merged_derives.get(&item.def_id) {
+                if derives.get(&item.def_id) != Some(names) {
+                    let attr = mk().call_attr("derive", names.iter().collect()).into_attrs();
+                    item.kind.visit_attrs(|attrs| attrs.extend(attr));
+                }
+            }
+        };
 
         // TODO: this probably needs to be PerNS
         let mut module_items: IndexMap<NodeId, Vec<MovedDecl>> = IndexMap::new();
+        // Header path of the first declaration of each name moved into each
+        // module, to detect different declarations that would collide there
+        let mut placed: HashMap<(NodeId, Namespace, Ident), String> = HashMap::new();
         // Move named items into module_items
         idents.map(|idents| {
             for (ident, items) in idents.into_iter() {
-                for item in items {
-                    let dest_module_id = self.find_destination_id(&item);
+                for mut item in items {
+                    union_derives(&mut item);
+                    let dest_module_id = match_or!([self.find_destination_id(&item)] Some(id) => id;
+                                                   { summary.dropped += 1; continue });
+                    self.record_route(&item, dest_module_id);
+                    if let Some(mut target) = self.alias_target(&item) {
+                        while let Some(other) = matching_defs.get(&target) {
+                            target = *other;
+                        }
+                        self.typedef_dests.entry(target).or_insert(dest_module_id);
+                    }
+
+                    // Declarations that are still apart after matching are
+                    // different, so two of them with the same name can't
+                    // share a module
+                    let mut ident = ident;
+                    if let (Some(first_header), Some(dest_module_info)) = (
+                        placed.get(&(dest_module_id, item.namespace, ident)),
+                        self.modules.get(&dest_module_id),
+                    ) {
+                        match self.options.on_conflict {
+                            OnConflict::Error => {
+                                self.report(ReorganizeError::ConflictingDeclarations(
+                                    ident,
+                                    dest_module_info.unique_ident,
+                                    first_header.clone(),
+                                    item.parent_header.path.clone(),
+                                    item.span(),
+                                ));
+                                summary.dropped += 1;
+                                continue;
+                            }
+                            OnConflict::Rename => {
+                                let base = format!("{}_{}", ident, item.parent_header.module_name());
+                                let namespace = item.namespace;
+                                let new_ident = (0..)
+                                    .map(|n| match n {
+                                        0 => Ident::from_str(&base),
+                                        n => Ident::from_str(&format!("{}_{}", base, n)),
+                                    })
+                                    .find(|name| {
+                                        !placed.contains_key(&(dest_module_id, namespace, *name))
+                                            && !dest_module_info.items[namespace].contains(name)
+                                    })
+                                    .unwrap();
+                                item.rename(new_ident);
+                                ident = new_ident;
+                            }
+                        }
+                    }
+                    placed.insert(
+                        (dest_module_id, item.namespace, ident),
+                        item.parent_header.path.clone(),
+                    );
 
-                    let dest_module_info = self.modules.get_mut(&dest_module_id).unwrap();
+                    let dest_module_info = match self.modules.get_mut(&dest_module_id) {
+                        Some(info) => info,
+                        None => {
+                            self.report(ReorganizeError::UnresolvedDestination(ident, item.span()));
+                            summary.dropped += 1;
+                            continue;
+                        }
+                    };
                     dest_module_info.items[item.namespace].insert(ident);
+                    item.rebase_visibility(self.cx, &dest_module_info.path);
                     let mut path_segments = dest_module_info.path.clone();
                     path_segments.push(mk().path_segment(ident.name));
                     let dest_path = mk().path(path_segments);
@@ -563,24 +2223,41 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                         item.def_id,
                         Replacement {
                             path: dest_path,
-                            parent: dest_module_id,
+                            parent: Some(dest_module_id),
                             def: None, // hasn't changed
                         },
                     );
 
+                    self.provenance
+                        .entry(dest_module_id)
+                        .or_default()
+                        .insert(item.parent_header.path.clone());
+
                     // Move the item to the `module_items` mapping.
                     module_items.entry(dest_module_id).or_default().push(item);
+                    summary.moved += 1;
                 }
             }
         });
 
         // Move unnamed items into module_items
         unnamed_items.map(|items| {
-            for item in items.into_iter() {
+            for mut item in items.into_iter() {
+                union_derives(&mut item);
                 let ident = item.ident();
-                let parent = self.find_destination_id(&item);
-
-                let dest_module_info = &self.modules[&parent];
+                let parent = match_or!([self.find_destination_id(&item)] Some(id) => id;
+                                       { summary.dropped += 1; continue });
+                self.record_route(&item, parent);
+
+                let dest_module_info = match self.modules.get(&parent) {
+                    Some(info) => info,
+                    None => {
+                        self.report(ReorganizeError::UnresolvedDestination(ident, item.span()));
+                        summary.dropped += 1;
+                        continue;
+                    }
+                };
+                item.rebase_visibility(self.cx, &dest_module_info.path);
                 let mut path_segments = dest_module_info.path.clone();
                 path_segments.push(mk().path_segment(ident.name));
                 let path = mk().path(path_segments);
@@ -588,13 +2265,19 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                     item.def_id,
                     Replacement {
                         path,
-                        parent,
+                        parent: Some(parent),
                         def: None,
                     },
                 );
 
+                self.provenance
+                    .entry(parent)
+                    .or_default()
+                    .insert(item.parent_header.path.clone());
+
                 // Move the item to the `module_items` mapping.
                 module_items.entry(parent).or_default().push(item);
+                summary.moved += 1;
             }
         });
 
@@ -609,34 +2292,49 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
         }
 
         // Convert the module_items vector into a HeaderDeclarations struct for
-        // each module
+        // each module. A declaration routed more than once must still only
+        // be emitted once.
         let mut module_items: IndexMap<NodeId, HeaderDeclarations> = module_items
             .into_iter()
-            .map(|(module_id, items)| {
+            .map(|(module_id, mut items)| {
+                let mut seen = HashSet::new();
+                items.retain(|item| seen.insert(item.def_id));
                 let mut decls = HeaderDeclarations::new(self.cx);
+                decls.merge_foreign_by_name = self.options.merge_foreign_by_name;
                 decls.extend(items);
                 (module_id, decls)
             }).collect();
 
+        // Number of declarations in `items`, counting each item of an extern
+        // block separately
+        fn count_decls(items: &[P<Item>]) -> usize {
+            items
+                .iter()
+                .map(|item| match &item.kind {
+                    ItemKind::ForeignMod(fm) => fm.items.len(),
+                    _ => 1,
+                })
+                .sum()
+        }
+
         // We should have handled merging of idents in match_defs
         // above. Therefore this new decl won't conflict with a decl in the
         // destination module, although it may need to replace an import or
         // foreign item.
         FlatMapNodes::visit(krate, |mut item: P<Item>| {
             let id = item.id;
+            let mut moved_macros = false;
             if let ItemKind::Mod(module) = &mut item.kind {
                 if let Some(mut declarations) = module_items.remove(&id) {
                     let module_info = &self.modules[&id];
+                    let old_count = count_decls(&module.items);
 
                     // Remove extern declarations or imports of new items we are
                     // injecting
                     module.items
                         .drain_filter(|item| {
                             if let ItemKind::ForeignMod(m) = &mut item.kind {
-                                let abi = m
-                                    .abi
-                                    .and_then(|abi| abi::lookup(&abi.symbol.as_str()))
-                                    .unwrap_or(Abi::Rust);
+                                let abi = foreign_mod_abi(m);
                                 m.items.retain(|item| {
                                     match declarations.find_foreign_item(item, abi) {
                                         ContainsDecl::NotContained => true,
@@ -660,13 +2358,18 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                                 }
                             }
                         });
+                    summary.replaced += old_count - count_decls(&module.items);
 
-                    let new_items: Vec<P<Item>> = declarations.into_items(self.st, module_info);
+                    let new_items: Vec<P<Item>> = declarations.into_items(self.st, module_info, self.options);
+                    moved_macros = new_items.iter().any(|item| matches!([item.kind] ItemKind::MacroDef(..)));
                     let old_items = mem::replace(&mut module.items, new_items);
                     module.items.extend(old_items);
                 }
 
             }
+            if moved_macros {
+                add_macro_use(&mut item.attrs);
+            }
             smallvec![item]
         });
 
@@ -676,7 +2379,8 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
         let inline = self.cx.is_executable();
         for mod_info in self.modules.values() {
             if let Some(declarations) = module_items.remove(&mod_info.id) {
-                let new_items = declarations.into_items(self.st, mod_info);
+                let new_items = declarations.into_items(self.st, mod_info, self.options);
+                let moved_macros = new_items.iter().any(|item| matches!([item.kind] ItemKind::MacroDef(..)));
                 if !new_items.is_empty() {
                     #[inline]
                     fn match_mod_item(item: &mut P<Item>, ident: Ident) -> Option<&mut Mod> {
@@ -690,52 +2394,290 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                         }
                     }
 
-                    if let Some(existing_mod) = krate
-                        .module
-                        .items
+                    let parent_items = self.parent_items(krate, mod_info, inline);
+                    if let Some(existing_mod) = parent_items
                         .iter_mut()
                         .find_map(|item| match_mod_item(item, mod_info.unique_ident))
                     {
-                        // FIXME: we should also check if items overlap
-                        existing_mod.items.extend(new_items.into_iter());
+                        let existing_names: HashSet<(Ident, Option<Namespace>)> = existing_mod
+                            .items
+                            .iter()
+                            .map(|existing| (existing.ident, self.cx.item_namespace(existing)))
+                            .collect();
+                        for mut new_item in new_items {
+                            // Extern blocks have no name to collide on, so
+                            // check their items against the foreign items
+                            // currently in the module instead.
+                            if let ItemKind::ForeignMod(fm) = &mut new_item.kind {
+                                let existing_items = &existing_mod.items;
+                                fm.items.retain(|fi| {
+                                    let existing = match_or!([find_foreign_item(existing_items, fi)]
+                                                             Some(existing) => existing; return true);
+                                    // Declarations of different symbols are never
+                                    // the same, whatever their signatures
+                                    let same_symbol = foreign_symbol(existing) == foreign_symbol(fi);
+                                    let equivalent = same_symbol && match (&existing.kind, &fi.kind) {
+                                        (ForeignItemKind::Fn(decl1, _), ForeignItemKind::Fn(decl2, _)) => {
+                                            compatible_prototypes(self.cx, decl1, decl2)
+                                        }
+                                        _ => existing.ast_equiv(fi),
+                                    };
+                                    summary.moved -= 1;
+                                    if equivalent {
+                                        summary.merged += 1;
+                                    } else if same_symbol && self.options.merge_foreign_by_name {
+                                        warn_signature_mismatch(self.cx, existing, fi);
+                                        summary.merged += 1;
+                                    } else {
+                                        self.report(ReorganizeError::NameCollision(
+                                            fi.ident,
+                                            mod_info.unique_ident,
+                                            fi.span,
+                                        ));
+                                        summary.dropped += 1;
+                                    }
+                                    false
+                                });
+                                if fm.items.is_empty() {
+                                    continue;
+                                }
+                            }
+                            let collides = !new_item.ident.as_str().is_empty()
+                                && existing_names.contains(&(
+                                    new_item.ident,
+                                    self.cx.item_namespace(&new_item),
+                                ));
+                            if collides {
+                                self.report(ReorganizeError::NameCollision(
+                                    new_item.ident,
+                                    mod_info.unique_ident,
+                                    new_item.span,
+                                ));
+                                summary.moved -= 1;
+                                summary.dropped += 1;
+                            } else if let ItemKind::Use(..) = new_item.kind {
+                                insert_uses(&mut existing_mod.items, Some(new_item));
+                            } else {
+                                existing_mod.items.push(new_item);
+                            }
+                        }
                     } else {
-                        let mut new_mod = mk().mod_(new_items);
-                        new_mod.inline = inline;
-                        let new_mod_item = mk()
-                            .pub_()
-                            .id(mod_info.id)
-                            .mod_item(mod_info.unique_ident, new_mod);
-
-                        krate.module.items.insert(0, new_mod_item);
+                        parent_items.insert(0, new_module_item(mod_info, new_items, inline));
+                    }
+                }
+                if moved_macros {
+                    let parent_items = self.parent_items(krate, mod_info, inline);
+                    let mod_item = parent_items.iter_mut().find(|item| {
+                        item.ident == mod_info.unique_ident && matches!([item.kind] ItemKind::Mod(..))
+                    });
+                    if let Some(mod_item) = mod_item {
+                        add_macro_use(&mut mod_item.attrs);
                     }
                 }
             }
         }
+        self.summary = summary;
 
-        // Remove src_loc attributes
-        FlatMapNodes::visit(krate, |mut item: P<Item>| {
-            item.attrs
-                .retain(|attr| !is_c2rust_attr(attr, "src_loc"));
-            smallvec![item]
-        });
-        FlatMapNodes::visit(krate, |mut item: ForeignItem| {
-            item.attrs
-                .retain(|attr| !is_c2rust_attr(attr, "src_loc"));
-            smallvec![item]
-        });
+        // Modules left out by `include` keep their attributes, so that they
+        // can still be reorganized later.
+        for root_item in &mut krate.module.items {
+            if self.excluded_mods.contains(&root_item.id) {
+                continue;
+            }
 
-        // Remove header_src attributes
-        FlatMapNodes::visit(krate, |mut item: P<Item>| {
-            item.attrs
-                .retain(|attr| !is_c2rust_attr(attr, "header_src"));
-            smallvec![item]
+            // Remove src_loc and prefer_module attributes
+            FlatMapNodes::visit(root_item, |mut item: P<Item>| {
+                item.attrs.retain(|attr| {
+                    !is_c2rust_attr(attr, "src_loc") && !is_c2rust_attr(attr, "prefer_module")
+                });
+                smallvec![item]
+            });
+            FlatMapNodes::visit(root_item, |mut item: ForeignItem| {
+                item.attrs.retain(|attr| {
+                    !is_c2rust_attr(attr, "src_loc") && !is_c2rust_attr(attr, "prefer_module")
+                });
+                smallvec![item]
+            });
+
+            // Remove header_src attributes
+            FlatMapNodes::visit(root_item, |mut item: P<Item>| {
+                item.attrs
+                    .retain(|attr| !is_header_attr(attr, &self.options.header_attr));
+                smallvec![item]
+            });
+        }
+    }
+
+    /// The items of the module a new module goes into: the crate root, or
+    /// the module's parent, which is created (empty) if it doesn't exist yet
+    fn parent_items<'k>(
+        &self,
+        krate: &'k mut Crate,
+        mod_info: &ModuleInfo,
+        inline: bool,
+    ) -> &'k mut Vec<P<Item>> {
+        let parent_id = match_or!([mod_info.parent] Some(id) => id; return &mut krate.module.items);
+        if find_module_items(&mut krate.module.items, parent_id).is_none() {
+            let parent_info = &self.modules[&parent_id];
+            let parent_item = new_module_item(parent_info, vec![], inline);
+            self.parent_items(krate, parent_info, inline).insert(0, parent_item);
+        }
+        find_module_items(&mut krate.module.items, parent_id).unwrap()
+    }
+
+    /// Remove public types and constants that are defined in more than one
+    /// destination module, keeping the first definition in crate order.
+    /// Matching declarations were already merged before they were moved, so
+    /// this catches copies that were written in different modules to begin
+    /// with. Paths to the removed copies are rewritten to the kept one by
+    /// `update_paths`.
+    fn dedup_across_modules(&mut self, krate: &mut Crate) {
+        // First definition of each name, and the module it is in
+        let mut canonical: HashMap<(Namespace, Ident), (NodeId, P<Item>)> = HashMap::new();
+        // Replacement for each removed item, by NodeId
+        let mut duplicates: HashMap<NodeId, Replacement> = HashMap::new();
+        // Modules and names of the removed items
+        let mut removed = vec![];
+        visit_nodes(&*krate, |module_item: &Item| {
+            let module = match_or!([&module_item.kind] ItemKind::Mod(m) => m; return);
+            if !self.modules.contains_key(&module_item.id) {
+                return;
+            }
+            for item in &module.items {
+                // Other modules can't refer to a private copy
+                if let VisibilityKind::Inherited = item.vis.node {
+                    continue;
+                }
+                let namespace = match item.kind {
+                    ItemKind::Struct(..) | ItemKind::Union(..) | ItemKind::Enum(..)
+                        | ItemKind::TyAlias(..) => Namespace::TypeNS,
+                    ItemKind::Const(..) if item.ident.name != kw::Underscore => Namespace::ValueNS,
+                    _ => continue,
+                };
+                match canonical.get(&(namespace, item.ident)) {
+                    None => {
+                        canonical.insert((namespace, item.ident), (module_item.id, item.clone()));
+                    }
+                    Some((mod_id, kept))
+                        if *mod_id != module_item.id && compatible_items(self.cx, kept, item) =>
+                    {
+                        let def_id = self.cx.node_def_id(kept.id);
+                        duplicates.insert(
+                            item.id,
+                            Replacement {
+                                path: self.current_path(def_id),
+                                parent: Some(*mod_id),
+                                def: Some(def_id),
+                            },
+                        );
+                        removed.push((module_item.id, namespace, item.ident));
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        if duplicates.is_empty() {
+            return;
+        }
+
+        for (id, replacement) in &duplicates {
+            let def_id = self.cx.node_def_id(*id);
+            let old_path = self.current_path(def_id);
+            // Declarations merged into the removed copy follow it
+            for mapping in self.path_mapping.values_mut() {
+                if mapping.path.ast_equiv(&old_path) {
+                    *mapping = replacement.clone();
+                }
+            }
+            self.path_mapping.insert(def_id, replacement.clone());
+        }
+
+        FlatMapNodes::visit(krate, |item: P<Item>| {
+            if duplicates.contains_key(&item.id) {
+                return smallvec![];
+            }
+            smallvec![item]
+        });
+        for (mod_id, namespace, ident) in removed {
+            if let Some(info) = self.modules.get_mut(&mod_id) {
+                info.items[namespace].remove(&ident);
+            }
+        }
+    }
+
+    /// Path to the definition `def_id` once it has been moved
+    fn current_path(&self, def_id: DefId) -> Path {
+        match self.path_mapping.get(&def_id) {
+            Some(replacement) => replacement.path.clone(),
+            None => self.cx.def_path(def_id),
+        }
+    }
+
+    /// Replace glob imports of removed header modules (`use self::foo_h::*;`)
+    /// with glob imports of the modules their declarations were moved to,
+    /// other than the importing module itself.
+    fn rewrite_header_globs(&self, krate: &mut Crate) {
+        let hir_map = self.cx.hir_map();
+
+        // Destination modules of the declarations of each header module, by
+        // path so that the new imports come out in a stable order
+        let mut header_dests: HashMap<NodeId, BTreeMap<String, Path>> = HashMap::new();
+        for (def_id, replacement) in &self.path_mapping {
+            let parent = match_or!([replacement.parent] Some(parent) => parent; continue);
+            let mod_info = match_or!([self.modules.get(&parent)] Some(info) => info; continue);
+            let hir_id = match_or!([hir_map.as_local_hir_id(*def_id)] Some(hir_id) => hir_id; continue);
+            let header_id = hir_map.hir_to_node_id(hir_map.get_module_parent_node(hir_id));
+            header_dests
+                .entry(header_id)
+                .or_default()
+                .insert(segments_to_string(&mod_info.path), mk().path(mod_info.path.clone()));
+        }
+        if header_dests.is_empty() {
+            return;
+        }
+
+        let rewrite_globs = |items: &mut Vec<P<Item>>, mod_path: &str| {
+            items.flat_map_in_place(|item| -> SmallVec<[P<Item>; 1]> {
+                match &item.kind {
+                    ItemKind::Use(tree) if matches!([tree.kind] UseTreeKind::Glob) => {}
+                    _ => return smallvec![item],
+                }
+                let target = self.cx
+                    .try_resolve_use_id(item.id)
+                    .and_then(|path| path.res.opt_def_id())
+                    .and_then(|def_id| hir_map.as_local_hir_id(def_id))
+                    .map(|hir_id| hir_map.hir_to_node_id(hir_id));
+                let dests = match_or!([target.and_then(|id| header_dests.get(&id))]
+                                      Some(dests) => dests; return smallvec![item]);
+                dests
+                    .iter()
+                    .filter(|(path, _)| path.as_str() != mod_path)
+                    .map(|(_, path)| mk().vis(item.vis.clone()).use_glob_item(path.clone()))
+                    .collect()
+            });
+        };
+
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            let id = item.id;
+            if let ItemKind::Mod(m) = &mut item.kind {
+                let mod_path = match self.modules.get(&id) {
+                    Some(info) => Some(segments_to_string(&info.path)),
+                    None => hir_map
+                        .opt_local_def_id_from_node_id(id)
+                        .map(|def_id| segments_to_string(&self.cx.def_path(def_id).segments)),
+                };
+                if let Some(mod_path) = mod_path {
+                    rewrite_globs(&mut m.items, &mod_path);
+                }
+            }
+            smallvec![item]
         });
+        rewrite_globs(&mut krate.module.items, "crate");
     }
 
     /// Update paths to moved items and remove redundant imports.
     fn update_paths(&self, krate: &mut Crate) {
-        let tcx = self.cx.ty_ctxt();
-
         // Maps NodeId of an AST element with an updated path to the NodeId of
         // the module its target is now located in and the DefId of the original
         // target.
@@ -757,6 +2699,10 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                     let inserted = remapped_paths.insert(id, (replacement.parent, def_id)).is_none();
                     assert!(inserted);
                     debug!("  -> {:?}", replacement.path);
+                    if replacement.path.ast_equiv(&path) {
+                        // Keep the path as written if it is already correct
+                        return (qself, path);
+                    }
                     return (qself, replacement.path.clone());
                 } else if is_relative_path(&path) {
                     // Canonicalize a new path from the crate root. Will rewrite
@@ -765,7 +2711,7 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                     if let Some(hir_id) = self.cx.hir_map().as_local_hir_id(def_id) {
                         let mod_hir_id = self.cx.hir_map().get_module_parent_node(hir_id);
                         let mod_id = self.cx.hir_map().hir_to_node_id(mod_hir_id);
-                        let inserted = remapped_paths.insert(id, (mod_id, def_id)).is_none();
+                        let inserted = remapped_paths.insert(id, (Some(mod_id), def_id)).is_none();
                         assert!(inserted);
                     }
                     return self.cx.def_qpath(def_id);
@@ -774,73 +2720,33 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
             (qself, path)
         });
 
-        // Fix casts of the following format:
-        // &global as *const [T; 0]
-        // where global is now [T; n] where n > 0, not [T; 0]
-        MutVisitNodes::visit(krate, |e: &mut P<Expr>| {
-            let cast_id = e.id;
-            let (val, ty) = match_or!([&mut e.kind] ExprKind::Cast(val, ty) => (val, ty); return);
-            let val = match_or!([&val.kind] ExprKind::AddrOf(_, _mutbl, val) => val; return);
-            let old_def_id = match_or!([self.cx.try_resolve_expr(&val)] Some(id) => id; return);
-            let replacement = match_or!([self.path_mapping.get(&old_def_id)] Some(x) => x; return);
-            let new_def_id = match_or!([replacement.def] Some(id) => id; return);
-            let val_ty = self.cx.def_type(new_def_id);
-            let val_len = match_or!([val_ty.kind] ty::TyKind::Array(_ty, n) => n; return);
-            let cast_ty = match_or!([self.cx.opt_node_type(cast_id)] Some(ty) => ty; return);
-            let cast_ty = match_or!([cast_ty.kind] ty::TyKind::RawPtr(ty) => ty; return);
-            let cast_ty = cast_ty.ty;
-            let cast_len = match_or!([cast_ty.kind] ty::TyKind::Array(_ty, n) => n; return);
-            if let Some(0) = cast_len.try_eval_usize(tcx, ParamEnv::empty()) {
-                if let Some(val_len) = val_len.try_eval_usize(tcx, ParamEnv::empty()) {
-                    let ty = match_or!([&mut ty.kind] TyKind::Ptr(ty) => ty; return);
-                    let cast_len = match_or!([&mut ty.ty.kind] TyKind::Array(_ty, n) => n; return);
-                    let lit = mk().lit_expr(mk().int_lit(val_len as u128, LitIntType::Unsuffixed));
-                    *cast_len = mk().anon_const(lit);
-                }
-            }
-        });
-
-        // Mapping from old DefId to new DefId
-        let replacement_map = self.path_mapping.iter().filter_map(|(old_def, replacement)| {
-            match self.cx.hir_map().get_if_local(*old_def) {
-                Some(Node::ForeignItem(_)) => {}
-                Some(Node::Item(item)) => match item.kind {
-                    hir::ItemKind::Static(..) | hir::ItemKind::Const(..) | hir::ItemKind::Fn(..)
-                    | hir::ItemKind::Union(..) | hir::ItemKind::Enum(..)
-                    | hir::ItemKind::TyAlias(..) => {}
-                    _ => return None,
-                },
-                _ => return None,
-            }
-            replacement.def.map(|new_def| (*old_def, new_def))
-        }).collect();
-        // Mapping from user path expr NodeId to old DefId
-        let path_ids = remapped_paths.iter().map(|(user, (_parent, def))| (*user, *def)).collect();
-        // Mapping from old DefId to new path
-        let new_paths = self.path_mapping.iter().map(|(old_did, replacement)| {
-            (*old_did, (None, replacement.path.clone()))
-        }).collect::<HashMap<_, _>>();
-
-        // Cast updated values back to their original type if needed
-        externs::fix_users(krate, &replacement_map, &path_ids, &new_paths, self.cx);
+        // The fixups below compare the types of the old and new items, which
+        // are only known at phase 3
+        if self.cx.has_ty_ctxt() {
+            self.fix_replaced_users(krate, &remapped_paths);
+        }
 
         // Remove use statements that now refer to their self module.
         FlatMapNodes::visit(krate, |mut item: P<Item>| {
             let mod_id = item.id;
+            let mod_ident = item.ident;
             if let ItemKind::Mod(m) = &mut item.kind {
                 // Add use statements for split namespace imports
                 m.items.flat_map_in_place(|item: P<Item>| -> SmallVec<[P<Item>; 1]> {
                     let mut items = smallvec![];
-                    if let ItemKind::Use(_) = &item.kind {
+                    if let ItemKind::Use(tree) = &item.kind {
+                        // The extra imports keep the name and visibility of
+                        // the original one, including any `as` rename
+                        let rename = match tree.kind {
+                            UseTreeKind::Simple(rename, ..) => rename,
+                            _ => None,
+                        };
                         if let Some((path, def_ids)) = multi_namespace_uses.get(&item.id) {
                             for def_id in def_ids {
                                 let (other_mod_id, _) = remapped_paths[&item.id];
                                 if let Some(Replacement {path, parent, ..}) = self.path_mapping.get(&def_id) {
-                                    if other_mod_id != *parent {
-                                        items.push(mk().use_simple_item(
-                                            path,
-                                            None as Option<Ident>,
-                                        ));
+                                    if parent.is_none() || other_mod_id != *parent {
+                                        items.push(mk().vis(item.vis.clone()).use_simple_item(path, rename));
                                     }
                                 } else if is_relative_path(&path) {
                                     // Canonicalize a new path from the crate root. Will rewrite
@@ -849,13 +2755,13 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                                     if let Some(hir_id) = self.cx.hir_map().as_local_hir_id(*def_id) {
                                         let mod_hir_id = self.cx.hir_map().get_module_parent_node(hir_id);
                                         let mod_id = self.cx.hir_map().hir_to_node_id(mod_hir_id);
-                                        if other_mod_id != mod_id {
+                                        if other_mod_id != Some(mod_id) {
                                             let new_node_id = self.st.next_node_id();
-                                            let inserted = remapped_paths.insert(new_node_id, (mod_id, *def_id)).is_none();
+                                            let inserted = remapped_paths.insert(new_node_id, (Some(mod_id), *def_id)).is_none();
                                             assert!(inserted);
-                                            items.push(mk().id(new_node_id).use_simple_item(
+                                            items.push(mk().id(new_node_id).vis(item.vis.clone()).use_simple_item(
                                                 self.cx.def_path(*def_id),
-                                                None as Option<Ident>,
+                                                rename,
                                             ));
                                         }
                                     }
@@ -868,7 +2774,48 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                 });
 
                 // Mapping from ident to the module we are importing that ident from
-                let mut uses: PerNS<HashMap<Ident, NodeId>> = PerNS::default();
+                // (`None` if the ident is imported from an unresolved module),
+                // and the use we keep for it.
+                let mut uses: PerNS<HashMap<Ident, (Option<NodeId>, NodeId)>> = PerNS::default();
+                // Removing a `pub use` must not shrink the module's public
+                // API, so the use or definition that stays in its place
+                // takes over its visibility.
+                let mut widened_uses: HashMap<NodeId, VisibilityKind> = HashMap::new();
+                let mut widened_defs: Vec<(Ident, Option<Namespace>, VisibilityKind)> = vec![];
+                let mut widen_use = |kept_id: NodeId, vis: &VisibilityKind| {
+                    widened_uses
+                        .entry(kept_id)
+                        .and_modify(|kept_vis| *kept_vis = join_visibility(kept_vis, vis))
+                        .or_insert_with(|| vis.clone());
+                };
+                // The module an imported definition ends up in
+                let target_module = |def_id: DefId| {
+                    if let Some(Replacement {parent, ..}) = self.path_mapping.get(&def_id) {
+                        *parent
+                    } else {
+                        let hir_id = self.cx.hir_map().as_local_hir_id(def_id)?;
+                        let mod_hir_id = self.cx.hir_map().get_module_parent_node(hir_id);
+                        Some(self.cx.hir_map().hir_to_node_id(mod_hir_id))
+                    }
+                };
+                // Nested imports that kept their paths stay as they are, so
+                // simple imports of the same names are the ones to drop
+                for item in &m.items {
+                    match &item.kind {
+                        ItemKind::Use(tree) if is_nested(tree) => {}
+                        _ => continue,
+                    }
+                    for leaf in split_uses(item.clone()) {
+                        let namespace = match_or!([self.cx.item_namespace(&leaf)] Some(ns) => ns; continue);
+                        let ident = expect!([&leaf.kind] ItemKind::Use(u) => u).ident();
+                        if let Some(def_id) = self.cx
+                            .try_resolve_use_id(leaf.id)
+                            .and_then(|def| def.res.opt_def_id())
+                        {
+                            uses[namespace].insert(ident, (target_module(def_id), item.id));
+                        }
+                    }
+                }
                 m.items.retain(|item| {
                     if let ItemKind::Use(u) = &item.kind {
                         match u.kind {
@@ -880,11 +2827,17 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                             }
 
                             // We don't need to handle Nested uses here because
-                            // `fold_resolved_paths_with_id` splits nested uses
-                            // into Simple uses when it remaps a path.
+                            // `fold_resolved_paths_with_id` splits the imports
+                            // it remaps out of nested uses.
                             _ => {
                                 if let Some((mod_def_id, _)) = remapped_paths.get(&item.id) {
-                                    if *mod_def_id == mod_id {
+                                    if *mod_def_id == Some(mod_id) {
+                                        // The item is now defined here
+                                        widened_defs.push((
+                                            u.ident(),
+                                            self.cx.item_namespace(&item),
+                                            item.vis.node.clone(),
+                                        ));
                                         return false;
                                     }
                                 }
@@ -901,136 +2854,1205 @@ impl<'a, 'tcx> Reorganizer<'a, 'tcx> {
                             {
                                 if let Some(Replacement {parent, ..}) = self.path_mapping.get(&def_id) {
                                     for ns in &[Namespace::ValueNS, Namespace::TypeNS] {
-                                        if let Some(target_mod) = uses[*ns].get(&u.ident()) {
-                                            if target_mod == parent {
+                                        if let Some(&(target_mod, kept_id)) = uses[*ns].get(&u.ident()) {
+                                            if target_mod.is_some() && target_mod == *parent {
+                                                widen_use(kept_id, &item.vis.node);
                                                 return false;
                                             } else if *ns == namespace {
-                                                panic!(
-                                                    "Conflicting imports of {:?} from {:?} and {:?}",
+                                                self.report(ReorganizeError::NameCollision(
                                                     u.ident(),
-                                                    target_mod,
-                                                    *parent,
-                                                );
+                                                    mod_ident,
+                                                    item.span,
+                                                ));
+                                                return true;
                                             }
                                         }
                                     }
                                 }
                             }
 
-                            if uses[namespace].contains_key(&u.ident()) {
+                            if let Some(&(_, kept_id)) = uses[namespace].get(&u.ident()) {
+                                widen_use(kept_id, &item.vis.node);
                                 return false;
                             } else {
                                 if let Some(def_id) = self.cx
                                     .try_resolve_use_id(item.id)
                                     .and_then(|def| def.res.opt_def_id())
                                 {
-                                    let mod_id = if let Some(Replacement {parent, ..}) = self.path_mapping.get(&def_id) {
-                                        *parent
-                                    } else {
-                                        if let Some(hir_id) = self.cx.hir_map().as_local_hir_id(def_id) {
-                                            let mod_hir_id = self.cx.hir_map().get_module_parent_node(hir_id);
-                                            self.cx.hir_map().hir_to_node_id(mod_hir_id)
-                                        } else {
-                                            DUMMY_NODE_ID
-                                        }
-                                    };
-                                    uses[namespace].insert(u.ident(), mod_id);
+                                    uses[namespace].insert(u.ident(), (target_module(def_id), item.id));
                                 }
                             }
                         }
                     }
                     true
                 });
+
+                for item in &mut m.items {
+                    if let Some(vis) = widened_uses.get(&item.id) {
+                        item.vis.node = join_visibility(&item.vis.node, vis);
+                    }
+                    for (ident, namespace, vis) in &widened_defs {
+                        if let ItemKind::ForeignMod(fm) = &mut item.kind {
+                            for fi in &mut fm.items {
+                                let fi_namespace = match fi.kind {
+                                    ForeignItemKind::Ty => Namespace::TypeNS,
+                                    _ => Namespace::ValueNS,
+                                };
+                                if fi.ident == *ident && *namespace == Some(fi_namespace) {
+                                    fi.vis.node = join_visibility(&fi.vis.node, vis);
+                                }
+                            }
+                        } else if item.ident == *ident
+                            && !matches!([item.kind] ItemKind::Use(..))
+                            && self.cx.item_namespace(item) == *namespace
+                        {
+                            item.vis.node = join_visibility(&item.vis.node, vis);
+                        }
+                    }
+                }
             }
             smallvec![item]
         });
     }
-}
 
-#[derive(Clone, Debug)]
-struct HeaderInfo {
-    ident: Ident,
-    path: String,
-    include_line: usize,
-}
+    /// Fix up users of replaced items whose types differ from the ones of
+    /// their replacements: extern arrays declared with length 0, and values
+    /// that need to be cast back to the type their users expect.
+    fn fix_replaced_users(
+        &self,
+        krate: &mut Crate,
+        remapped_paths: &HashMap<NodeId, (Option<NodeId>, DefId)>,
+    ) {
+        let tcx = self.cx.ty_ctxt();
 
-impl HeaderInfo {
-    fn new(ident: Ident, path: String, include_line: usize) -> Self {
-        Self {
-            ident,
-            path,
-            include_line,
-        }
-    }
+        // Fix casts of the following format:
+        // &global as *const [T; 0]
+        // where global is now [T; n] where n > 0, not [T; 0]
+        MutVisitNodes::visit(krate, |e: &mut P<Expr>| {
+            let cast_id = e.id;
+            let (val, ty) = match_or!([&mut e.kind] ExprKind::Cast(val, ty) => (val, ty); return);
+            let val = match_or!([&val.kind] ExprKind::AddrOf(_, _mutbl, val) => val; return);
+            let old_def_id = match_or!([self.cx.try_resolve_expr(&val)] Some(id) => id; return);
+            let replacement = match_or!([self.path_mapping.get(&old_def_id)] Some(x) => x; return);
+            let new_def_id = match_or!([replacement.def] Some(id) => id; return);
+            let val_ty = self.cx.def_type(new_def_id);
+            let val_len = match_or!([val_ty.kind] ty::TyKind::Array(_ty, n) => n; return);
+            let cast_ty = match_or!([self.cx.opt_node_type(cast_id)] Some(ty) => ty; return);
+            let cast_ty = match_or!([cast_ty.kind] ty::TyKind::RawPtr(ty) => ty; return);
+            let cast_ty = cast_ty.ty;
+            let cast_len = match_or!([cast_ty.kind] ty::TyKind::Array(_ty, n) => n; return);
+            if let Some(0) = cast_len.try_eval_usize(tcx, ParamEnv::empty()) {
+                if let Some(val_len) = val_len.try_eval_usize(tcx, ParamEnv::empty()) {
+                    let ty = match_or!([&mut ty.kind] TyKind::Ptr(ty) => ty; return);
+                    let cast_len = match_or!([&mut ty.ty.kind] TyKind::Array(_ty, n) => n; return);
+                    let lit = mk().lit_expr(mk().int_lit(val_len as u128, LitIntType::Unsuffixed));
+                    *cast_len = mk().anon_const(lit);
+                }
+            }
+        });
 
-    /// A complementary check to `has_source_header`. Checks if the header source
-    /// path contains `/usr/include`
-    // TODO: In macOS mojave the system headers aren't in `/usr/include` anymore,
-    // so this needs to be updated.
-    fn is_std(&self) -> bool {
-        self.path.contains("/usr/include")
-    }
-}
+        // Mapping from old DefId to new DefId
+        let replacement_map = self.path_mapping.iter().filter_map(|(old_def, replacement)| {
+            match self.cx.hir_map().get_if_local(*old_def) {
+                Some(Node::ForeignItem(_)) => {}
+                Some(Node::Item(item)) => match item.kind {
+                    hir::ItemKind::Static(..) | hir::ItemKind::Const(..) | hir::ItemKind::Fn(..)
+                    | hir::ItemKind::Union(..) | hir::ItemKind::Enum(..)
+                    | hir::ItemKind::TyAlias(..) => {}
+                    _ => return None,
+                },
+                _ => return None,
+            }
+            replacement.def.map(|new_def| (*old_def, new_def))
+        }).collect();
+        // Mapping from user path expr NodeId to old DefId
+        let path_ids = remapped_paths.iter().map(|(user, (_parent, def))| (*user, *def)).collect();
+        // Mapping from old DefId to new path
+        let new_paths = self.path_mapping.iter().map(|(old_did, replacement)| {
+            (*old_did, (None, replacement.path.clone()))
+        }).collect::<HashMap<_, _>>();
 
-impl ModuleInfo {
-    fn new(orig_ident: Ident, unique_ident: Ident, id: NodeId) -> Self {
-        Self {
-            orig_ident,
-            unique_ident,
-            id,
-            path: vec![mk().path_segment(kw::Crate), mk().path_segment(unique_ident.name)],
-            new: true,
-            has_main: false,
-            header_lines: HashMap::new(),
-            headers: HashSet::new(),
-            items: PerNS::default(),
-        }
+        // Cast updated values back to their original type if needed
+        externs::fix_users(krate, &replacement_map, &path_ids, &new_paths, self.cx);
     }
 
-    /// Create a ModuleInfo from a module `Item`
-    fn from_item(item: &Item, cx: &RefactorCtxt) -> Self {
-        let module = expect!([&item.kind] ItemKind::Mod(m) => m);
-        let mut has_main = false;
-        let mut header_lines: HashMap<Ident, usize> = HashMap::new();
-        let mut headers = HashSet::new();
-        let def_id = cx.node_def_id(item.id);
-        let path = cx.def_path(def_id);
-        for i in &module.items {
-            match &i.kind {
-                ItemKind::Fn(..) => {
-                    if i.ident.as_str() == "main" {
-                        has_main = true;
-                    }
-                }
-                ItemKind::Mod(..) => {
-                    let (path, line) = parse_source_header(&i.attrs)
-                        .unwrap_or_else(|| panic!("Could not parse source header for item: {:?}", i));
-                    headers.insert(path);
-                    if header_lines.insert(i.ident, line).is_some() {
-                        panic!(
-                            "Conflicting headers in the same module with name: {}",
-                            i.ident
-                        );
-                    }
-                }
-                _ => {}
+    /// Recreate the `pub use self::...` re-exports taken out of header modules
+    /// in the modules that declared those headers, going through the new
+    /// location of the moved item, e.g. `pub use self::Mode::Fast;` becomes
+    /// `pub use crate::dest::Mode::Fast;`.
+    fn restore_self_reexports(&self, krate: &mut Crate) {
+        let mut new_uses: HashMap<NodeId, Vec<P<Item>>> = HashMap::new();
+        for reexport in &self.self_reexports {
+            let replacement = match_or!([self.path_mapping.get(&reexport.def_id)] Some(r) => r; continue);
+            let mut tree = reexport.tree.clone();
+            if tree.prefix.segments.len() == 2 && replacement.parent == Some(reexport.parent) {
+                // The item itself now lives in this module, so re-exporting
+                // it there would clash with its definition.
+                continue;
             }
+            let mut segments = replacement.path.segments.clone();
+            segments.extend(tree.prefix.segments.drain(2..));
+            tree.prefix = mk().path(segments);
+            new_uses
+                .entry(reexport.parent)
+                .or_default()
+                .push(mk().vis(reexport.vis.clone()).use_item(tree));
         }
-        Self {
-            orig_ident: item.ident,
-            unique_ident: item.ident,
-            id: item.id,
-            path: path.segments,
-            new: false,
-            has_main,
-            header_lines,
-            headers,
-            items: PerNS::default(),
+        if new_uses.is_empty() {
+            return;
         }
-    }
-}
 
-#[derive(Debug)]
+        if let Some(items) = new_uses.remove(&CRATE_NODE_ID) {
+            insert_uses(&mut krate.module.items, items);
+        }
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            let item_id = item.id;
+            if let ItemKind::Mod(m) = &mut item.kind {
+                if let Some(items) = new_uses.remove(&item_id) {
+                    insert_uses(&mut m.items, items);
+                }
+            }
+            smallvec![item]
+        });
+    }
+
+    /// Re-export each public declaration moved out of a header module from
+    /// that module, at its new path
+    fn emit_shims(&self, krate: &mut Crate) {
+        let hir_map = self.cx.hir_map();
+        let mut shims: HashMap<NodeId, BTreeMap<String, Path>> = HashMap::new();
+        for (def_id, replacement) in &self.path_mapping {
+            if !self.cx.is_exported_def(*def_id) {
+                continue;
+            }
+            let hir_id = match_or!([hir_map.as_local_hir_id(*def_id)] Some(hir_id) => hir_id; continue);
+            let header_id = hir_map.hir_to_node_id(hir_map.get_module_parent_node(hir_id));
+            shims
+                .entry(header_id)
+                .or_default()
+                .insert(path_to_string(&replacement.path), replacement.path.clone());
+        }
+
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if !has_source_header(&item.attrs, &self.options.header_attr) {
+                return smallvec![item];
+            }
+            let paths = match_or!([shims.remove(&item.id)] Some(paths) => paths; return smallvec![item]);
+            if let ItemKind::Mod(m) = &mut item.kind {
+                insert_uses(
+                    &mut m.items,
+                    paths
+                        .into_iter()
+                        .map(|(_, path)| mk().pub_().use_simple_item(path, None as Option<Ident>)),
+                );
+            }
+            smallvec![item]
+        });
+    }
+
+    /// Add a `prelude` module to the crate root that re-exports the public
+    /// items we moved, sorted by path. Items moved into the stdlib module and
+    /// unnamed helper types are not part of the crate's API, so we leave them
+    /// out, as well as names that were moved into more than one module.
+    fn emit_prelude(&self, krate: &mut Crate) {
+        let tcx = self.cx.ty_ctxt();
+        let mut paths_by_name: HashMap<Ident, BTreeMap<String, Path>> = HashMap::new();
+        for (&def_id, replacement) in &self.path_mapping {
+            let parent = match_or!([replacement.parent] Some(parent) => parent; continue);
+            let in_stdlib = parent == self.stdlib_id
+                || self.modules.get(&parent).map_or(false, |info| info.parent == Some(self.stdlib_id));
+            if in_stdlib || tcx.visibility(def_id) != ty::Visibility::Public {
+                continue;
+            }
+            let ident = replacement.path.segments.last().unwrap().ident;
+            if ident.as_str().is_empty() || ident.as_str().contains("C2RustUnnamed") {
+                continue;
+            }
+            paths_by_name
+                .entry(ident)
+                .or_default()
+                .insert(path_to_string(&replacement.path), replacement.path.clone());
+        }
+
+        let mut paths: Vec<(String, Path)> = paths_by_name
+            .into_iter()
+            .filter_map(|(_, paths)| if paths.len() == 1 { paths.into_iter().next() } else { None })
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        paths.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let uses: Vec<P<Item>> = paths
+            .into_iter()
+            .map(|(_, path)| mk().pub_().use_simple_item(path, None as Option<Ident>))
+            .collect();
+        krate.module.items.push(mk().pub_().mod_item("prelude", mk().mod_(uses)));
+    }
+
+    /// Remove impl items that became duplicates once the types they are
+    /// implemented for were deduplicated. Impls with equivalent headers
+    /// (generics, trait and self type) may define the same associated item
+    /// only if both definitions are equivalent, in which case we keep the
+    /// first one; an impl left with nothing but duplicates is removed. We keep
+    /// and report conflicting definitions rather than pick one of them.
+    fn dedup_impl_items(&self, krate: &mut Crate) {
+        // Header (the impl without its items) and items of every impl so far
+        let mut impls: Vec<(ItemKind, Vec<ImplItem>)> = vec![];
+        // Indices of the duplicate items of each impl
+        let mut duplicates: HashMap<NodeId, HashSet<usize>> = HashMap::new();
+        let mut redundant_impls = HashSet::new();
+        visit_nodes(&*krate, |item: &Item| {
+            let items = match_or!([&item.kind] ItemKind::Impl(.., items) => items; return);
+            let mut header = item.kind.clone();
+            if let ItemKind::Impl(.., header_items) = &mut header {
+                header_items.clear();
+            }
+
+            let equivalent_impls = impls
+                .iter()
+                .filter(|(prev_header, _)| header.unnamed_equiv(prev_header))
+                .collect::<Vec<_>>();
+            if equivalent_impls.is_empty() {
+                impls.push((header, items.clone()));
+                return;
+            }
+
+            let mut dups = HashSet::new();
+            for (idx, impl_item) in items.iter().enumerate() {
+                let prev = equivalent_impls
+                    .iter()
+                    .flat_map(|(_, prev_items)| prev_items.iter())
+                    .find(|prev| prev.ident.name == impl_item.ident.name);
+                if let Some(prev) = prev {
+                    if prev.generics.unnamed_equiv(&impl_item.generics)
+                        && prev.kind.unnamed_equiv(&impl_item.kind)
+                    {
+                        dups.insert(idx);
+                    } else {
+                        self.report(ReorganizeError::ConflictingImplItem(
+                            impl_item.ident,
+                            impl_item.span,
+                        ));
+                    }
+                }
+            }
+            if dups.len() == items.len() {
+                redundant_impls.insert(item.id);
+            } else if !dups.is_empty() {
+                duplicates.insert(item.id, dups);
+            }
+            impls.push((header, items.clone()));
+        });
+
+        if redundant_impls.is_empty() && duplicates.is_empty() {
+            return;
+        }
+
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if redundant_impls.contains(&item.id) {
+                return smallvec![];
+            }
+            if let Some(dups) = duplicates.get(&item.id) {
+                if let ItemKind::Impl(.., items) = &mut item.kind {
+                    let mut idx = 0;
+                    items.retain(|_| {
+                        let keep = !dups.contains(&idx);
+                        idx += 1;
+                        keep
+                    });
+                }
+            }
+            smallvec![item]
+        });
+    }
+
+    /// Hoist the only item of each single-item module created by this pass
+    /// into its parent module, and remove the now empty module. Nested new
+    /// modules are collapsed first, so a chain of them collapses all the
+    /// way up. A hoisted item whose name is taken in the parent is renamed
+    /// to `module_item`, unless it is exported under its name.
+    ///
+    /// All paths to moved items are absolute by now, so we can fix up paths
+    /// that went through a collapsed module syntactically. Modules that are
+    /// named in a way we can't fix up (imported themselves, used in relative
+    /// paths or in macro arguments) are left alone.
+    fn collapse_single_item_modules(&self, krate: &mut Crate) {
+        let mut pinned_names = HashSet::new();
+        let mut pinned_paths = HashSet::new();
+        visit_nodes(krate, |path: &Path| {
+            match path.segments.first() {
+                Some(seg) if seg.ident.name == kw::Crate => {
+                    pinned_paths.insert(path.segments.iter().map(|seg| seg.ident.name).collect::<Vec<_>>());
+                }
+                _ => {
+                    let prefix = &path.segments[..path.segments.len().saturating_sub(1)];
+                    pinned_names.extend(prefix.iter().map(|seg| seg.ident.name));
+                }
+            }
+        });
+        let mut macro_names = MacroNames::default();
+        visit::walk_crate(&mut macro_names, krate);
+        pinned_names.extend(macro_names.names);
+
+        // Module path, and old and new name of its hoisted item, in the
+        // order the modules were collapsed
+        let mut collapsed: Vec<(Vec<Symbol>, Ident, Ident)> = vec![];
+        self.collapse_module_items(
+            &mut krate.module.items,
+            &pinned_names,
+            &pinned_paths,
+            &mut collapsed,
+        );
+
+        if collapsed.is_empty() {
+            return;
+        }
+
+        MutVisitNodes::visit(krate, |path: &mut Path| {
+            for (mod_path, old_ident, new_ident) in &collapsed {
+                let len = mod_path.len();
+                if path.segments.len() > len
+                    && path.segments[len].ident.name == old_ident.name
+                    && path.segments.iter().zip(mod_path).all(|(seg, name)| seg.ident.name == *name)
+                {
+                    path.segments.remove(len - 1);
+                    let span = path.segments[len - 1].ident.span;
+                    path.segments[len - 1].ident = Ident::new(new_ident.name, span);
+                }
+            }
+        });
+    }
+
+    /// Collapse the single-item new modules among `items`, see
+    /// `collapse_single_item_modules`
+    fn collapse_module_items(
+        &self,
+        items: &mut Vec<P<Item>>,
+        pinned_names: &HashSet<Symbol>,
+        pinned_paths: &HashSet<Vec<Symbol>>,
+        collapsed: &mut Vec<(Vec<Symbol>, Ident, Ident)>,
+    ) {
+        for item in items.iter_mut() {
+            if let ItemKind::Mod(m) = &mut item.kind {
+                self.collapse_module_items(&mut m.items, pinned_names, pinned_paths, collapsed);
+            }
+        }
+
+        let mut idents: HashMap<Ident, usize> = HashMap::new();
+        for item in items.iter() {
+            let ident = match &item.kind {
+                ItemKind::Use(tree) => tree.ident(),
+                _ => item.ident,
+            };
+            *idents.entry(ident).or_default() += 1;
+        }
+
+        for item in items.iter_mut() {
+            let mod_info = match self.modules.get(&item.id) {
+                Some(info) if info.new => info,
+                _ => continue,
+            };
+            let mod_path = mod_info.path.iter().map(|seg| seg.ident.name).collect::<Vec<_>>();
+            if pinned_paths.contains(&mod_path) || pinned_names.contains(&item.ident.name) {
+                continue;
+            }
+            let mod_ident = item.ident;
+            let inner = match &item.kind {
+                ItemKind::Mod(m) if m.items.len() == 1 => &m.items[0],
+                _ => continue,
+            };
+            match inner.kind {
+                ItemKind::Mod(..) | ItemKind::Use(..) | ItemKind::ForeignMod(..)
+                    | ItemKind::Impl(..) | ItemKind::Mac(..) | ItemKind::MacroDef(..) => continue,
+                _ => {}
+            }
+
+            // Don't hoist an item over an existing item with the same name;
+            // rename it instead, if we can
+            let mut new_ident = inner.ident;
+            let existing = idents.get(&new_ident).cloned().unwrap_or(0);
+            if existing > usize::from(new_ident == mod_ident) {
+                if is_exported(inner) {
+                    continue;
+                }
+                let base = format!("{}_{}", mod_ident, inner.ident);
+                new_ident = (0..)
+                    .map(|n| match n {
+                        0 => Ident::from_str(&base),
+                        n => Ident::from_str(&format!("{}_{}", base, n)),
+                    })
+                    .find(|name| !idents.contains_key(name))
+                    .unwrap();
+            }
+
+            let mut inner = inner.clone();
+            collapsed.push((mod_path, inner.ident, new_ident));
+            inner.ident = new_ident;
+            *idents.entry(mod_ident).or_default() -= 1;
+            *idents.entry(new_ident).or_default() += 1;
+            *item = inner;
+        }
+    }
+
+    /// Shorten absolute paths that point into the module they are used from.
+    /// This runs on the final AST, whose paths have not been resolved, so
+    /// instead of asking the resolver we only shorten a path when the name it
+    /// would start with is bound exactly once in the module, by one of the
+    /// module's own items.
+    fn relativize_paths(&self, krate: &mut Crate) {
+        relativize_module_paths(&mut krate.module, &[Ident::with_dummy_span(kw::Crate)]);
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if let Some(info) = self.modules.get(&item.id) {
+                let prefix = info.path.iter().map(|seg| seg.ident).collect::<Vec<_>>();
+                if let ItemKind::Mod(m) = &mut item.kind {
+                    relativize_module_paths(m, &prefix);
+                }
+            }
+            smallvec![item]
+        });
+    }
+
+    /// Remove the private simple imports of each destination module whose
+    /// names aren't used in it (or its child modules) anymore. Imports that
+    /// can't be resolved are kept, as are imports of traits and macros,
+    /// which can be used without naming them.
+    fn remove_unused_uses(&self, krate: &mut Crate) {
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if let Some(module) = self.modules.get(&item.id) {
+                if let ItemKind::Mod(m) = &mut item.kind {
+                    // Collect the paths in the module and its child modules
+                    // that may refer to the module's imports
+                    let mut used = UsedPaths::new(self.cx, &module.path);
+                    for item in &m.items {
+                        used.visit_item(item);
+                    }
+                    m.items.retain(|item| !self.is_unused_use(item, &used));
+                }
+            }
+            smallvec![item]
+        });
+    }
+
+    fn is_unused_use(&self, item: &Item, used: &UsedPaths) -> bool {
+        let tree = match_or!([&item.kind] ItemKind::Use(tree) => tree; return false);
+        if !is_prunable_use(item) {
+            return false;
+        }
+        let name = tree.ident().name;
+        if name == kw::Underscore {
+            return false;
+        }
+        match self.cx.try_resolve_use_id(item.id).map(|path| path.res) {
+            Some(Res::Def(DefKind::Trait, _))
+            | Some(Res::Def(DefKind::TraitAlias, _))
+            | Some(Res::Def(DefKind::Macro(..), _))
+            | Some(Res::Err)
+            | None => false,
+            Some(res) => !used.is_used(name, res),
+        }
+    }
+
+    /// Lay out the simple imports of each module according to `use_style`
+    fn restyle_uses(&self, krate: &mut Crate) {
+        let style = self.options.use_style;
+        let parent_module = |item: &Item| self.use_parent_module(item);
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if self.modules.contains_key(&item.id) {
+                if let ItemKind::Mod(m) = &mut item.kind {
+                    restyle_module_uses(&mut m.items, style, &parent_module);
+                }
+            }
+            smallvec![item]
+        });
+    }
+
+    /// The module a simple import takes its item from, if the item is a
+    /// local one that sits directly in a module, after moving it
+    fn use_parent_module(&self, item: &Item) -> Option<NodeId> {
+        let hir_map = self.cx.hir_map();
+        // Imports added by the pass have no resolution
+        hir_map.opt_local_def_id_from_node_id(item.id)?;
+        let def_id = self.cx.try_resolve_use_id(item.id)?.res.opt_def_id()?;
+        if let Some(replacement) = self.path_mapping.get(&def_id) {
+            return replacement.parent;
+        }
+        let hir_id = hir_map.as_local_hir_id(def_id)?;
+        let mod_hir_id = hir_map.get_module_parent_node(hir_id);
+        if hir_map.get_parent_item(hir_id) != mod_hir_id {
+            // e.g. an enum variant
+            return None;
+        }
+        Some(hir_map.hir_to_node_id(mod_hir_id))
+    }
+
+    /// Merge the extern blocks of every destination module into the first
+    /// block with the same ABI and attributes, keeping the order of their
+    /// items, so that declarations from several headers share one block
+    fn merge_extern_blocks(&self, krate: &mut Crate) {
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if self.modules.contains_key(&item.id) {
+                if let ItemKind::Mod(m) = &mut item.kind {
+                    let mut merged: Vec<P<Item>> = Vec::with_capacity(m.items.len());
+                    for block in m.items.drain(..) {
+                        let abi = match &block.kind {
+                            ItemKind::ForeignMod(fm) => Some(foreign_mod_abi(fm)),
+                            _ => None,
+                        };
+                        if let Some(abi) = abi {
+                            let target = merged.iter_mut().find(|prev| match &prev.kind {
+                                ItemKind::ForeignMod(prev_fm) => {
+                                    foreign_mod_abi(prev_fm) == abi && prev.attrs.ast_equiv(&block.attrs)
+                                }
+                                _ => false,
+                            });
+                            if let Some(target) = target {
+                                let fm = expect!([block.into_inner().kind] ItemKind::ForeignMod(fm) => fm);
+                                let target_fm = expect!([&mut target.kind] ItemKind::ForeignMod(fm) => fm);
+                                target_fm.items.extend(fm.items);
+                                continue;
+                            }
+                        }
+                        merged.push(block);
+                    }
+                    m.items = merged;
+                }
+            }
+            smallvec![item]
+        });
+    }
+
+    /// Stably sort the items of every destination module by `item_kind_rank`
+    fn order_items_by_kind(&self, krate: &mut Crate) {
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if self.modules.contains_key(&item.id) {
+                if let ItemKind::Mod(m) = &mut item.kind {
+                    m.items.sort_by_key(|item| item_kind_rank(item));
+                }
+            }
+            smallvec![item]
+        });
+    }
+
+    /// Report modules that share their name with a sibling module. Moving
+    /// declarations into an existing module with the same name as a new one
+    /// should prevent this, but if it slips through, rustc would reject the
+    /// crate with a much less helpful "defined multiple times" error.
+    fn check_module_names(&self, krate: &Crate) {
+        let check_items = |items: &[P<Item>]| {
+            let mut seen = HashSet::new();
+            for item in items {
+                if let ItemKind::Mod(_) = item.kind {
+                    if !seen.insert(item.ident) {
+                        self.report(ReorganizeError::DuplicateModule(item.ident, item.span));
+                    }
+                }
+            }
+        };
+        check_items(&krate.module.items);
+        visit_nodes(krate, |item: &Item| {
+            if let ItemKind::Mod(m) = &item.kind {
+                check_items(&m.items);
+            }
+        });
+    }
+
+    /// Warn about items of destination modules that nothing in the crate
+    /// refers to any more and that are not visible from outside the crate
+    /// either. Matching is by name only, so this can miss items that share a
+    /// name with something still in use, but never reports a live item.
+    fn report_unreachable_items(&self, krate: &Crate) {
+        let mut referenced = HashSet::new();
+        visit_nodes(krate, |path: &Path| {
+            if let Some(segment) = path.segments.last() {
+                referenced.insert(segment.ident.name);
+            }
+        });
+
+        let mut stack: Vec<(&Mod, NodeId, Vec<Ident>, bool)> =
+            vec![(&krate.module, CRATE_NODE_ID, vec![], true)];
+        while let Some((module, module_id, path, public)) = stack.pop() {
+            let check_items = self.modules.contains_key(&module_id);
+            for item in &module.items {
+                let is_pub = match item.vis.node {
+                    VisibilityKind::Public => true,
+                    _ => false,
+                };
+                let mut item_path = path.clone();
+                item_path.push(item.ident);
+                match &item.kind {
+                    ItemKind::Mod(m) => {
+                        stack.push((m, item.id, item_path, public && is_pub));
+                        continue;
+                    }
+                    ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Union(..)
+                        | ItemKind::TyAlias(..) | ItemKind::Trait(..) | ItemKind::Const(..)
+                        | ItemKind::Static(..) | ItemKind::Fn(..) => {}
+                    _ => continue,
+                }
+                if !check_items
+                    || (public && is_pub)
+                    || is_exported(item)
+                    || item.ident.name == sym::main
+                    || referenced.contains(&item.ident.name)
+                {
+                    continue;
+                }
+                let path_str = item_path
+                    .iter()
+                    .map(|ident| ident.as_str().to_string())
+                    .collect::<Vec<_>>()
+                    .join("::");
+                let msg = format!("{} is unreachable after reorganize_definitions", path_str);
+                self.cx.session().struct_span_warn(item.span, &msg).emit();
+            }
+        }
+    }
+
+    /// Warn about groups of moved types that refer to each other, directly or
+    /// through other moved types, but were moved into different modules.
+    /// Merged copies of a type count as one type, identified by its new
+    /// path.
+    fn report_type_cycles(&self) {
+        fn reachable<'s>(
+            edges: &HashMap<&'s str, BTreeSet<&'s str>>,
+            start: &'s str,
+        ) -> HashSet<&'s str> {
+            let mut seen = HashSet::new();
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                for &next in edges.get(node).into_iter().flatten() {
+                    if seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            seen
+        }
+
+        let tcx = self.cx.ty_ctxt();
+        let mut node_of: HashMap<DefId, String> = HashMap::new();
+        for (&def_id, replacement) in &self.path_mapping {
+            if replacement.parent.is_none() {
+                continue;
+            }
+            if matches!([tcx.def_kind(def_id)]
+                         Some(DefKind::Struct), Some(DefKind::Union),
+                         Some(DefKind::Enum), Some(DefKind::TyAlias)) {
+                node_of.insert(def_id, path_to_string(&replacement.path));
+            }
+        }
+
+        // Destination module and lowest def of each type, and the types its
+        // fields (or aliased type) refer to
+        let mut nodes: BTreeMap<&str, (NodeId, DefId)> = BTreeMap::new();
+        let mut edges: HashMap<&str, BTreeSet<&str>> = HashMap::new();
+        for (&def_id, from) in &node_of {
+            let parent = self.path_mapping[&def_id].parent.unwrap();
+            let node = nodes.entry(from.as_str()).or_insert((parent, def_id));
+            node.1 = node.1.min(def_id);
+
+            let tys = match tcx.def_kind(def_id) {
+                Some(DefKind::TyAlias) => vec![tcx.type_of(def_id)],
+                _ => tcx.adt_def(def_id)
+                    .all_fields()
+                    .map(|field| tcx.type_of(field.did))
+                    .collect(),
+            };
+            for ty in tys.into_iter().flat_map(|ty| ty.walk()) {
+                if let ty::Adt(adt_def, _) = ty.kind {
+                    match node_of.get(&adt_def.did) {
+                        Some(to) if to != from => {
+                            edges.entry(from.as_str()).or_default().insert(to.as_str());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let mut reported: HashSet<&str> = HashSet::new();
+        for (&path, &(_, def_id)) in &nodes {
+            if reported.contains(path) {
+                continue;
+            }
+            let mut cluster: BTreeSet<&str> = reachable(&edges, path)
+                .into_iter()
+                .filter(|&other| reachable(&edges, other).contains(path))
+                .collect();
+            cluster.insert(path);
+            let modules: BTreeSet<String> = cluster
+                .iter()
+                .filter_map(|other| self.modules.get(&nodes[other].0))
+                .map(|info| segments_to_string(&info.path))
+                .collect();
+            if modules.len() < 2 {
+                continue;
+            }
+            let msg = format!(
+                "{} refer to each other but were moved into different modules ({})",
+                cluster.iter().cloned().collect::<Vec<_>>().join(", "),
+                modules.into_iter().collect::<Vec<_>>().join(", "),
+            );
+            self.cx.session()
+                .struct_span_warn(tcx.def_span(def_id), &msg)
+                .help("consider moving them into the same module")
+                .emit();
+            reported.extend(cluster);
+        }
+    }
+
+    /// Write `contents` to the output file `path`, reporting an error if
+    /// that fails
+    fn write_output(&self, what: &'static str, path: &str, contents: String) {
+        if let Err(e) = fs::write(path, contents) {
+            self.report(ReorganizeError::WriteFailed(what, path.to_string(), e.to_string()));
+        }
+    }
+
+    /// Write the `provenance_out` manifest, mapping each destination module
+    /// path to the sorted list of headers that contributed items to it.
+    fn write_provenance(&self, path: &str) {
+        let modules: BTreeMap<String, &BTreeSet<String>> = self.provenance
+            .iter()
+            .map(|(mod_id, headers)| (segments_to_string(&self.modules[mod_id].path), headers))
+            .collect();
+        let mut manifest = JsonValue::new_object();
+        for (module, headers) in modules {
+            let headers = headers.iter().map(|h| JsonValue::from(h.as_str())).collect();
+            manifest[module.as_str()] = JsonValue::Array(headers);
+        }
+        self.write_output("provenance manifest", path, json::stringify_pretty(manifest, 2));
+    }
+
+    /// Write the `map_out` module mapping, in the format read by `map_in`.
+    fn write_module_map(&self, path: &str) {
+        let mut map = JsonValue::new_object();
+        for (header, dests) in &self.module_routes {
+            if dests.len() == 1 {
+                map[header.as_str()] = dests.iter().next().unwrap().as_str().into();
+            } else {
+                self.cx.session().warn(&format!(
+                    "declarations of {} were moved into several modules ({}), leaving it out of {}",
+                    header,
+                    dests.iter().cloned().collect::<Vec<_>>().join(", "),
+                    path,
+                ));
+            }
+        }
+        self.write_output("module map", path, json::stringify_pretty(map, 2));
+    }
+
+    /// Write the `path_report` of the old and new path and the destination
+    /// module of each moved declaration.
+    fn write_path_report(&self, path: &str) {
+        let rewrites: BTreeMap<String, &Replacement> = self.path_mapping
+            .iter()
+            .map(|(def_id, replacement)| {
+                (segments_to_string(&self.cx.def_path(*def_id).segments), replacement)
+            })
+            .collect();
+        let mut report = JsonValue::new_object();
+        for (old_path, replacement) in rewrites {
+            let module = replacement.parent
+                .and_then(|parent| self.modules.get(&parent))
+                .map_or(JsonValue::Null, |info| segments_to_string(&info.path).into());
+            report[old_path.as_str()] = object! {
+                "old_path" => old_path.as_str(),
+                "new_path" => segments_to_string(&replacement.path.segments),
+                "module" => module,
+            };
+        }
+        self.write_output("path report", path, json::stringify_pretty(report, 2));
+    }
+
+    /// Write the `plan_out` routing table, in the format read by `plan_in`.
+    fn write_plan(&self, path: &str) {
+        let mut plan = JsonValue::new_object();
+        for (item, module) in &self.plan {
+            plan[item.as_str()] = module.as_str().into();
+        }
+        self.write_output("reorganization plan", path, json::stringify_pretty(plan, 2));
+    }
+
+    /// Rewrite intra-doc links (`[foo_h::Bar]`) that name a moved item to
+    /// point at its new location. Doc comments are plain text, so we match
+    /// link paths by their trailing segments against the original paths of
+    /// all moved items, and only rewrite links that match unambiguously.
+    fn rewrite_doc_links(&self, krate: &mut Crate) {
+        let moved_paths = self.moved_paths();
+        let link_re = Regex::new(r"\[(`?)((?:\w+::)+\w+)(`?)\]").unwrap();
+        MutVisitNodes::visit(krate, |attr: &mut Attribute| {
+            let text = match_or!([doc_text(attr)] Some(text) => text; return);
+            let new_text = link_re.replace_all(&text, |caps: &Captures| {
+                rewrite_doc_link(&moved_paths, caps)
+            });
+            if new_text == text {
+                return;
+            }
+            if let AttrKind::DocComment(comment) = &mut attr.kind {
+                *comment = Symbol::intern(&new_text);
+            } else {
+                let mut new_attr = mk().str_attr("doc", &*new_text).into_attrs().pop().unwrap();
+                new_attr.style = attr.style;
+                *attr = new_attr;
+            }
+        });
+    }
+
+    /// Rewrite paths to moved items that appear in attribute arguments, e.g.
+    /// `#[c2rust::related(foo_h::Thing)]`, and in `macro_rules!` bodies.
+    /// These are only tokens, so they never get resolved and `update_paths`
+    /// can't see them.
+    fn rewrite_token_paths(&self, krate: &mut Crate) {
+        let moved_paths = self.moved_paths();
+        MutVisitNodes::visit(krate, |attr: &mut Attribute| {
+            if let AttrKind::Normal(item) = &mut attr.kind {
+                let args = mem::replace(&mut item.args, MacArgs::Empty);
+                item.args = rewrite_mac_args(&moved_paths, args);
+            }
+        });
+        FlatMapNodes::visit(krate, |mut item: P<Item>| {
+            if let ItemKind::MacroDef(def) = &mut item.kind {
+                let args = mem::replace(&mut *def.body, MacArgs::Empty);
+                *def.body = rewrite_mac_args(&moved_paths, args);
+            }
+            smallvec![item]
+        });
+    }
+
+    /// (old, new) path segments of all moved local items
+    fn moved_paths(&self) -> Vec<(Vec<String>, Vec<String>)> {
+        self.path_mapping
+            .iter()
+            .filter(|(old_def, _)| old_def.is_local())
+            .map(|(old_def, replacement)| {
+                (path_strings(&self.cx.def_path(*old_def)), path_strings(&replacement.path))
+            })
+            .collect()
+    }
+}
+
+/// Rewrite a single intra-doc link matched by `rewrite_doc_links`, given the
+/// (old, new) path segments of all moved items.
+fn rewrite_doc_link(moved_paths: &[(Vec<String>, Vec<String>)], caps: &Captures) -> String {
+    let segments: Vec<&str> = caps[2].split("::").collect();
+    let absolute = segments[0] == "crate";
+    let new_path = match find_moved_path(moved_paths, &segments) {
+        Some(path) => path,
+        None => return caps[0].to_string(),
+    };
+    let new_path = new_path
+        .iter()
+        .skip_while(|seg| !absolute && seg.as_str() == "crate")
+        .map(|seg| seg.as_str())
+        .collect::<Vec<_>>()
+        .join("::");
+    format!("[{}{}{}]", &caps[1], new_path, &caps[3])
+}
+
+/// Find the new path of the moved item that the (possibly relative) path
+/// `segments` refers to, given the (old, new) path segments of all moved
+/// items. Bare idents and paths matching more than one moved item are not
+/// resolved.
+fn find_moved_path<'p>(
+    moved_paths: &'p [(Vec<String>, Vec<String>)],
+    segments: &[&str],
+) -> Option<&'p Vec<String>> {
+    let tail: Vec<&str> = segments
+        .iter()
+        .cloned()
+        .skip_while(|seg| *seg == "crate" || *seg == "self" || *seg == "super")
+        .collect();
+    // Only rewrite paths that still name a module, not bare idents
+    if tail.len() < 2 {
+        return None;
+    }
+    let new_paths: HashSet<&Vec<String>> = moved_paths
+        .iter()
+        .filter(|(old, _)| {
+            old.len() >= tail.len()
+                && old[old.len() - tail.len()..]
+                    .iter()
+                    .zip(&tail)
+                    .all(|(a, b)| a.as_str() == *b)
+        })
+        .map(|(_, new)| new)
+        .collect();
+    if new_paths.len() != 1 {
+        return None;
+    }
+    new_paths.into_iter().next()
+}
+
+fn rewrite_mac_args(moved_paths: &[(Vec<String>, Vec<String>)], args: MacArgs) -> MacArgs {
+    match args {
+        MacArgs::Delimited(span, delim, tts) => {
+            MacArgs::Delimited(span, delim, rewrite_path_tokens(moved_paths, tts))
+        }
+        MacArgs::Eq(span, tts) => MacArgs::Eq(span, rewrite_path_tokens(moved_paths, tts)),
+        MacArgs::Empty => MacArgs::Empty,
+    }
+}
+
+/// Replace `a::b::c` token sequences in `tts` that name a moved item with
+/// the item's new absolute path. Macro metavariables (`$foo`) are not path
+/// segments, but `$crate` is.
+fn rewrite_path_tokens(moved_paths: &[(Vec<String>, Vec<String>)], tts: TokenStream) -> TokenStream {
+    let trees: Vec<TokenTree> = tts.into_trees().collect();
+    let ident_at = |idx: usize| match trees.get(idx) {
+        Some(TokenTree::Token(Token { kind: TokenKind::Ident(name, false), .. })) => {
+            let after_dollar = idx > 0 && match &trees[idx - 1] {
+                TokenTree::Token(Token { kind: TokenKind::Dollar, .. }) => true,
+                _ => false,
+            };
+            if after_dollar && *name != kw::Crate {
+                None
+            } else {
+                Some(*name)
+            }
+        }
+        _ => None,
+    };
+    let mod_sep_at = |idx: usize| match trees.get(idx) {
+        Some(TokenTree::Token(Token { kind: TokenKind::ModSep, .. })) => true,
+        _ => false,
+    };
+
+    let mut new_trees = Vec::with_capacity(trees.len());
+    let mut i = 0;
+    while i < trees.len() {
+        // Collect the longest path starting here
+        let mut segments = vec![];
+        let mut end = i;
+        while let Some(name) = ident_at(end) {
+            segments.push(name.as_str().to_string());
+            end += 1;
+            if mod_sep_at(end) && ident_at(end + 1).is_some() {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+
+        if end == i {
+            new_trees.push(match trees[i].clone() {
+                TokenTree::Delimited(span, delim, tts) => {
+                    TokenTree::Delimited(span, delim, rewrite_path_tokens(moved_paths, tts))
+                }
+                tt => tt,
+            });
+            i += 1;
+            continue;
+        }
+
+        let segments: Vec<&str> = segments.iter().map(|seg| seg.as_str()).collect();
+        match find_moved_path(moved_paths, &segments) {
+            Some(new_path) => {
+                let span = trees[i].span();
+                for (idx, seg) in new_path.iter().enumerate() {
+                    if idx > 0 {
+                        new_trees.push(TokenTree::Token(Token { kind: TokenKind::ModSep, span }));
+                    }
+                    let kind = TokenKind::Ident(Symbol::intern(seg), false);
+                    new_trees.push(TokenTree::Token(Token { kind, span }));
+                }
+            }
+            None => new_trees.extend(trees[i..end].iter().cloned()),
+        }
+        i = end;
+    }
+    new_trees.into_iter().collect()
+}
+
+/// Does `item` make anything public, either itself or, for an extern
+/// block, one of its items?
+fn exports_items(item: &P<Item>) -> bool {
+    match &item.kind {
+        ItemKind::ForeignMod(fm) => fm.items.iter().any(|fi| fi.vis.node.is_pub()),
+        _ => item.vis.node.is_pub(),
+    }
+}
+
+/// Mark a module with `#[macro_use]`, so that the `macro_rules!` macros
+/// moved into it stay visible to the items that follow it
+fn add_macro_use(attrs: &mut Vec<Attribute>) {
+    if !attr::contains_name(attrs, sym::macro_use) {
+        attrs.extend(mk().single_attr("macro_use").into_attrs());
+    }
+}
+
+/// Insert the synthesized `uses` after the imports at the top of `items`,
+/// ahead of the first other item, instead of at the end of the module
+fn insert_uses<I: IntoIterator<Item = P<Item>>>(items: &mut Vec<P<Item>>, uses: I) {
+    let idx = items
+        .iter()
+        .position(|item| item_kind_rank(item) != 0)
+        .unwrap_or(items.len());
+    items.splice(idx..idx, uses);
+}
+
+/// ABI of an extern block
+fn foreign_mod_abi(fm: &ForeignMod) -> Abi {
+    fm.abi
+        .and_then(|abi| abi::lookup(&abi.symbol.as_str()))
+        .unwrap_or(Abi::Rust)
+}
+
+/// Text of a doc comment or `#[doc = "..."]` attribute
+fn doc_text(attr: &Attribute) -> Option<String> {
+    match &attr.kind {
+        AttrKind::DocComment(comment) => Some(comment.as_str().to_string()),
+        AttrKind::Normal(_) if attr.has_name(sym::doc) => {
+            attr.value_str().map(|value| value.as_str().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Path of the module containing the item at `path`
+fn header_module_path(path: &Path) -> String {
+    let segments = &path.segments[..path.segments.len().saturating_sub(1)];
+    segments_to_string(segments)
+}
+
+/// Render module path segments as a `::`-separated string
+fn segments_to_string(segments: &[PathSegment]) -> String {
+    segments
+        .iter()
+        .map(|seg| seg.ident.as_str().to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// The non-root segments of `path` as strings
+fn path_strings(path: &Path) -> Vec<String> {
+    path.segments
+        .iter()
+        .filter(|seg| seg.ident.name != kw::PathRoot)
+        .map(|seg| seg.ident.as_str().to_string())
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+struct HeaderInfo {
+    ident: Ident,
+    path: String,
+    include_line: usize,
+    /// Paths of any further, different headers the header module was marked
+    /// with, in source order
+    other_paths: Vec<String>,
+}
+
+impl HeaderInfo {
+    fn new(ident: Ident, path: String, include_line: usize) -> Self {
+        Self {
+            ident,
+            path,
+            include_line,
+            other_paths: vec![],
+        }
+    }
+
+    /// All header paths of the header module, starting with `path`
+    fn paths(&self) -> impl Iterator<Item = &String> {
+        iter::once(&self.path).chain(&self.other_paths)
+    }
+
+    /// Normalized name of the module this header belongs to: the file stem
+    /// of its path, or the name of its header module without the `_h`
+    /// suffix if the path has none
+    fn module_name(&self) -> String {
+        self.module_name_of(&self.path)
+    }
+
+    /// Like `module_name`, but for another path of the same header module
+    fn module_name_of(&self, path: &str) -> String {
+        header_module_name(path, self.ident)
+    }
+}
+
+impl ModuleInfo {
+    fn new(orig_ident: Ident, unique_ident: Ident, id: NodeId) -> Self {
+        Self {
+            orig_ident,
+            unique_ident,
+            id,
+            path: vec![mk().path_segment(kw::Crate), mk().path_segment(unique_ident.name)],
+            new: true,
+            parent: None,
+            has_main: false,
+            header_lines: HashMap::new(),
+            headers: HashSet::new(),
+            items: PerNS::default(),
+        }
+    }
+
+    /// Create a new module named `ident` inside `parent`
+    fn new_child(ident: Ident, id: NodeId, parent: &ModuleInfo) -> Self {
+        let mut path = parent.path.clone();
+        path.push(mk().path_segment(ident.name));
+        Self {
+            path,
+            parent: Some(parent.id),
+            ..Self::new(ident, ident, id)
+        }
+    }
+
+    /// Does `name` refer to this module, either by its path (with or without
+    /// a leading `crate::`) or by its unique name?
+    fn is_named(&self, name: &str) -> bool {
+        let path = segments_to_string(&self.path);
+        path == name
+            || path.trim_start_matches("crate::") == name
+            || self.unique_ident.as_str() == name
+    }
+
+    /// Create a ModuleInfo from a module `Item`
+    fn from_item(item: &Item, cx: &RefactorCtxt, header_attr: &str) -> Result<Self, ReorganizeError> {
+        let module = expect!([&item.kind] ItemKind::Mod(m) => m);
+        let mut has_main = false;
+        let mut header_lines: HashMap<Ident, usize> = HashMap::new();
+        let mut headers = HashSet::new();
+        let def_id = cx.node_def_id(item.id);
+        let path = cx.def_path(def_id);
+        for i in &module.items {
+            match &i.kind {
+                ItemKind::Fn(..) => {
+                    if i.ident.as_str() == "main" {
+                        has_main = true;
+                    }
+                }
+                ItemKind::Mod(..) => {
+                    let mod_headers = parse_source_headers(&i.attrs, header_attr)?;
+                    let line = match mod_headers.first() {
+                        Some(&(_, line)) => line,
+                        None => return Err(ReorganizeError::MalformedHeaderSrc(i.span)),
+                    };
+                    headers.extend(mod_headers.into_iter().map(|(path, _)| path));
+                    if header_lines.insert(i.ident, line).is_some() {
+                        return Err(ReorganizeError::NameCollision(i.ident, item.ident, i.span));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self {
+            orig_ident: item.ident,
+            unique_ident: item.ident,
+            id: item.id,
+            path: path.segments,
+            new: false,
+            parent: None,
+            has_main,
+            header_lines,
+            headers,
+            items: PerNS::default(),
+        })
+    }
+}
+
+#[derive(Debug)]
 struct MovedDecl {
     kind: DeclKind,
     def_id: DefId,
@@ -1039,657 +4061,2143 @@ struct MovedDecl {
     parent_header: HeaderInfo,
 }
 
-impl MovedDecl {
-    fn new<T>(decl: T, def_id: DefId, namespace: Namespace, parent_header: HeaderInfo) -> Self
-    where
-        T: Into<DeclKind>,
-    {
-        let kind: DeclKind = decl.into();
-        let loc: Option<SrcLoc> = kind
-            .attrs()
+impl MovedDecl {
+    /// Module named by a `#[c2rust::prefer_module = "..."]` hint on the
+    /// declaration, if any
+    fn preferred_module(&self) -> Option<Symbol> {
+        self.kind
+            .attrs()
+            .iter()
+            .find(|attr| is_c2rust_attr(attr, "prefer_module"))
+            .and_then(|attr| attr.value_str())
+    }
+
+    fn new<T>(decl: T, def_id: DefId, namespace: Namespace, parent_header: HeaderInfo) -> Self
+    where
+        T: Into<DeclKind>,
+    {
+        let kind: DeclKind = decl.into();
+        let loc: Option<SrcLoc> = kind
+            .attrs()
+            .iter()
+            .find(|attr| is_c2rust_attr(attr, "src_loc"))
+            .map(|l| l.into());
+
+        Self {
+            kind,
+            def_id,
+            namespace,
+            loc,
+            parent_header,
+        }
+    }
+
+    fn is_foreign(&self) -> bool {
+        match &self.kind {
+            DeclKind::ForeignItem(..) => true,
+            _ => false,
+        }
+    }
+
+    fn visibility(&self) -> &Visibility {
+        match &self.kind {
+            DeclKind::ForeignItem(item, _) => &item.vis,
+            DeclKind::Item(item) => &item.vis,
+        }
+    }
+
+    /// Restate a restricted visibility of this declaration, given relative
+    /// to the header module it was declared in, for its new module at
+    /// `dest`, so that it stays visible in the same places. Items that were
+    /// visible exactly in `dest` become private there.
+    fn rebase_visibility(&mut self, cx: &RefactorCtxt, dest: &[PathSegment]) {
+        let vis = match &mut self.kind {
+            DeclKind::ForeignItem(item, _) => &mut item.vis,
+            DeclKind::Item(item) => match item.kind {
+                ItemKind::Use(..) => return,
+                _ => &mut item.vis,
+            },
+        };
+        let scope_path = match_or!([&vis.node] VisibilityKind::Restricted { path, .. } => path; return);
+
+        // Resolve the scope against the old module
+        let mut old_mod = path_strings(&cx.def_path(self.def_id));
+        old_mod.pop();
+        let mut segments = scope_path.segments.iter().map(|seg| seg.ident.name).peekable();
+        let mut scope = match segments.peek() {
+            Some(&name) if name == kw::Crate => {
+                segments.next();
+                vec![kw::Crate.to_string()]
+            }
+            Some(&name) if name == kw::SelfLower || name == kw::Super => old_mod,
+            _ => vec![kw::Crate.to_string()],
+        };
+        for name in segments {
+            if name == kw::Super {
+                scope.pop();
+            } else if name != kw::SelfLower {
+                scope.push(name.to_string());
+            }
+        }
+
+        let dest: Vec<String> = dest.iter().map(|seg| seg.ident.to_string()).collect();
+        let common = scope
+            .iter()
+            .zip(dest.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        scope.truncate(common);
+        vis.node = if scope == dest {
+            VisibilityKind::Inherited
+        } else if scope.len() <= 1 {
+            VisibilityKind::Crate(CrateSugar::PubCrate)
+        } else {
+            VisibilityKind::Restricted {
+                path: P(mk().path(scope)),
+                id: DUMMY_NODE_ID,
+            }
+        };
+    }
+
+    fn join_visibility(&mut self, vis: &VisibilityKind) {
+        match &mut self.kind {
+            DeclKind::ForeignItem(item, _) => item.vis.node = join_visibility(&item.vis.node, vis),
+            DeclKind::Item(item) => item.vis.node = join_visibility(&item.vis.node, vis),
+        }
+    }
+
+    /// Add the doc comments and `#[doc]` attributes among `attrs`, taken
+    /// from a duplicate of this declaration that is being dropped, that this
+    /// declaration doesn't already have. They go right after its own docs.
+    fn join_docs(&mut self, attrs: &[Attribute]) {
+        let own_docs: HashSet<String> = self.kind.attrs().iter().filter_map(doc_text).collect();
+        let new_docs: Vec<Attribute> = attrs
+            .iter()
+            .filter(|attr| doc_text(attr).map_or(false, |text| !own_docs.contains(&text)))
+            .cloned()
+            .collect();
+        if new_docs.is_empty() {
+            return;
+        }
+        self.kind.visit_attrs(|attrs| {
+            let pos = attrs
+                .iter()
+                .rposition(|attr| doc_text(attr).is_some())
+                .map_or(0, |idx| idx + 1);
+            attrs.splice(pos..pos, new_docs);
+        });
+    }
+
+    /// Rename this declaration to `ident`. Extern functions and statics
+    /// keep linking against their original symbol.
+    fn rename(&mut self, ident: Ident) {
+        match &mut self.kind {
+            DeclKind::ForeignItem(item, _) => {
+                let needs_link_name = !matches!([item.kind] ForeignItemKind::Ty)
+                    && !attr::contains_name(&item.attrs, sym::link_name);
+                if needs_link_name {
+                    let link_name = mk().str_attr("link_name", item.ident.name).into_attrs();
+                    item.attrs.extend(link_name);
+                }
+                item.ident = ident;
+            }
+            DeclKind::Item(item) => match &mut item.kind {
+                ItemKind::Use(tree) => {
+                    if let UseTreeKind::Simple(rename, ..) = &mut tree.kind {
+                        *rename = Some(ident);
+                    }
+                }
+                _ => item.ident = ident,
+            },
+        }
+    }
+
+    fn ident(&self) -> Ident {
+        match &self.kind {
+            DeclKind::ForeignItem(item, _) => item.ident,
+            DeclKind::Item(item) => if let ItemKind::Use(tree) = &item.kind {
+                tree.ident()
+            } else {
+                item.ident
+            },
+        }
+    }
+
+    fn span(&self) -> Span {
+        match &self.kind {
+            DeclKind::ForeignItem(item, _) => item.span,
+            DeclKind::Item(item) => item.span,
+        }
+    }
+}
+
+impl ToString for MovedDecl {
+    fn to_string(&self) -> String {
+        match &self.kind {
+            DeclKind::ForeignItem(item, _) => foreign_item_to_string(item),
+            DeclKind::Item(item) => item_to_string(item),
+        }
+    }
+}
+
+#[derive(Clone, Debug, From)]
+enum DeclKind {
+    Item(P<Item>),
+    ForeignItem(ForeignItem, Abi),
+}
+
+impl HasAttrs for DeclKind {
+    fn attrs(&self) -> &[Attribute] {
+        match self {
+            DeclKind::Item(i) => i.attrs(),
+            DeclKind::ForeignItem(i, _) => i.attrs(),
+        }
+    }
+
+    fn visit_attrs<F: FnOnce(&mut Vec<Attribute>)>(&mut self, f: F) {
+        match self {
+            DeclKind::Item(i) => i.visit_attrs(f),
+            DeclKind::ForeignItem(i, _) => i.visit_attrs(f),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct SrcLoc {
+    line: usize,
+    col: usize,
+}
+
+impl From<&Attribute> for SrcLoc {
+    fn from(attr: &Attribute) -> Self {
+        let value_str = attr
+            .value_str()
+            .expect("Expected a value for src_loc attribute")
+            .as_str();
+        let mut iter = value_str.split(':');
+        let line: usize = iter
+            .next()
+            .and_then(|x| x.parse().ok())
+            .expect("Expected a line number in src_loc attribute");
+        let col: usize = iter
+            .next()
+            .and_then(|x| x.parse().ok())
+            .expect("Expected an column number in src_loc attribute");
+        Self { line, col }
+    }
+}
+
+/// Store and de-duplicate header-declared items
+struct HeaderDeclarations<'a, 'tcx: 'a> {
+    cx: &'a RefactorCtxt<'a, 'tcx>,
+    idents: PerNS<IndexMap<Ident, Vec<MovedDecl>>>,
+    unnamed_items: PerNS<Vec<MovedDecl>>,
+    /// Indices into `unnamed_items` of the types we match by content, by
+    /// the `unnamed_hash` of their kind, so that `find_item` only compares
+    /// a new unnamed type against the ones that can be equivalent to it
+    unnamed_types: PerNS<HashMap<u64, Vec<usize>>>,
+    /// Indices into `unnamed_items` of the foreign types, which `find_item`
+    /// compares against every new unnamed type
+    unnamed_foreign_types: PerNS<Vec<usize>>,
+    matching_defs: HashMap<DefId, DefId>,
+    /// Names of the traits derived for each header type, taken from the
+    /// derived impls we drop
+    derives: HashMap<DefId, BTreeSet<String>>,
+    /// Merge foreign items with the same name and ABI even if their
+    /// signatures differ
+    merge_foreign_by_name: bool,
+    // // Set of imported definition NodeIds that must be made pub(crate) at least
+    // imports: HashSet<HirId>,
+
+}
+impl<'a, 'tcx> Extend<MovedDecl> for HeaderDeclarations<'a, 'tcx> {
+    fn extend<T: IntoIterator<Item = MovedDecl>>(&mut self, iter: T) {
+        for item in iter {
+            let ident = item.ident();
+            if ident.as_str().contains("C2RustUnnamed") {
+                self.push_unnamed(item);
+            } else {
+                self.idents[item.namespace].entry(ident).or_default().push(item);
+            }
+        }    
+    }
+}
+
+impl<'a, 'tcx> HeaderDeclarations<'a, 'tcx> {
+    pub fn new(cx: &'a RefactorCtxt<'a, 'tcx>) -> Self {
+        Self {
+            cx,
+            idents: PerNS::default(),
+            unnamed_items: PerNS::default(),
+            unnamed_types: PerNS::default(),
+            unnamed_foreign_types: PerNS::default(),
+            matching_defs: HashMap::new(),
+            derives: HashMap::new(),
+            merge_foreign_by_name: false,
+            // imports: HashSet::new(),
+        }
+    }
+
+    /// Add a declaration without a proper name (or a hand-written impl),
+    /// indexing it for `find_item` if it is a type
+    fn push_unnamed(&mut self, decl: MovedDecl) {
+        let ns = decl.namespace;
+        let index = self.unnamed_items[ns].len();
+        match &decl.kind {
+            DeclKind::Item(item) => match item.kind {
+                ItemKind::TyAlias(..)
+                | ItemKind::Struct(..)
+                | ItemKind::Union(..)
+                | ItemKind::Enum(..) => {
+                    self.unnamed_types[ns].entry(unnamed_hash(&item.kind)).or_default().push(index);
+                }
+                _ => {}
+            },
+            DeclKind::ForeignItem(item, _) => {
+                if let ForeignItemKind::Ty = item.kind {
+                    self.unnamed_foreign_types[ns].push(index);
+                }
+            }
+        }
+        self.unnamed_items[ns].push(decl);
+    }
+
+    /// Remove and return declarations matching the specified item definition
+    fn remove_matching_defs<P>(
+        &mut self,
+        namespace: Namespace,
+        ident: Ident,
+        mut predicate: P,
+    ) -> Vec<DefId>
+        where P: FnMut(&DeclKind) -> bool
+    {
+        assert!(ident.name != kw::Invalid);
+        let mut matches = vec![];
+        if let Some(items) = self.idents[namespace].get_mut(&ident) {
+            items.retain(|decl| {
+                match &decl.kind {
+                    DeclKind::Item(decl) => {
+                        // Don't match use statements
+                        if let ItemKind::Use(_) = decl.kind {
+                            return true;
+                        }
+                    }
+                    _ => {}
+                }
+                if predicate(&decl.kind) {
+                    matches.push(decl.def_id);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        matches
+    }
+
+    /// Add an item into the module. If it has a name conflict with an existing
+    /// item, choose the definition item over any declarations.
+    pub fn insert_item(
+        &mut self,
+        mut item: P<Item>,
+        parent_header: HeaderInfo,
+    ) -> bool {
+        let namespace = self.cx.item_namespace(&item);
+        let new_def_id = self.cx.node_def_id(item.id);
+        let ident = if let ItemKind::Use(tree) = &item.kind {
+            tree.ident()
+        } else {
+            item.ident
+        };
+        match &item.kind {
+            // We have to disambiguate anonymous items by contents,
+            // since we don't have a proper Ident.
+
+            // Uses are split into simple uses (no brackets) and added into
+            // ident_map.
+            ItemKind::Use(tree) if is_nested(tree) => {
+                for u in split_uses(item).into_iter() {
+                    self.insert_item(u, parent_header.clone());
+                }
+                true
+            }
+
+            // Hand-written impls move along with the types defined next to
+            // them. Derived impls are expanded from macros anyway, so we
+            // don't keep those, but we remember which traits were derived.
+            ItemKind::Impl(..) if !item.span.from_expansion() => {
+                let new_item = MovedDecl::new(item, new_def_id, Namespace::TypeNS, parent_header);
+                self.push_unnamed(new_item);
+                true
+            }
+            ItemKind::Impl(_, _, _, _, Some(trait_ref), self_ty, _) => {
+                if attr::contains_name(&item.attrs, Symbol::intern("automatically_derived")) {
+                    if let Some(def_id) = self.cx.try_resolve_ty(self_ty) {
+                        let name = trait_ref.path.segments.last().unwrap().ident.to_string();
+                        self.derives.entry(def_id).or_default().insert(name);
+                    }
+                }
+                true
+            }
+            ItemKind::Impl(..) => true,
+
+            // We collect all ForeignItems and later filter out any idents
+            // defined in ident_map after processing the whole list of items.
+            ItemKind::ForeignMod(..) => {
+                let f = expect!([item.into_inner().kind] ItemKind::ForeignMod(f) => f);
+                let abi = foreign_mod_abi(&f);
+                for item in f.items {
+                    self.insert_foreign_item(item, abi, parent_header.clone());
+                }
+                true
+            }
+
+            // We disambiguate named items by their names and check that
+            // we don't have any items with the same name but different
+            // contents.
+
+            _ => {
+                let unnamed = ident.as_str().contains("C2RustUnnamed");
+                let def_id_mapping = match self.find_item(&item, namespace.unwrap()) {
+                    ContainsDecl::NotContained => {
+                        let new_item = MovedDecl::new(item, new_def_id, namespace.unwrap(), parent_header);
+                        if unnamed {
+                            self.push_unnamed(new_item);
+                        } else {
+                            self.idents[namespace.unwrap()]
+                                .entry(ident)
+                                .or_default()
+                                .push(new_item);
+                        }
+                        None
+                    }
+
+                    ContainsDecl::Definition(existing) => {
+                        existing.join_visibility(&item.vis.node);
+                        existing.join_docs(&item.attrs);
+                        Some((new_def_id, existing.def_id))
+                    }
+
+                    ContainsDecl::Use(existing) => {
+                        let existing_def_id = existing.def_id;
+                        existing.join_visibility(&item.vis.node);
+                        *existing = MovedDecl::new(item, new_def_id, namespace.unwrap(), parent_header);
+                        Some((existing_def_id, new_def_id))
+                    }
+
+                    ContainsDecl::Equivalent(existing) if existing.is_foreign() => {
+                        let existing_def_id = existing.def_id;
+                        let existing_attrs = existing.kind.attrs().to_vec();
+                        item.vis.node = join_visibility(&existing.visibility().node, &item.vis.node);
+                        *existing = MovedDecl::new(item, new_def_id, namespace.unwrap(), parent_header);
+                        existing.join_docs(&existing_attrs);
+                        Some((existing_def_id, new_def_id))
+                    }
+
+                    ContainsDecl::Equivalent(existing) => {
+                        existing.join_docs(&item.attrs);
+                        Some((new_def_id, existing.def_id))
+                    }
+                };
+                if let Some((old, new)) = def_id_mapping {
+                    self.matching_defs.insert(old, new);
+                }
+                true
+            }
+        }
+    }
+
+    fn insert_foreign_item(
+        &mut self,
+        item: ForeignItem,
+        abi: Abi,
+        parent_header: HeaderInfo,
+    ) {
+        let new_def_id = self.cx.node_def_id(item.id);
+        let ident = item.ident;
+        let namespace = self.cx.foreign_item_namespace(&item).unwrap();
+        let unnamed = ident.as_str().contains("C2RustUnnamed");
+        let def_id_mapping = match self.find_foreign_item(&item, abi) {
+            ContainsDecl::NotContained => {
+                let new_item = MovedDecl::new(
+                    (item, abi),
+                    new_def_id,
+                    namespace,
+                    parent_header.clone(),
+                );
+                if unnamed {
+                    self.push_unnamed(new_item);
+                } else {
+                    self.idents[namespace]
+                        .entry(ident)
+                        .or_default()
+                        .push(new_item);
+                }
+                None
+            }
+
+            ContainsDecl::Definition(existing) => {
+                let existing_def_id = existing.def_id;
+                let existing_attrs = existing.kind.attrs().to_vec();
+                *existing = MovedDecl::new(
+                    (item, abi),
+                    new_def_id,
+                    namespace,
+                    parent_header.clone(),
+                );
+                existing.join_docs(&existing_attrs);
+                Some((existing_def_id, new_def_id))
+            }
+
+            ContainsDecl::Equivalent(existing) => {
+                existing.join_visibility(&item.vis.node);
+                existing.join_docs(&item.attrs);
+                Some((new_def_id, existing.def_id))
+            }
+
+            ContainsDecl::Use(..) => panic!("Foreign items cannot be use statements"),
+        };
+        if let Some((old, new)) = def_id_mapping {
+            self.matching_defs.insert(old, new);
+        }
+    }
+
+    /// Finalize and return a de-duplicated Vec of items
+    fn into_items(self, st: &CommandState, info: &ModuleInfo, options: &Options) -> Vec<P<Item>> {
+        fn make_header_comment(last_mod: Option<Ident>, next_mod: Ident) -> Comment {
+            let mut lines = vec![];
+            if let Some(last_mod) = last_mod {
+                lines.push(format!(
+                    "// ================ END {} ================",
+                    last_mod.as_str(),
+                ));
+            }
+            lines.push(format!(
+                "// =============== BEGIN {} ================",
+                next_mod.as_str(),
+            ));
+            Comment {
+                style: CommentStyle::Isolated,
+                lines,
+                pos: BytePos(0),
+            }
+        }
+
+        fn make_group_comment(header_path: &str) -> Comment {
+            let file_name = header_path.rsplit('/').next().unwrap_or(header_path);
+            Comment {
+                style: CommentStyle::Isolated,
+                lines: vec![format!("// from {}", file_name)],
+                pos: BytePos(0),
+            }
+        }
+
+        let Self {
+            idents,
+            unnamed_items,
+            ..
+        } = self;
+        let group_comments = options.group_comments;
+
+        let mut all_items = unnamed_items
+            .type_ns
+            .into_iter()
+            .chain(unnamed_items.value_ns.into_iter())
+            .chain(idents.type_ns.into_iter().map(|(_, v)| v.lone()))
+            .chain(idents.value_ns.into_iter().map(|(_, v)| v.lone()))
+            .collect::<Vec<_>>();
+
+        if group_comments {
+            // Emit one group per header file, in the order the headers were
+            // included.
+            all_items.sort_by(|a, b| {
+                let key_a = (a.parent_header.include_line, &a.parent_header.path);
+                let key_b = (b.parent_header.include_line, &b.parent_header.path);
+                key_a.cmp(&key_b)
+                    .then_with(|| a.loc.cmp(&b.loc))
+                    .then_with(|| a.span().lo().cmp(&b.span().lo()))
+            });
+        } else {
+            all_items.sort_by(|a, b| {
+                if a.parent_header.ident == b.parent_header.ident {
+                    a.loc.cmp(&b.loc).then_with(|| a.span().lo().cmp(&b.span().lo()))
+                } else {
+                    let line_a = info.header_lines.get(&a.parent_header.ident).unwrap_or(&0);
+                    let line_b = info.header_lines.get(&b.parent_header.ident).unwrap_or(&0);
+                    if line_a != line_b {
+                        line_a.cmp(line_b)
+                    } else {
+                        a.parent_header.ident.as_str().cmp(&b.parent_header.ident.as_str())
+                    }
+                }
+            });
+        }
+
+        let mut items: Vec<P<Item>> = Vec::new();
+        // Extern blocks are emitted in the order their ABIs first appear, so
+        // that the output doesn't change from run to run
+        let mut foreign_items: IndexMap<Abi, Vec<ForeignItem>> = IndexMap::new();
+        let mut last_item_mod = None;
+        let mut last_foreign_item_mod = None;
+        let mut last_item_header: Option<String> = None;
+        let mut last_foreign_item_header: Option<String> = None;
+        for item in all_items {
+            let cur_mod_name = item.parent_header.ident;
+            let cur_header = item.parent_header.path.clone();
+            match item.kind {
+                DeclKind::Item(i) => {
+                    if group_comments {
+                        if last_item_header.as_ref() != Some(&cur_header) {
+                            st.add_comment(i.id, make_group_comment(&cur_header));
+                            last_item_header = Some(cur_header);
+                        }
+                    } else if last_item_mod != Some(cur_mod_name) {
+                        st.add_comment(i.id, make_header_comment(last_item_mod, cur_mod_name));
+                        last_item_mod = Some(cur_mod_name);
+                    }
+                    items.push(i);
+                }
+                DeclKind::ForeignItem(fi, abi) => {
+                    if group_comments {
+                        if last_foreign_item_header.as_ref() != Some(&cur_header) {
+                            st.add_comment(fi.id, make_group_comment(&cur_header));
+                            last_foreign_item_header = Some(cur_header);
+                        }
+                    } else if last_foreign_item_mod != Some(cur_mod_name) {
+                        st.add_comment(
+                            fi.id,
+                            make_header_comment(last_foreign_item_mod, cur_mod_name),
+                        );
+                        last_foreign_item_mod = Some(cur_mod_name);
+                    }
+                    foreign_items.entry(abi).or_default().push(fi);
+                }
+            }
+        }
+
+        let foreign_count: usize = foreign_items.values().map(|items| items.len()).sum();
+        let mut foreign_mods: Vec<P<Item>> = foreign_items
+            .into_iter()
+            .map(|(abi, items)| mk().extern_(abi).foreign_items(items))
+            .collect();
+
+        // Keep large amounts of raw FFI declarations apart from the rest of
+        // the module
+        if options.ffi_submodule_threshold.map_or(false, |n| foreign_count > n) {
+            for foreign_mod in &mut foreign_mods {
+                let fm = expect!([&mut foreign_mod.kind] ItemKind::ForeignMod(fm) => fm);
+                for fi in &mut fm.items {
+                    if let VisibilityKind::Inherited = fi.vis.node {
+                        fi.vis.node = VisibilityKind::Restricted {
+                            path: P(mk().path("super")),
+                            id: DUMMY_NODE_ID,
+                        };
+                    }
+                }
+            }
+            // Types the declarations mention may be imported into the parent
+            let mut ffi_items = vec![mk().use_glob_item("super")];
+            ffi_items.append(&mut foreign_mods);
+            foreign_mods = vec![
+                mk().pub_().use_glob_item(vec!["self", "ffi"]),
+                mk().pub_().mod_item("ffi", mk().mod_(ffi_items)),
+            ];
+        }
+
+        foreign_mods
+            .into_iter()
+            .chain(items.into_iter())
+            .collect()
+    }
+
+    fn find_item<'b>(&'b mut self, item: &Item, namespace: Namespace) -> ContainsDecl<'b> {
+        let ident = if let ItemKind::Use(tree) = &item.kind {
+            tree.ident()
+        } else {
+            item.ident
+        };
+        assert!(ident.name != kw::Invalid);
+
+        if ident.as_str().contains("C2RustUnnamed") {
+            // Only the types with the same `unnamed_hash` and the foreign
+            // types can match. Check them in the order they were added, so
+            // the first equivalent declaration still wins.
+            let mut candidates = self.unnamed_foreign_types[namespace].clone();
+            if let Some(indices) = self.unnamed_types[namespace].get(&unnamed_hash(&item.kind)) {
+                candidates.extend(indices);
+                candidates.sort_unstable();
+            }
+            let unnamed_items = &mut self.unnamed_items[namespace];
+            let found = candidates.into_iter().find(|&index| {
+                match &unnamed_items[index].kind {
+                    DeclKind::Item(existing_item) => match &existing_item.kind {
+                        // Does the new item match the existing item, except
+                        // for unnamed names?
+                        ItemKind::TyAlias(..)
+                        | ItemKind::Struct(..)
+                        | ItemKind::Union(..)
+                        | ItemKind::Enum(..) => item.kind.unnamed_equiv(&existing_item.kind),
+
+                        // TODO?
+                        _ => false,
+                    },
+
+                    // This item may be equivalent to an existing foreign
+                    // item, modulo visibility.
+                    DeclKind::ForeignItem(existing_foreign, _) => match &existing_foreign.kind {
+                        ForeignItemKind::Ty => foreign_equiv(&existing_foreign, &item),
+                        _ => false,
+                    },
+                }
+            });
+            if let Some(index) = found {
+                return ContainsDecl::Equivalent(&mut unnamed_items[index]);
+            }
+
+            return ContainsDecl::NotContained;
+        }
+
+        if let Some(existing_decls) = self.idents[namespace].get_mut(&ident) {
+            for existing_decl in existing_decls {
+                match &existing_decl.kind {
+                    DeclKind::Item(existing_item) => match (&existing_item.kind, &item.kind) {
+                        // Replace a use with a real definition
+                        (ItemKind::Use(..), _) => {
+                            return ContainsDecl::Use(existing_decl);
+                        }
+
+                        // Make sure we use the widest visibility for this item
+                        (_, ItemKind::Use(..)) => {
+                            return ContainsDecl::Definition(existing_decl);
+                        }
+
+                        // Otherwise make sure these items are structurally
+                        // equivalent.
+                        _ => {
+                            if compatible_items(self.cx, &item, &existing_item) {
+                                return ContainsDecl::Equivalent(existing_decl);
+                            }
+                        }
+                    }
+
+                    DeclKind::ForeignItem(existing_foreign, _) => {
+                        if let ItemKind::Use(..) = item.kind {
+                            // If the import refers to an existing foreign item, do
+                            // not replace it.
+                            let path = self.cx.resolve_use_id(item.id);
+                            if let Some(did) = path.res.opt_def_id() {
+                                if let Some(Node::ForeignItem(_)) = self.cx.hir_map().get_if_local(did)
+                                {
+                                    return ContainsDecl::Definition(existing_decl);
+                                }
+                            }
+
+                            return ContainsDecl::Equivalent(existing_decl);
+                        }
+                        if foreign_equiv(&existing_foreign, &item) {
+                            return ContainsDecl::Equivalent(existing_decl);
+                        }
+                    }
+                }
+                trace!("{:?} and {:?} share idents, but are not compatible", item, existing_decl);
+            }
+        }
+
+        ContainsDecl::NotContained
+    }
+
+    fn find_foreign_item<'b>(&'b mut self, item: &ForeignItem, abi: Abi) -> ContainsDecl<'b> {
+        let ns = match &item.kind {
+            ForeignItemKind::Fn(..) | ForeignItemKind::Static(..) => Namespace::ValueNS,
+            ForeignItemKind::Ty => Namespace::TypeNS,
+            ForeignItemKind::Macro(..) => unimplemented!(),
+        };
+        let ident = item.ident;
+        assert!(ident.name != kw::Invalid);
+
+        if let Some(existing_decls) = self.idents[ns].get_mut(&ident) {
+            for existing_decl in existing_decls {
+                match &existing_decl.kind {
+                    DeclKind::Item(existing_item) => {
+                        if foreign_equiv(&item, &existing_item) {
+                            return ContainsDecl::Equivalent(existing_decl)
+                        } else if let ItemKind::Use(_) = existing_item.kind {
+                            // A use takes precedence over a foreign declaration
+                            // unless the use refers to the foreign declaration are
+                            // attempting to insert.
+                            let path = self.cx.resolve_use_id(existing_item.id);
+                            if let Some(did) = path.res.opt_def_id() {
+                                if let Some(Node::ForeignItem(_)) = self.cx.hir_map().get_if_local(did)
+                                {
+                                    return ContainsDecl::Definition(existing_decl);
+                                }
+                            }
+                            return ContainsDecl::Equivalent(existing_decl);
+                        }
+                    }
+
+                    DeclKind::ForeignItem(existing_foreign, existing_abi) => {
+                        // Declarations linking against different symbols
+                        // (e.g. through `#[link_name]`) are kept apart
+                        if *existing_abi != abi || foreign_symbol(existing_foreign) != foreign_symbol(item) {
+                            continue;
+                        }
+                        let matches_existing = match (&existing_foreign.kind, &item.kind) {
+                            (ForeignItemKind::Fn(decl1, _), ForeignItemKind::Fn(decl2, _)) => {
+                                compatible_prototypes(self.cx, decl1, decl2)
+                            }
+
+                            _ => existing_foreign.ast_equiv(&item),
+                        };
+                        if matches_existing {
+                            return ContainsDecl::Equivalent(existing_decl);
+                        }
+                        if self.merge_foreign_by_name {
+                            warn_signature_mismatch(self.cx, existing_foreign, item);
+                            return ContainsDecl::Equivalent(existing_decl);
+                        }
+                    }
+                }
+            }
+        }
+
+        ContainsDecl::NotContained
+    }
+}
+
+enum ContainsDecl<'a> {
+    NotContained,
+
+    /// The module contains a declaration or definition that is equivalent to
+    /// the given item.
+    Equivalent(&'a mut MovedDecl),
+
+    /// The module contains a definition that can replace the given item.
+    Definition(&'a mut MovedDecl),
+
+    /// The module contains a use of the given item.
+    Use(&'a mut MovedDecl),
+}
+
+/// Warn that `dropped` was merged into the extern declaration `kept` of the
+/// same name despite their different signatures, for `merge_foreign_by_name`
+fn warn_signature_mismatch(cx: &RefactorCtxt, kept: &ForeignItem, dropped: &ForeignItem) {
+    let msg = format!(
+        "extern declaration of {} merged into another one despite a different signature",
+        dropped.ident,
+    );
+    cx.session()
+        .struct_span_warn(dropped.span, &msg)
+        .span_note(kept.span, &format!("kept this declaration: {}", foreign_item_to_string(kept)))
+        .emit();
+}
+
+/// Are `item1` and `item2` compatible declarations of the same C type or
+/// value? Without typeck results (at phase 2) this falls back to comparing
+/// their syntax.
+fn compatible_items(cx: &RefactorCtxt, item1: &Item, item2: &Item) -> bool {
+    match (&item1.kind, &item2.kind) {
+        // Constants and statics are only interchangeable if they have the
+        // same value, however their initializers are spelled
+        (ItemKind::Const(ty1, init1), ItemKind::Const(ty2, init2)) => {
+            compatible_value_types(cx, item1, ty1, item2, ty2) && same_initializer(init1, init2)
+        }
+        (ItemKind::Static(ty1, mutbl1, init1), ItemKind::Static(ty2, mutbl2, init2)) => {
+            mutbl1 == mutbl2
+                && is_exported(item1) == is_exported(item2)
+                && export_symbol(item1) == export_symbol(item2)
+                && compatible_value_types(cx, item1, ty1, item2, ty2)
+                && same_initializer(init1, init2)
+        }
+        // Types with different layouts are different types to C, however
+        // alike their fields are
+        (ItemKind::Struct(..), _) | (ItemKind::Enum(..), _) | (ItemKind::Union(..), _)
+            if !same_repr(cx, item1, item2) => false,
+        // A generic alias and a plain one can't stand in for each other, even
+        // if they expand to the same type
+        (ItemKind::TyAlias(_, generics1), ItemKind::TyAlias(_, generics2))
+            if !generics1.ast_equiv(generics2) => false,
+        _ if cx.has_ty_ctxt() => cx.compatible_types(item1, item2),
+        _ => item1.kind.ast_equiv(&item2.kind),
+    }
+}
+
+/// Do `item1` and `item2` have the same `#[repr(...)]` hints, in any order?
+fn same_repr(cx: &RefactorCtxt, item1: &Item, item2: &Item) -> bool {
+    let parse_sess = &cx.session().parse_sess;
+    let repr_hints = |item: &Item| {
+        item.attrs
             .iter()
-            .find(|attr| is_c2rust_attr(attr, "src_loc"))
-            .map(|l| l.into());
+            .flat_map(|attr| attr::find_repr_attrs(parse_sess, attr))
+            .collect::<Vec<_>>()
+    };
+    let (hints1, hints2) = (repr_hints(item1), repr_hints(item2));
+    hints1.iter().all(|hint| hints2.contains(hint)) && hints2.iter().all(|hint| hints1.contains(hint))
+}
 
-        Self {
-            kind,
-            def_id,
-            namespace,
-            loc,
-            parent_header,
+/// Do the const or static items `item1` and `item2` have the same type?
+fn compatible_value_types(cx: &RefactorCtxt, item1: &Item, ty1: &Ty, item2: &Item, ty2: &Ty) -> bool {
+    if cx.has_ty_ctxt() {
+        if let (Some(ty1), Some(ty2)) = (cx.opt_node_type(item1.id), cx.opt_node_type(item2.id)) {
+            return cx.structural_eq_tys(ty1, ty2);
         }
     }
+    ty1.unnamed_equiv(ty2)
+}
 
-    fn is_foreign(&self) -> bool {
-        match &self.kind {
-            DeclKind::ForeignItem(..) => true,
-            _ => false,
+/// Value of a constant initializer built from literals, so that `100`,
+/// `0x64`, `100i32` and `(100)` all compare equal
+#[derive(PartialEq, Debug)]
+enum InitValue {
+    /// Integer, as its sign and magnitude
+    Int(bool, u128),
+    /// Bits of a float, widened to `f64`
+    Float(u64),
+    Bool(bool),
+    Char(char),
+    Str(Symbol),
+}
+
+fn init_value(expr: &Expr) -> Option<InitValue> {
+    match &expr.kind {
+        ExprKind::Paren(e) => init_value(e),
+        ExprKind::Lit(lit) => match lit.kind {
+            LitKind::Int(i, _) => Some(InitValue::Int(false, i)),
+            LitKind::Float(f, _) => {
+                let f = f.as_str().parse::<f64>().ok()?;
+                Some(InitValue::Float(f.to_bits()))
+            }
+            LitKind::Bool(b) => Some(InitValue::Bool(b)),
+            LitKind::Char(c) => Some(InitValue::Char(c)),
+            LitKind::Byte(b) => Some(InitValue::Int(false, b as u128)),
+            LitKind::Str(s, _) => Some(InitValue::Str(s)),
+            _ => None,
+        },
+        ExprKind::Unary(UnOp::Neg, e) => match init_value(e)? {
+            InitValue::Int(_, 0) => Some(InitValue::Int(false, 0)),
+            InitValue::Int(neg, i) => Some(InitValue::Int(!neg, i)),
+            InitValue::Float(bits) => Some(InitValue::Float((-f64::from_bits(bits)).to_bits())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Do two const or static initializers evaluate to the same value? Only
+/// literals are evaluated; anything else has to match structurally.
+fn same_initializer(init1: &Expr, init2: &Expr) -> bool {
+    match (init_value(init1), init_value(init2)) {
+        (Some(v1), Some(v2)) => v1 == v2,
+        _ => init1.unnamed_equiv(init2),
+    }
+}
+
+/// Do two extern function declarations have compatible prototypes? Like
+/// `compatible_items`, this compares syntax when there are no typeck results.
+fn compatible_prototypes(cx: &RefactorCtxt, decl1: &FnDecl, decl2: &FnDecl) -> bool {
+    if cx.has_ty_ctxt() {
+        cx.compatible_fn_prototypes(decl1, decl2)
+    } else {
+        decl1.inputs.len() == decl2.inputs.len()
+            && decl1.inputs.iter().zip(&decl2.inputs).all(|(arg1, arg2)| arg1.ty.ast_equiv(&arg2.ty))
+            && decl1.output.ast_equiv(&decl2.output)
+    }
+}
+
+/// Returns true if the given ForeignItem can be a declaration for the given
+/// Item definition.
+fn foreign_equiv(foreign: &ForeignItem, item: &Item) -> bool {
+    match (&foreign.kind, &item.kind) {
+        // We should never encounter a foreign function with the same name but a
+        // different declaration. Nonetheless, the FnDecls in rust might be
+        // slightly different (param name, mutability), so we can't do an
+        // ast_equiv on the FnDecl. Might be worth writing a custom comparison
+        // for a sanity check, but not doing that right now. The declaration
+        // has to be of the symbol the definition exports, though.
+        (ForeignItemKind::Fn(..), ItemKind::Fn(..)) => foreign_symbol(foreign) == export_symbol(item),
+
+        (ForeignItemKind::Static(frn_ty, _frn_mutbl), ItemKind::Static(ty, _mutbl, _)) => {
+            if foreign_symbol(foreign) != export_symbol(item) {
+                return false;
+            }
+            if frn_ty.ast_equiv(&ty) {
+                return true;
+            }
+
+            match (&frn_ty.kind, &ty.kind) {
+                // An extern array declaration of any length matches a concrete
+                // definition if they have the same element type
+                (TyKind::Array(frn_elem_ty, _), TyKind::Array(elem_ty, _)) => {
+                    frn_elem_ty.ast_equiv(elem_ty)
+                }
+                _ => false,
+            }
         }
+
+        // If we have a definition for this type name we can assume it is
+        // equivalent.
+        (ForeignItemKind::Ty, ItemKind::TyAlias(..))
+        | (ForeignItemKind::Ty, ItemKind::Enum(..))
+        | (ForeignItemKind::Ty, ItemKind::Struct(..))
+        | (ForeignItemKind::Ty, ItemKind::Union(..)) => true,
+
+        _ => false,
     }
+}
 
-    fn visibility(&self) -> &Visibility {
-        match &self.kind {
-            DeclKind::ForeignItem(item, _) => &item.vis,
-            DeclKind::Item(item) => &item.vis,
+/// Symbol the foreign item `foreign` links against: the value of its
+/// `#[link_name]` attribute, or its name
+fn foreign_symbol(foreign: &ForeignItem) -> Symbol {
+    attr::first_attr_value_str_by_name(&foreign.attrs, sym::link_name).unwrap_or(foreign.ident.name)
+}
+
+/// Symbol the definition `item` is exported under: the value of its
+/// `#[export_name]` attribute, or its name
+fn export_symbol(item: &Item) -> Symbol {
+    attr::first_attr_value_str_by_name(&item.attrs, sym::export_name).unwrap_or(item.ident.name)
+}
+
+/// Find a foreign item with the same name and namespace as `item` in the
+/// extern blocks among `items`
+fn find_foreign_item<'i>(items: &'i [P<Item>], item: &ForeignItem) -> Option<&'i ForeignItem> {
+    let is_type = |fi: &ForeignItem| match fi.kind {
+        ForeignItemKind::Ty => true,
+        _ => false,
+    };
+    items
+        .iter()
+        .filter_map(|i| match_or!([&i.kind] ItemKind::ForeignMod(fm) => Some(fm); None))
+        .flat_map(|fm| fm.items.iter())
+        .find(|fi| fi.ident == item.ident && is_type(fi) == is_type(item))
+}
+
+/// Parse the header path and include line out of the
+/// `#[header_src = "/some/path:line"]` attribute (or the one named by
+/// `header_attr`), if the item has one. If it has several, this is the first.
+fn parse_source_header(
+    attrs: &[Attribute],
+    header_attr: &str,
+) -> Result<Option<(String, usize)>, ReorganizeError> {
+    Ok(parse_source_headers(attrs, header_attr)?.into_iter().next())
+}
+
+/// Parse the header paths and include lines out of all `header_src`
+/// attributes (or the ones named by `header_attr`) of an item, in source
+/// order. Modules merged from several header modules can have more than one.
+fn parse_source_headers(
+    attrs: &[Attribute],
+    header_attr: &str,
+) -> Result<Vec<(String, usize)>, ReorganizeError> {
+    attrs
+        .iter()
+        .filter(|a| is_header_attr(a, header_attr))
+        .map(|attr| parse_header_attr(attr).ok_or_else(|| ReorganizeError::MalformedHeaderSrc(attr.span)))
+        .collect()
+}
+
+/// Shorten paths inside `module` (but not inside its child modules) that
+/// start with `prefix`, the module's own absolute path. Paths in expressions
+/// and types become bare relative paths, while imports get a `self::` prefix.
+fn relativize_module_paths(module: &mut Mod, prefix: &[Ident]) {
+    let mut bindings = BindingCounter::default();
+    let mut own_names = HashSet::new();
+    for item in &module.items {
+        bindings.visit_item(item);
+        match &item.kind {
+            ItemKind::Use(..) | ItemKind::Impl(..) | ItemKind::Mac(..) => {}
+            ItemKind::ForeignMod(fm) => {
+                own_names.extend(fm.items.iter().map(|fi| fi.ident.name));
+            }
+            _ => {
+                own_names.insert(item.ident.name);
+            }
         }
     }
 
-    fn join_visibility(&mut self, vis: &VisibilityKind) {
-        match &mut self.kind {
-            DeclKind::ForeignItem(item, _) => item.vis.node = join_visibility(&item.vis.node, vis),
-            DeclKind::Item(item) => item.vis.node = join_visibility(&item.vis.node, vis),
+    let has_prefix = |path: &Path| {
+        path.segments.len() > prefix.len()
+            && path.segments.iter().zip(prefix).all(|(seg, ident)| seg.ident.name == ident.name)
+    };
+    let unambiguous = |path: &Path| {
+        let name = path.segments[prefix.len()].ident.name;
+        own_names.contains(&name) && bindings.counts.get(&name) == Some(&1)
+    };
+
+    for item in module.items.iter_mut() {
+        match &mut item.kind {
+            ItemKind::Mod(..) => continue,
+            ItemKind::Use(tree) => {
+                if has_prefix(&tree.prefix) {
+                    tree.prefix.segments.drain(..prefix.len());
+                    tree.prefix.segments.insert(0, mk().path_segment(kw::SelfLower));
+                }
+                continue;
+            }
+            _ => {}
         }
+        MutVisitNodes::visit(item, |path: &mut Path| {
+            if has_prefix(path) && unambiguous(path) {
+                path.segments.drain(..prefix.len());
+            }
+        });
     }
+}
 
-    fn ident(&self) -> Ident {
-        match &self.kind {
-            DeclKind::ForeignItem(item, _) => item.ident,
-            DeclKind::Item(item) => if let ItemKind::Use(tree) = &item.kind {
-                tree.ident()
-            } else {
-                item.ident
-            },
+/// Print the module tree and item signatures of `krate`, eliding function
+/// bodies and the initializers of statics and constants.
+fn crate_skeleton(krate: &Crate) -> String {
+    // Elided code is replaced by this placeholder while pretty-printing and
+    // then cut out of the printed text.
+    const ELIDED: &str = "__c2rust_elided";
+    let elided_expr = || mk().ident_expr(ELIDED);
+    let elided_block = || mk().block(vec![mk().expr_stmt(elided_expr())]);
+
+    let mut krate = krate.clone();
+    FlatMapNodes::visit(&mut krate, |mut item: P<Item>| {
+        match &mut item.kind {
+            ItemKind::Fn(_, _, body) => *body = elided_block(),
+            ItemKind::Static(_, _, init) | ItemKind::Const(_, init) => *init = elided_expr(),
+            // Print out-of-line modules in place
+            ItemKind::Mod(m) => m.inline = true,
+            _ => {}
+        }
+        smallvec![item]
+    });
+    FlatMapNodes::visit(&mut krate, |mut item: ImplItem| {
+        match &mut item.kind {
+            ImplItemKind::Method(_, body) => *body = elided_block(),
+            ImplItemKind::Const(_, init) => *init = elided_expr(),
+            _ => {}
+        }
+        smallvec![item]
+    });
+    FlatMapNodes::visit(&mut krate, |mut item: TraitItem| {
+        match &mut item.kind {
+            TraitItemKind::Method(_, Some(body)) => *body = elided_block(),
+            TraitItemKind::Const(_, Some(init)) => *init = elided_expr(),
+            _ => {}
         }
+        smallvec![item]
+    });
+
+    let body_re = Regex::new(&format!(r"\{{\s*{}\s*\}}", ELIDED)).unwrap();
+    let init_re = Regex::new(&format!(r"\s*=\s*{}", ELIDED)).unwrap();
+    let mut skeleton = String::new();
+    for item in &krate.module.items {
+        let text = item_to_string(item);
+        let text = body_re.replace_all(&text, "{ /* ... */ }");
+        skeleton.push_str(&init_re.replace_all(&text, ""));
+        skeleton.push_str("\n\n");
     }
+    skeleton
 }
 
-impl ToString for MovedDecl {
-    fn to_string(&self) -> String {
-        match &self.kind {
-            DeclKind::ForeignItem(item, _) => foreign_item_to_string(item),
-            DeclKind::Item(item) => item_to_string(item),
+/// Pretty-print `before` and `after` as a unified diff of the two
+fn crate_diff(cx: &RefactorCtxt, before: &Crate, after: &Crate) -> String {
+    fn crate_to_string(krate: &Crate) -> String {
+        // Print out-of-line modules in place
+        let mut krate = krate.clone();
+        FlatMapNodes::visit(&mut krate, |mut item: P<Item>| {
+            if let ItemKind::Mod(m) = &mut item.kind {
+                m.inline = true;
+            }
+            smallvec![item]
+        });
+        pprust::to_string(|s| s.print_mod(&krate.module, &krate.attrs))
+    }
+
+    let name = match &cx.session().local_crate_source_file {
+        Some(file) => file.display().to_string(),
+        None => "crate".to_string(),
+    };
+    format!(
+        "--- old/{}\n+++ new/{}\n{}",
+        name,
+        name,
+        unified_diff(&crate_to_string(before), &crate_to_string(after)),
+    )
+}
+
+/// Build the item for the new module `mod_info` containing `items`
+fn new_module_item(mod_info: &ModuleInfo, items: Vec<P<Item>>, inline: bool) -> P<Item> {
+    // Only modules with public contents need to be public; the rest is for
+    // use within the crate. Modules created empty only hold other new
+    // modules, so they stay public for the sake of those.
+    let vis = if items.is_empty() || items.iter().any(exports_items) {
+        "pub"
+    } else {
+        "pub(crate)"
+    };
+    // Give the module the span of its first item with one, so that
+    // diagnostics about it point somewhere useful
+    let span = items
+        .iter()
+        .map(|item| item.span)
+        .find(|span| !span.is_dummy())
+        .unwrap_or(DUMMY_SP);
+    let mut new_mod = mk().span(span).mod_(items);
+    new_mod.inline = inline;
+    mk().vis(vis)
+        .span(span)
+        .id(mod_info.id)
+        .mod_item(mod_info.unique_ident, new_mod)
+}
+
+/// The items of the module with id `id` among `items`, at any depth
+fn find_module_items(items: &mut Vec<P<Item>>, id: NodeId) -> Option<&mut Vec<P<Item>>> {
+    for item in items.iter_mut() {
+        let item_id = item.id;
+        if let ItemKind::Mod(m) = &mut item.kind {
+            if item_id == id {
+                return Some(&mut m.items);
+            }
+            if let Some(found) = find_module_items(&mut m.items, id) {
+                return Some(found);
+            }
         }
     }
+    None
 }
 
-#[derive(Clone, Debug, From)]
-enum DeclKind {
-    Item(P<Item>),
-    ForeignItem(ForeignItem, Abi),
+/// Position of an item's kind in the `kind_order` layout of a module
+fn item_kind_rank(item: &Item) -> u8 {
+    match item.kind {
+        ItemKind::ExternCrate(..) | ItemKind::Use(..) => 0,
+        ItemKind::Struct(..)
+        | ItemKind::Enum(..)
+        | ItemKind::Union(..)
+        | ItemKind::TyAlias(..)
+        | ItemKind::OpaqueTy(..)
+        | ItemKind::Trait(..)
+        | ItemKind::TraitAlias(..) => 1,
+        ItemKind::Const(..) | ItemKind::Static(..) => 2,
+        ItemKind::Fn(..) | ItemKind::ForeignMod(..) => 3,
+        ItemKind::Impl(..) => 4,
+        _ => 5,
+    }
 }
 
-impl HasAttrs for DeclKind {
-    fn attrs(&self) -> &[Attribute] {
-        match self {
-            DeclKind::Item(i) => i.attrs(),
-            DeclKind::ForeignItem(i, _) => i.attrs(),
+/// The names of the segments of a path, for use as a map key. Unlike the
+/// printed path, this doesn't depend on generic arguments or on how the
+/// printer lays the path out.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+struct PathKey(Vec<Symbol>);
+
+impl PathKey {
+    fn new(path: &Path) -> Self {
+        PathKey(path.segments.iter().map(|seg| seg.ident.name).collect())
+    }
+}
+
+/// Where the items of a group of imports come from: the module they were
+/// resolved to, or else the parent path as written
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum UseParent {
+    Module(NodeId),
+    Path(PathKey),
+}
+
+/// Group or sort the simple (`use a::b::c;` or `use a::b::c as d;`) imports
+/// among `items`. Imports with attributes are left alone. Rearranged imports
+/// take the place of the first import they replace.
+fn restyle_module_uses(
+    items: &mut Vec<P<Item>>,
+    style: UseStyle,
+    parent_module: &dyn Fn(&Item) -> Option<NodeId>,
+) {
+    fn simple_use(item: &Item) -> Option<&UseTree> {
+        match &item.kind {
+            ItemKind::Use(tree) if item.attrs.is_empty() && tree.prefix.segments.len() > 1 => {
+                match tree.kind {
+                    UseTreeKind::Simple(..) => Some(tree),
+                    _ => None,
+                }
+            }
+            _ => None,
         }
     }
 
-    fn visit_attrs<F: FnOnce(&mut Vec<Attribute>)>(&mut self, f: F) {
-        match self {
-            DeclKind::Item(i) => i.visit_attrs(f),
-            DeclKind::ForeignItem(i, _) => i.visit_attrs(f),
+    match style {
+        UseStyle::Flat => {}
+
+        UseStyle::Sorted => {
+            let positions = items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| simple_use(item).is_some())
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+            let mut uses = positions.iter().map(|&idx| items[idx].clone()).collect::<Vec<_>>();
+            uses.sort_by_cached_key(|item| item_to_string(item));
+            for (idx, item) in positions.into_iter().zip(uses) {
+                items[idx] = item;
+            }
+        }
+
+        UseStyle::Grouped => {
+            // Group imports by visibility and parent module, remembering the
+            // index of each member. Imports of the same module written with
+            // different paths (`super::foo::A` and `crate::foo::B`) end up in
+            // one group, under the path of its first member.
+            let mut groups: IndexMap<(String, UseParent), Vec<usize>> = IndexMap::new();
+            for (idx, item) in items.iter().enumerate() {
+                if let Some(tree) = simple_use(item) {
+                    let parent = match parent_module(item) {
+                        Some(id) => UseParent::Module(id),
+                        None => {
+                            let mut parent = tree.prefix.clone();
+                            parent.segments.pop();
+                            UseParent::Path(PathKey::new(&parent))
+                        }
+                    };
+                    groups.entry((vis_to_string(&item.vis), parent)).or_default().push(idx);
+                }
+            }
+
+            let mut grouped_items: HashMap<usize, P<Item>> = HashMap::new();
+            let mut skipped = HashSet::new();
+            for members in groups.values().filter(|members| members.len() > 1) {
+                let mut leaves = members
+                    .iter()
+                    .map(|&idx| {
+                        let mut leaf = simple_use(&items[idx]).unwrap().clone();
+                        let last = leaf.prefix.segments.pop().unwrap();
+                        leaf.prefix.segments = vec![last];
+                        (leaf, DUMMY_NODE_ID)
+                    })
+                    .collect::<Vec<_>>();
+                leaves.sort_by_cached_key(|(leaf, _)| leaf.ident().as_str().to_string());
+
+                let mut grouped = items[members[0]].clone();
+                let mut prefix = simple_use(&grouped).unwrap().prefix.clone();
+                prefix.segments.pop();
+                grouped.kind = ItemKind::Use(P(UseTree {
+                    prefix,
+                    kind: UseTreeKind::Nested(leaves),
+                    span: DUMMY_SP,
+                }));
+                grouped_items.insert(members[0], grouped);
+                skipped.extend(members[1..].iter().cloned());
+            }
+
+            let old_items = mem::replace(items, Vec::new());
+            for (idx, item) in old_items.into_iter().enumerate() {
+                if let Some(grouped) = grouped_items.remove(&idx) {
+                    items.push(grouped);
+                } else if !skipped.contains(&idx) {
+                    items.push(item);
+                }
+            }
         }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct SrcLoc {
-    line: usize,
-    col: usize,
+/// Counts the names bound by items, imports, patterns and generic parameters,
+/// without descending into child modules.
+#[derive(Default)]
+struct BindingCounter {
+    counts: HashMap<Symbol, usize>,
 }
 
-impl From<&Attribute> for SrcLoc {
-    fn from(attr: &Attribute) -> Self {
-        let value_str = attr
-            .value_str()
-            .expect("Expected a value for src_loc attribute")
-            .as_str();
-        let mut iter = value_str.split(':');
-        let line: usize = iter
-            .next()
-            .and_then(|x| x.parse().ok())
-            .expect("Expected a line number in src_loc attribute");
-        let col: usize = iter
-            .next()
-            .and_then(|x| x.parse().ok())
-            .expect("Expected an column number in src_loc attribute");
-        Self { line, col }
+impl BindingCounter {
+    fn bind(&mut self, ident: Ident) {
+        *self.counts.entry(ident.name).or_default() += 1;
     }
 }
 
-/// Store and de-duplicate header-declared items
-struct HeaderDeclarations<'a, 'tcx: 'a> {
-    cx: &'a RefactorCtxt<'a, 'tcx>,
-    idents: PerNS<IndexMap<Ident, Vec<MovedDecl>>>,
-    unnamed_items: PerNS<Vec<MovedDecl>>,
-    matching_defs: HashMap<DefId, DefId>
-    // // Set of imported definition NodeIds that must be made pub(crate) at least
-    // imports: HashSet<HirId>,
+impl<'ast> Visitor<'ast> for BindingCounter {
+    fn visit_item(&mut self, i: &'ast Item) {
+        match i.kind {
+            ItemKind::Use(..) => {}
+            ItemKind::Mod(..) => {
+                self.bind(i.ident);
+                return;
+            }
+            _ => self.bind(i.ident),
+        }
+        visit::walk_item(self, i);
+    }
+
+    fn visit_foreign_item(&mut self, i: &'ast ForeignItem) {
+        self.bind(i.ident);
+        visit::walk_foreign_item(self, i);
+    }
+
+    fn visit_use_tree(&mut self, tree: &'ast UseTree, id: NodeId, _nested: bool) {
+        if let UseTreeKind::Simple(..) = tree.kind {
+            self.bind(tree.ident());
+        }
+        visit::walk_use_tree(self, tree, id);
+    }
+
+    fn visit_pat(&mut self, p: &'ast Pat) {
+        if let PatKind::Ident(_, ident, _) = p.kind {
+            self.bind(ident);
+        }
+        visit::walk_pat(self, p);
+    }
+
+    fn visit_generic_param(&mut self, p: &'ast GenericParam) {
+        self.bind(p.ident);
+        visit::walk_generic_param(self, p);
+    }
 
+    fn visit_mac(&mut self, mac: &'ast Mac) {
+        visit::walk_mac(self, mac);
+    }
 }
-impl<'a, 'tcx> Extend<MovedDecl> for HeaderDeclarations<'a, 'tcx> {
-    fn extend<T: IntoIterator<Item = MovedDecl>>(&mut self, iter: T) {
-        for item in iter {
-            let ident = item.ident();
-            if ident.as_str().contains("C2RustUnnamed") {
-                self.unnamed_items[item.namespace].push(item);
-            } else {
-                self.idents[item.namespace].entry(ident).or_default().push(item);
-            }
-        }    
+
+/// Is `item` an import `prune_uses` may remove: a private, simple import
+/// without attributes?
+fn is_prunable_use(item: &Item) -> bool {
+    match (&item.vis.node, &item.kind) {
+        (VisibilityKind::Inherited, ItemKind::Use(tree)) => {
+            item.attrs.is_empty() && matches!([tree.kind] UseTreeKind::Simple(..))
+        }
+        _ => false,
     }
 }
 
-impl<'a, 'tcx> HeaderDeclarations<'a, 'tcx> {
-    pub fn new(cx: &'a RefactorCtxt<'a, 'tcx>) -> Self {
-        Self {
+/// Collects what paths in a module and its child modules look up in the
+/// module's own scope, for `prune_uses`. That is the first segment of a path
+/// in the module itself, the segment after a leading `self`, the segment
+/// after as many `super`s as a child module is deep, and the segment after
+/// the module's own absolute path. Where that segment ends the path and the
+/// path is resolved, only an import of the same definition counts as used
+/// by it; otherwise (e.g. for paths built by the pass, which have no
+/// resolution) any import of the name does. Identifiers in macro arguments
+/// count as uses of any import of the name as well.
+struct UsedPaths<'a, 'tcx: 'a> {
+    cx: &'a RefactorCtxt<'a, 'tcx>,
+    /// Absolute path of the module, without the leading `crate`
+    mod_path: Vec<Symbol>,
+    /// How many modules deep inside the module we are
+    depth: usize,
+    /// Names looked up in the module's scope without a known target
+    names: HashSet<Symbol>,
+    /// Names looked up in the module's scope for a known definition
+    defs: HashSet<(Symbol, DefId)>,
+    /// First segments of the paths in child modules, which look up names in
+    /// the module's scope if a child glob-imports it
+    child_names: HashSet<Symbol>,
+    /// Does a child module glob-import the module?
+    glob_imported: bool,
+}
+
+impl<'a, 'tcx> UsedPaths<'a, 'tcx> {
+    fn new(cx: &'a RefactorCtxt<'a, 'tcx>, mod_path: &[PathSegment]) -> Self {
+        let mod_path = mod_path
+            .iter()
+            .map(|segment| segment.ident.name)
+            .skip_while(|&name| name == kw::Crate || name == kw::PathRoot)
+            .collect();
+        UsedPaths {
             cx,
-            idents: PerNS::default(),
-            unnamed_items: PerNS::default(),
-            matching_defs: HashMap::new(),
-            // imports: HashSet::new(),
+            mod_path,
+            depth: 0,
+            names: HashSet::new(),
+            defs: HashSet::new(),
+            child_names: HashSet::new(),
+            glob_imported: false,
         }
     }
 
-    /// Remove and return declarations matching the specified item definition
-    fn remove_matching_defs<P>(
-        &mut self,
-        namespace: Namespace,
-        ident: Ident,
-        mut predicate: P,
-    ) -> Vec<DefId>
-        where P: FnMut(&DeclKind) -> bool
-    {
-        assert!(ident.name != kw::Invalid);
-        let mut matches = vec![];
-        if let Some(items) = self.idents[namespace].get_mut(&ident) {
-            items.retain(|decl| {
-                match &decl.kind {
-                    DeclKind::Item(decl) => {
-                        // Don't match use statements
-                        if let ItemKind::Use(_) = decl.kind {
-                            return true;
-                        }
-                    }
-                    _ => {}
-                }
-                if predicate(&decl.kind) {
-                    matches.push(decl.def_id);
-                    false
-                } else {
-                    true
-                }
-            });
+    /// Is an import of `name`, which resolves to `res`, used?
+    fn is_used(&self, name: Symbol, res: Res) -> bool {
+        if self.names.contains(&name) || (self.glob_imported && self.child_names.contains(&name)) {
+            return true;
+        }
+        match res.opt_def_id() {
+            Some(def_id) => self.defs.contains(&(name, def_id)),
+            None => self.defs.iter().any(|&(used, _)| used == name),
         }
-        matches
     }
 
-    /// Add an item into the module. If it has a name conflict with an existing
-    /// item, choose the definition item over any declarations.
-    pub fn insert_item(
-        &mut self,
-        mut item: P<Item>,
-        parent_header: HeaderInfo,
-    ) -> bool {
-        let namespace = self.cx.item_namespace(&item);
-        let new_def_id = self.cx.node_def_id(item.id);
-        let ident = if let ItemKind::Use(tree) = &item.kind {
-            tree.ident()
+    /// Index of the segment of `segments` that is looked up in the module's
+    /// scope, which is `segments.len()` for a path to the module itself
+    fn lookup_index(&self, segments: &[Symbol]) -> Option<usize> {
+        let first = *segments.first()?;
+        if first == kw::Crate || first == kw::PathRoot {
+            let rest = &segments[1..];
+            if rest.len() >= self.mod_path.len() && rest[..self.mod_path.len()] == self.mod_path[..] {
+                return Some(self.mod_path.len() + 1);
+            }
+            None
+        } else if first == kw::SelfLower {
+            Some(1).filter(|_| self.depth == 0)
+        } else if first == kw::Super {
+            let supers = segments.iter().take_while(|&&name| name == kw::Super).count();
+            Some(supers).filter(|&supers| supers == self.depth)
         } else {
-            item.ident
-        };
-        match &item.kind {
-            // We have to disambiguate anonymous items by contents,
-            // since we don't have a proper Ident.
+            Some(0).filter(|_| self.depth == 0)
+        }
+    }
 
-            // Uses are split into simple uses (no brackets) and added into
-            // ident_map.
-            ItemKind::Use(tree) if is_nested(tree) => {
-                for u in split_uses(item).into_iter() {
-                    self.insert_item(u, parent_header.clone());
+    /// Record the path `segments`, which resolves to `res` if known
+    fn add_path(&mut self, segments: &[Symbol], res: Option<Res>) {
+        if self.depth > 0 {
+            if let Some(&first) = segments.first() {
+                self.child_names.insert(first);
+            }
+        }
+        let idx = match self.lookup_index(segments) {
+            Some(idx) if idx < segments.len() => idx,
+            _ => return,
+        };
+        let name = segments[idx];
+        match res {
+            // Constructors aren't what an import of their type resolves to
+            Some(Res::Def(DefKind::Ctor(..), _)) => {}
+            Some(res) if idx + 1 == segments.len() => {
+                if let Some(def_id) = res.opt_def_id() {
+                    self.defs.insert((name, def_id));
+                    return;
                 }
-                true
             }
+            _ => {}
+        }
+        self.names.insert(name);
+    }
 
-            // Keep function definitions, if any
-            ItemKind::Fn(..) => false,
+    fn add_ast_path(&mut self, path: &Path, res: Option<Res>) {
+        let segments: Vec<_> = path.segments.iter().map(|segment| segment.ident.name).collect();
+        self.add_path(&segments, res);
+    }
 
-            // Don't keep impl blocks, these are expanded from macros anyway
-            ItemKind::Impl(..) => true,
+    /// Visit the generic arguments of `path` without recording it again
+    fn walk_path_args(&mut self, path: &Path) {
+        for segment in &path.segments {
+            visit::walk_path_segment(self, path.span, segment);
+        }
+    }
 
-            // We collect all ForeignItems and later filter out any idents
-            // defined in ident_map after processing the whole list of items.
-            ItemKind::ForeignMod(f) => {
-                for item in f.items.iter() {
-                    let abi = f
-                        .abi
-                        .and_then(|abi| abi::lookup(&abi.symbol.as_str()))
-                        .unwrap_or(Abi::Rust);
-                    self.insert_foreign_item(item.clone(), abi, parent_header.clone());
+    /// Record the paths of the import `tree` under `prefix`
+    fn add_use_tree(&mut self, tree: &UseTree, prefix: &[Symbol], res: Option<Res>) {
+        let mut segments = prefix.to_vec();
+        segments.extend(tree.prefix.segments.iter().map(|segment| segment.ident.name));
+        match &tree.kind {
+            UseTreeKind::Simple(..) => {
+                // `use foo::{self};` imports `foo`
+                if segments.len() > 1 && segments.last() == Some(&kw::SelfLower) {
+                    segments.pop();
                 }
-                true
+                self.add_path(&segments, res);
             }
-
-            // We disambiguate named items by their names and check that
-            // we don't have any items with the same name but different
-            // contents.
-
-            _ => {
-                let unnamed = ident.as_str().contains("C2RustUnnamed");
-                let def_id_mapping = match self.find_item(&item, namespace.unwrap()) {
-                    ContainsDecl::NotContained => {
-                        let new_item = MovedDecl::new(item, new_def_id, namespace.unwrap(), parent_header);
-                        if unnamed {
-                            self.unnamed_items[namespace.unwrap()].push(new_item);
-                        } else {
-                            self.idents[namespace.unwrap()]
-                                .entry(ident)
-                                .or_default()
-                                .push(new_item);
-                        }
-                        None
-                    }
-
-                    ContainsDecl::Definition(existing) => {
-                        existing.join_visibility(&item.vis.node);
-                        Some((new_def_id, existing.def_id))
-                    }
-
-                    ContainsDecl::Use(existing) => {
-                        let existing_def_id = existing.def_id;
-                        existing.join_visibility(&item.vis.node);
-                        *existing = MovedDecl::new(item, new_def_id, namespace.unwrap(), parent_header);
-                        Some((existing_def_id, new_def_id))
-                    }
-
-                    ContainsDecl::Equivalent(existing) if existing.is_foreign() => {
-                        let existing_def_id = existing.def_id;
-                        item.vis.node = join_visibility(&existing.visibility().node, &item.vis.node);
-                        *existing = MovedDecl::new(item, new_def_id, namespace.unwrap(), parent_header);
-                        Some((existing_def_id, new_def_id))
-                    }
-
-                    ContainsDecl::Equivalent(existing) => {
-                        Some((new_def_id, existing.def_id))
-                    }
-                };
-                if let Some((old, new)) = def_id_mapping {
-                    self.matching_defs.insert(old, new);
+            UseTreeKind::Nested(trees) => {
+                for (tree, _) in trees {
+                    self.add_use_tree(tree, &segments, None);
                 }
-                true
             }
-        }
-    }
-
-    fn insert_foreign_item(
-        &mut self,
-        item: ForeignItem,
-        abi: Abi,
-        parent_header: HeaderInfo,
-    ) {
-        let new_def_id = self.cx.node_def_id(item.id);
-        let ident = item.ident;
-        let namespace = self.cx.foreign_item_namespace(&item).unwrap();
-        let unnamed = ident.as_str().contains("C2RustUnnamed");
-        let def_id_mapping = match self.find_foreign_item(&item, abi) {
-            ContainsDecl::NotContained => {
-                let new_item = MovedDecl::new(
-                    (item.clone(), abi),
-                    new_def_id,
-                    namespace,
-                    parent_header.clone(),
-                );
-                if unnamed {
-                    self.unnamed_items[namespace].push(new_item);
+            UseTreeKind::Glob => {
+                if self.depth > 0 && self.lookup_index(&segments) == Some(segments.len()) {
+                    self.glob_imported = true;
                 } else {
-                    self.idents[namespace]
-                        .entry(ident)
-                        .or_default()
-                        .push(new_item);
+                    self.add_path(&segments, None);
                 }
-                None
             }
+        }
+    }
+}
 
-            ContainsDecl::Definition(existing) => {
-                let existing_def_id = existing.def_id;
-                *existing = MovedDecl::new(
-                    (item.clone(), abi),
-                    new_def_id,
-                    namespace,
-                    parent_header.clone(),
-                );
-                Some((existing_def_id, new_def_id))
+impl<'a, 'tcx, 'ast> Visitor<'ast> for UsedPaths<'a, 'tcx> {
+    fn visit_item(&mut self, i: &'ast Item) {
+        match &i.kind {
+            ItemKind::Use(tree) => {
+                // Imports added by the pass have no resolution
+                let res = match tree.kind {
+                    UseTreeKind::Simple(..)
+                        if self.cx.hir_map().opt_local_def_id_from_node_id(i.id).is_some() =>
+                    {
+                        self.cx.try_resolve_use_id(i.id).map(|path| path.res)
+                    }
+                    _ => None,
+                };
+                self.add_use_tree(tree, &[], res);
             }
-
-            ContainsDecl::Equivalent(existing) => {
-                existing.join_visibility(&item.vis.node);
-                Some((new_def_id, existing.def_id))
+            ItemKind::Mod(_) => {
+                self.depth += 1;
+                visit::walk_item(self, i);
+                self.depth -= 1;
             }
-
-            ContainsDecl::Use(..) => panic!("Foreign items cannot be use statements"),
-        };
-        if let Some((old, new)) = def_id_mapping {
-            self.matching_defs.insert(old, new);
+            _ => visit::walk_item(self, i),
         }
     }
 
-    /// Finalize and return a de-duplicated Vec of items
-    fn into_items(self, st: &CommandState, info: &ModuleInfo) -> Vec<P<Item>> {
-        fn make_header_comment(last_mod: Option<Ident>, next_mod: Ident) -> Comment {
-            let mut lines = vec![];
-            if let Some(last_mod) = last_mod {
-                lines.push(format!(
-                    "// ================ END {} ================",
-                    last_mod.as_str(),
-                ));
-            }
-            lines.push(format!(
-                "// =============== BEGIN {} ================",
-                next_mod.as_str(),
-            ));
-            Comment {
-                style: CommentStyle::Isolated,
-                lines,
-                pos: BytePos(0),
+    fn visit_expr(&mut self, e: &'ast Expr) {
+        if let ExprKind::Path(qself, path) = &e.kind {
+            self.add_ast_path(path, self.cx.try_resolve_expr_hir(e));
+            if let Some(qself) = qself {
+                self.visit_ty(&qself.ty);
             }
+            self.walk_path_args(path);
+            return;
         }
+        visit::walk_expr(self, e);
+    }
 
-        let Self {
-            idents,
-            unnamed_items,
-            ..
-        } = self;
-
-        let mut all_items = unnamed_items
-            .type_ns
-            .into_iter()
-            .chain(unnamed_items.value_ns.into_iter())
-            .chain(idents.type_ns.into_iter().map(|(_, v)| v.lone()))
-            .chain(idents.value_ns.into_iter().map(|(_, v)| v.lone()))
-            .collect::<Vec<_>>();
+    fn visit_ty(&mut self, t: &'ast Ty) {
+        if let TyKind::Path(qself, path) = &t.kind {
+            self.add_ast_path(path, self.cx.try_resolve_ty_hir(t));
+            if let Some(qself) = qself {
+                self.visit_ty(&qself.ty);
+            }
+            self.walk_path_args(path);
+            return;
+        }
+        visit::walk_ty(self, t);
+    }
 
-        all_items.sort_by(|a, b| {
-            if a.parent_header.ident == b.parent_header.ident {
-                a.loc.cmp(&b.loc)
-            } else {
-                let line_a = info.header_lines.get(&a.parent_header.ident).unwrap_or(&0);
-                let line_b = info.header_lines.get(&b.parent_header.ident).unwrap_or(&0);
-                if line_a != line_b {
-                    line_a.cmp(line_b)
-                } else {
-                    a.parent_header.ident.as_str().cmp(&b.parent_header.ident.as_str())
+    fn visit_pat(&mut self, p: &'ast Pat) {
+        let res = self.cx.try_resolve_pat_hir(p);
+        match &p.kind {
+            PatKind::Ident(_, ident, sub) => {
+                self.add_path(&[ident.name], res);
+                if let Some(sub) = sub {
+                    self.visit_pat(sub);
                 }
             }
-        });
-
-        let mut items: Vec<P<Item>> = Vec::new();
-        let mut foreign_items: HashMap<Abi, Vec<ForeignItem>> = HashMap::new();
-        let mut last_item_mod = None;
-        let mut last_foreign_item_mod = None;
-        for item in all_items {
-            let cur_mod_name = item.parent_header.ident;
-            match item.kind {
-                DeclKind::Item(i) => {
-                    if last_item_mod != Some(cur_mod_name) {
-                        st.add_comment(i.id, make_header_comment(last_item_mod, cur_mod_name));
-                        last_item_mod = Some(cur_mod_name);
-                    }
-                    items.push(i);
+            PatKind::Path(qself, path) => {
+                self.add_ast_path(path, res);
+                if let Some(qself) = qself {
+                    self.visit_ty(&qself.ty);
                 }
-                DeclKind::ForeignItem(fi, abi) => {
-                    if last_foreign_item_mod != Some(cur_mod_name) {
-                        st.add_comment(
-                            fi.id,
-                            make_header_comment(last_foreign_item_mod, cur_mod_name),
-                        );
-                        last_foreign_item_mod = Some(cur_mod_name);
-                    }
-                    foreign_items.entry(abi).or_default().push(fi);
+                self.walk_path_args(path);
+            }
+            PatKind::Struct(path, fields, _) => {
+                self.add_ast_path(path, res);
+                self.walk_path_args(path);
+                for field in fields {
+                    self.visit_pat(&field.pat);
+                }
+            }
+            PatKind::TupleStruct(path, pats) => {
+                self.add_ast_path(path, res);
+                self.walk_path_args(path);
+                for pat in pats {
+                    self.visit_pat(pat);
                 }
             }
+            _ => visit::walk_pat(self, p),
         }
+    }
 
-        let foreign_mods = foreign_items
-            .into_iter()
-            .map(|(abi, items)| mk().extern_(abi).foreign_items(items));
-
-        foreign_mods
-            .chain(items.into_iter())
-            .collect()
+    fn visit_path(&mut self, path: &'ast Path, _id: NodeId) {
+        self.add_ast_path(path, None);
+        visit::walk_path(self, path);
     }
 
-    fn find_item<'b>(&'b mut self, item: &Item, namespace: Namespace) -> ContainsDecl<'b> {
-        let ident = if let ItemKind::Use(tree) = &item.kind {
-            tree.ident()
-        } else {
-            item.ident
-        };
-        assert!(ident.name != kw::Invalid);
+    fn visit_mac(&mut self, mac: &'ast Mac) {
+        add_token_names(&mac.args, &mut self.names);
+        visit::walk_mac(self, mac);
+    }
+}
 
-        if ident.as_str().contains("C2RustUnnamed") {
-            for existing_decl in self.unnamed_items[namespace].iter_mut() {
-                match &existing_decl.kind {
-                    DeclKind::Item(existing_item) => match &existing_item.kind {
-                        ItemKind::TyAlias(..)
-                        | ItemKind::Struct(..)
-                        | ItemKind::Union(..)
-                        | ItemKind::Enum(..) => {
-                            // Does the new item match the existing item, except
-                            // for unnamed names?
-                            if item.kind.unnamed_equiv(&existing_item.kind) {
-                                return ContainsDecl::Equivalent(existing_decl);
-                            }
-                        }
+/// Collects the identifiers in macro arguments
+#[derive(Default)]
+struct MacroNames {
+    names: HashSet<Symbol>,
+}
 
-                        // TODO?
-                        _ => {}
-                    },
+impl<'ast> Visitor<'ast> for MacroNames {
+    fn visit_mac(&mut self, mac: &'ast Mac) {
+        add_token_names(&mac.args, &mut self.names);
+        visit::walk_mac(self, mac);
+    }
+}
 
-                    DeclKind::ForeignItem(existing_foreign, _) => {
-                        if let ForeignItemKind::Ty = &existing_foreign.kind {
-                            if foreign_equiv(&existing_foreign, &item) {
-                                // This item is equivalent to an existing foreign item,
-                                // modulo visibility.
-                                return ContainsDecl::Equivalent(existing_decl);
-                            }
-                        }
-                    }
+fn add_token_names(args: &MacArgs, names: &mut HashSet<Symbol>) {
+    fn add_tokens(tts: &TokenStream, names: &mut HashSet<Symbol>) {
+        for tree in tts.trees() {
+            match tree {
+                TokenTree::Token(Token { kind: TokenKind::Ident(name, _), .. }) => {
+                    names.insert(name);
                 }
+                TokenTree::Token(..) => {}
+                TokenTree::Delimited(_, _, tts) => add_tokens(&tts, names),
             }
-
-            return ContainsDecl::NotContained;
         }
+    }
 
-        if let Some(existing_decls) = self.idents[namespace].get_mut(&ident) {
-            for existing_decl in existing_decls {
-                match &existing_decl.kind {
-                    DeclKind::Item(existing_item) => match (&existing_item.kind, &item.kind) {
-                        // Replace a use with a real definition
-                        (ItemKind::Use(..), _) => {
-                            return ContainsDecl::Use(existing_decl);
-                        }
+    match args {
+        MacArgs::Delimited(_, _, tts) | MacArgs::Eq(_, tts) => add_tokens(tts, names),
+        MacArgs::Empty => {}
+    }
+}
 
-                        // Make sure we use the widest visibility for this item
-                        (_, ItemKind::Use(..)) => {
-                            return ContainsDecl::Definition(existing_decl);
-                        }
+/// Looks for references to any of a set of types, e.g. in a function
+/// signature.
+struct TypeRefFinder<'a, 'b, 'tcx: 'a> {
+    cx: &'a RefactorCtxt<'a, 'tcx>,
+    types: &'b HashSet<DefId>,
+    found: bool,
+}
 
-                        // Otherwise make sure these items are structurally
-                        // equivalent.
-                        _ => {
-                            if self.cx.compatible_types(&item, &existing_item) {
-                                return ContainsDecl::Equivalent(existing_decl);
-                            }
-                        }
-                    }
+impl<'a, 'b, 'tcx, 'ast> Visitor<'ast> for TypeRefFinder<'a, 'b, 'tcx> {
+    fn visit_ty(&mut self, ty: &'ast Ty) {
+        if let Some(def_id) = self.cx.try_resolve_ty(ty) {
+            if self.types.contains(&def_id) {
+                self.found = true;
+            }
+        }
+        visit::walk_ty(self, ty);
+    }
 
-                    DeclKind::ForeignItem(existing_foreign, _) => {
-                        if let ItemKind::Use(..) = item.kind {
-                            // If the import refers to an existing foreign item, do
-                            // not replace it.
-                            let path = self.cx.resolve_use_id(item.id);
-                            if let Some(did) = path.res.opt_def_id() {
-                                if let Some(Node::ForeignItem(_)) = self.cx.hir_map().get_if_local(did)
-                                {
-                                    return ContainsDecl::Definition(existing_decl);
-                                }
-                            }
+    fn visit_mac(&mut self, mac: &'ast Mac) {
+        visit::walk_mac(self, mac);
+    }
+}
 
-                            return ContainsDecl::Equivalent(existing_decl);
-                        }
-                        if foreign_equiv(&existing_foreign, &item) {
-                            return ContainsDecl::Equivalent(existing_decl);
-                        }
-                    }
-                }
-                trace!("{:?} and {:?} share idents, but are not compatible", item, existing_decl);
+/// Compute the maximum nesting depth of modules in `krate`. This uses an
+/// explicit work stack rather than recursion so that it is safe to run on
+/// arbitrarily deep module trees.
+fn module_depth(krate: &Crate) -> usize {
+    let mut max_depth = 0;
+    let mut stack: Vec<(&Mod, usize)> = vec![(&krate.module, 0)];
+    while let Some((module, depth)) = stack.pop() {
+        max_depth = max_depth.max(depth);
+        for item in &module.items {
+            if let ItemKind::Mod(m) = &item.kind {
+                stack.push((m, depth + 1));
             }
         }
-
-        ContainsDecl::NotContained
     }
+    max_depth
+}
 
-    fn find_foreign_item<'b>(&'b mut self, item: &ForeignItem, abi: Abi) -> ContainsDecl<'b> {
-        let ns = match &item.kind {
-            ForeignItemKind::Fn(..) | ForeignItemKind::Static(..) => Namespace::ValueNS,
-            ForeignItemKind::Ty => Namespace::TypeNS,
-            ForeignItemKind::Macro(..) => unimplemented!(),
-        };
-        let ident = item.ident;
-        assert!(ident.name != kw::Invalid);
+/// Derive a module name for a header module with an empty ident. We use the
+/// common prefix of the idents of its items if there is a meaningful one, and
+/// otherwise a hash of the set of item idents, so the result only depends on
+/// the module contents.
+fn derive_module_ident(items: &[P<Item>]) -> Ident {
+    let mut names = items
+        .iter()
+        .filter_map(|item| match &item.kind {
+            // Glob and nested imports have no single name
+            ItemKind::Use(tree) => match tree.kind {
+                UseTreeKind::Simple(..) => Some(tree.ident()),
+                _ => None,
+            },
+            _ => Some(item.ident),
+        })
+        .map(|ident| ident.as_str().to_string())
+        .filter(|name| !name.is_empty())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+
+    // The common prefix of a sorted list is the common prefix of its first
+    // and last elements
+    let prefix = match (names.first(), names.last()) {
+        (Some(first), Some(last)) => first
+            .chars()
+            .zip(last.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect::<String>(),
+        _ => String::new(),
+    };
+    let prefix = prefix.trim_end_matches('_');
+    if prefix.len() >= 2 && prefix.chars().any(|c| c != '_') {
+        // Mirror the `foo_h` naming of translated header modules
+        Ident::from_str(&format!("{}_h", prefix))
+    } else {
+        let mut hasher = DefaultHasher::new();
+        names.hash(&mut hasher);
+        Ident::from_str(&format!("header_{:016x}", hasher.finish()))
+    }
+}
 
-        if let Some(existing_decls) = self.idents[ns].get_mut(&ident) {
-            for existing_decl in existing_decls {
-                match &existing_decl.kind {
-                    DeclKind::Item(existing_item) => {
-                        if foreign_equiv(&item, &existing_item) {
-                            return ContainsDecl::Equivalent(existing_decl)
-                        } else if let ItemKind::Use(_) = existing_item.kind {
-                            // A use takes precedence over a foreign declaration
-                            // unless the use refers to the foreign declaration are
-                            // attempting to insert.
-                            let path = self.cx.resolve_use_id(existing_item.id);
-                            if let Some(did) = path.res.opt_def_id() {
-                                if let Some(Node::ForeignItem(_)) = self.cx.hir_map().get_if_local(did)
-                                {
-                                    return ContainsDecl::Definition(existing_decl);
-                                }
-                            }
-                            return ContainsDecl::Equivalent(existing_decl);
-                        }
-                    }
+fn is_nested(tree: &UseTree) -> bool {
+    if let UseTreeKind::Nested(..) = &tree.kind {
+        true
+    } else {
+        false
+    }
+}
 
-                    DeclKind::ForeignItem(existing_foreign, existing_abi) => {
-                        if *existing_abi != abi {
-                            continue;
-                        }
-                        let matches_existing = match (&existing_foreign.kind, &item.kind) {
-                            (ForeignItemKind::Fn(decl1, _), ForeignItemKind::Fn(decl2, _)) => {
-                                self.cx.compatible_fn_prototypes(decl1, decl2)
-                            }
+/// Remove redundant `extern crate` items from `items`: repeats of an `extern
+/// crate` and, if the crate is also imported under another name, plain
+/// `extern crate`s of it. Each is merged into the first one it repeats, or
+/// into the first one renaming the crate.
+fn dedup_extern_crates(items: &mut Vec<P<Item>>) {
+    // Crate named by an `extern crate` item, and whether it is renamed
+    fn extern_crate(item: &Item) -> Option<(Symbol, bool)> {
+        match item.kind {
+            ItemKind::ExternCrate(orig) => {
+                let name = orig.unwrap_or(item.ident.name);
+                Some((name, name != item.ident.name))
+            }
+            _ => None,
+        }
+    }
 
-                            _ => existing_foreign.ast_equiv(&item),
-                        };
-                        if matches_existing {
-                            return ContainsDecl::Equivalent(existing_decl);
-                        }
-                    }
+    // Pairs of the index of each redundant item and the item it merges into
+    let mut merges = vec![];
+    for (idx, item) in items.iter().enumerate() {
+        let (name, renamed) = match_or!([extern_crate(item)] Some(x) => x; continue);
+        // Indices, names and renaming of the `extern crate`s of this crate
+        let same_crate = items
+            .iter()
+            .enumerate()
+            .filter_map(|(other_idx, other)| match extern_crate(other) {
+                Some((other_name, other_renamed)) if other_name == name => {
+                    Some((other_idx, other.ident.name, other_renamed))
                 }
-            }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let renaming = same_crate
+            .iter()
+            .find(|&&(_, _, other_renamed)| other_renamed && !renamed);
+        let target = renaming
+            .or_else(|| same_crate.iter().find(|&&(_, other_ident, _)| other_ident == item.ident.name))
+            .unwrap()
+            .0;
+        if target != idx {
+            merges.push((idx, target));
         }
+    }
 
-        ContainsDecl::NotContained
+    if merges.is_empty() {
+        return;
     }
-}
 
-enum ContainsDecl<'a> {
-    NotContained,
+    for &(idx, target) in &merges {
+        let vis = items[idx].vis.node.clone();
+        let attrs = items[idx].attrs.clone();
+        let target = &mut items[target];
+        target.vis.node = join_visibility(&target.vis.node, &vis);
+        for attr in attrs {
+            if !target.attrs.iter().any(|other| other.ast_equiv(&attr)) {
+                target.attrs.push(attr);
+            }
+        }
+    }
+    let redundant: HashSet<usize> = merges.iter().map(|&(idx, _)| idx).collect();
+    let mut idx = 0;
+    items.retain(|_| {
+        let keep = !redundant.contains(&idx);
+        idx += 1;
+        keep
+    });
+}
 
-    /// The module contains a declaration or definition that is equivalent to
-    /// the given item.
-    Equivalent(&'a mut MovedDecl),
+/// Describe how the items of a module changed between `before` and `after`,
+/// as removed (`-`) and added (`+`) items. Modules found in both are compared
+/// item by item rather than printed whole.
+fn diff_items(before: &[P<Item>], after: &[P<Item>], out: &mut Vec<String>) {
+    fn find_mod(items: &[P<Item>], ident: Ident) -> Option<&Mod> {
+        items.iter().find_map(|item| match &item.kind {
+            ItemKind::Mod(m) if item.ident == ident => Some(m),
+            _ => None,
+        })
+    }
 
-    /// The module contains a definition that can replace the given item.
-    Definition(&'a mut MovedDecl),
+    for item in before {
+        if let ItemKind::Mod(m) = &item.kind {
+            if let Some(other) = find_mod(after, item.ident) {
+                diff_items(&m.items, &other.items, out);
+                continue;
+            }
+        }
+        if !after.iter().any(|other| other.ast_equiv(item)) {
+            out.push(format!("- {}", item_to_string(item)));
+        }
+    }
+    for item in after {
+        if let ItemKind::Mod(..) = item.kind {
+            if find_mod(before, item.ident).is_some() {
+                continue;
+            }
+        }
+        if !before.iter().any(|other| other.ast_equiv(item)) {
+            out.push(format!("+ {}", item_to_string(item)));
+        }
+    }
+}
 
-    /// The module contains a use of the given item.
-    Use(&'a mut MovedDecl),
+/// Run `reorganize_definitions` with the `KEY=VALUE` options `args` on a
+/// copy of the crate in `st`, and return the destination module of each
+/// header declaration it found (see `Reorganizer::resolved_mapping`). The
+/// crate itself is left untouched, so other commands can reuse the placement
+/// decisions. `cx` has to come from a phase with name resolution.
+pub fn destination_modules(
+    st: &CommandState,
+    cx: &RefactorCtxt,
+    args: &[String],
+) -> Result<HashMap<NodeId, Ident>, String> {
+    let mut options = Options::from_args(args);
+    options.check_phase()?;
+    options.load_plan()?;
+    let mut krate = st.krate().clone();
+    let mut reorg = Reorganizer::new(st, cx, &options);
+    reorg.run(&mut krate);
+
+    let source_map = cx.session().source_map();
+    let errors = reorg.errors
+        .borrow()
+        .iter()
+        .map(|e| e.to_message(source_map))
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(format!("reorganize_definitions failed:\n{}", errors.join("\n")));
+    }
+    Ok(reorg.resolved_mapping())
 }
 
-/// Returns true if the given ForeignItem can be a declaration for the given
-/// Item definition.
-fn foreign_equiv(foreign: &ForeignItem, item: &Item) -> bool {
-    match (&foreign.kind, &item.kind) {
-        // We should never encounter a foreign function with the same name but a
-        // different declaration. Nonetheless, the FnDecls in rust might be
-        // slightly different (param name, mutability), so we can't do an
-        // ast_equiv on the FnDecl. Might be worth writing a custom comparison
-        // for a sanity check, but not doing that right now.
-        (ForeignItemKind::Fn(..), ItemKind::Fn(..)) => true,
+impl Command for ReorganizeDefinitions {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
+        self.options.check_phase()?;
+        self.options.load_plan()?;
+        let options = &self.options;
+        let errors = state
+            .transform_crate(options.phase, |st, cx| {
+                let original = if options.dry_run || options.previews_diff() {
+                    Some(st.krate().clone())
+                } else {
+                    None
+                };
+                let mut reorg = Reorganizer::new(st, cx, options);
+                reorg.run(&mut *st.krate_mut());
+                cx.session().note_without_error(&reorg.summary.to_string());
+                if let Some(original) = original {
+                    if options.dry_run {
+                        for (item, module) in &reorg.plan {
+                            println!("{} -> {}", item, module);
+                        }
+                    }
+                    if options.previews_diff() {
+                        let diff = crate_diff(cx, &original, &st.krate());
+                        match &options.diff_out {
+                            Some(path) => reorg.write_output("diff", path, diff),
+                            None => print!("{}", diff),
+                        }
+                    }
+                    *st.krate_mut() = original;
+                }
 
-        (ForeignItemKind::Static(frn_ty, _frn_mutbl), ItemKind::Static(ty, _mutbl, _)) => {
-            if frn_ty.ast_equiv(&ty) {
-                return true;
-            }
+                let source_map = cx.session().source_map();
+                reorg.errors
+                    .into_inner()
+                    .iter()
+                    .map(|e| e.to_message(source_map))
+                    .collect::<Vec<_>>()
+            })
+            .expect("Failed to run compiler");
+
+        if !errors.is_empty() {
+            return Err(format!("reorganize_definitions failed:\n{}", errors.join("\n")));
+        }
 
-            match (&frn_ty.kind, &ty.kind) {
-                // An extern array declaration of any length matches a concrete
-                // definition if they have the same element type
-                (TyKind::Array(frn_elem_ty, _), TyKind::Array(elem_ty, _)) => {
-                    frn_elem_ty.ast_equiv(elem_ty)
-                }
-                _ => false,
+        if options.selfcheck && !options.dry_run && !options.previews_diff() {
+            // The second run shouldn't repeat reports or overwrite the
+            // output files of the first one
+            let recheck = Options {
+                provenance_out: None,
+                skeleton_out: None,
+                plan_out: None,
+                map_out: None,
+                path_report: None,
+                report_unreachable: false,
+                report_type_cycles: false,
+                verbose: false,
+                diff: false,
+                diff_out: None,
+                ..options.clone()
+            };
+            // Run the pass again on a copy of its output, which is thrown
+            // away, so the check can't change the crate
+            let (errors, changes) = state
+                .transform_crate(recheck.phase, |st, cx| {
+                    let mut krate = st.krate().clone();
+                    let mut reorg = Reorganizer::new(st, cx, &recheck);
+                    reorg.run(&mut krate);
+
+                    let source_map = cx.session().source_map();
+                    let errors = reorg.errors
+                        .into_inner()
+                        .iter()
+                        .map(|e| e.to_message(source_map))
+                        .collect::<Vec<_>>();
+                    let mut changes = vec![];
+                    diff_items(&st.krate().module.items, &krate.module.items, &mut changes);
+                    (errors, changes)
+                })
+                .expect("Failed to run compiler");
+
+            if !errors.is_empty() {
+                return Err(format!(
+                    "reorganize_definitions is not idempotent, a second run failed:\n{}",
+                    errors.join("\n"),
+                ));
+            }
+            if !changes.is_empty() {
+                return Err(format!(
+                    "reorganize_definitions is not idempotent, a second run changed:\n{}",
+                    changes.join("\n"),
+                ));
             }
         }
 
-        // If we have a definition for this type name we can assume it is
-        // equivalent.
-        (ForeignItemKind::Ty, ItemKind::TyAlias(..))
-        | (ForeignItemKind::Ty, ItemKind::Enum(..))
-        | (ForeignItemKind::Ty, ItemKind::Struct(..))
-        | (ForeignItemKind::Ty, ItemKind::Union(..)) => true,
-
-        _ => false,
+        Ok(())
     }
 }
 
-/// Check if the `Item` has the `#[header_src = "/some/path"]` attribute
-fn has_source_header(attrs: &[Attribute]) -> bool {
-    attrs.iter().any(|attr| is_c2rust_attr(attr, "header_src"))
+/// # `split_modules` Command
+///
+/// Usage: `split_modules [threshold=N]`
+///
+/// The inverse of `reorganize_definitions`: split each module holding items
+/// from more than one header into one child module per header. An item's
+/// header is given by a `#[c2rust::header_src = "/path/foo.h:N"]` attribute
+/// on the item itself; items without one stay where they are.
+///
+/// Each child module is named after its header file (`foo.h` becomes `foo`)
+/// and starts with `use super::*;`, and the parent re-exports its contents
+/// with `pub use self::foo::*;`, so that existing paths keep resolving.
+/// Private items become `pub(super)` for the same reason.
+///
+/// With `threshold=N`, only modules with more than `N` items are split.
+pub struct SplitModules {
+    threshold: usize,
 }
 
-/// Check if the `Item` has the `#[header_src = "/some/path"]` attribute
-fn parse_source_header(attrs: &[Attribute]) -> Option<(String, usize)> {
-    attrs.iter().find(|a| is_c2rust_attr(a, "header_src")).map(|attr| {
-        let value_str = attr
-            .value_str()
-            .expect("Expected a value for header_src attribute")
-            .as_str();
-        let mut iter = value_str.split(':');
-        let path = iter
-            .next()
-            .expect("Expected a path in header_src attribute");
-        let line: usize = iter
-            .next()
-            .and_then(|line| line.parse().ok())
-            .expect("Expected an include line number in header_src attribute");
-        (path.to_string(), line)
-    })
+impl SplitModules {
+    fn from_args(args: &[String]) -> Self {
+        let mut threshold = 0;
+        for arg in args {
+            let (key, value) = match arg.find('=') {
+                Some(idx) => (&arg[..idx], &arg[idx + 1..]),
+                None => (&arg[..], "1"),
+            };
+            match key {
+                "threshold" => {
+                    threshold = value.parse().unwrap_or_else(|_| {
+                        panic!("Expected a number for split_modules option {}, found {:?}", key, value)
+                    })
+                }
+                _ => panic!("Unknown split_modules option: {}", arg),
+            }
+        }
+        SplitModules { threshold }
+    }
 }
 
-fn is_nested(tree: &UseTree) -> bool {
-    if let UseTreeKind::Nested(..) = &tree.kind {
-        true
-    } else {
-        false
+impl Command for SplitModules {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
+        let threshold = self.threshold;
+        let errors = state
+            .transform_crate(Phase::Phase1, |st, cx| {
+                let mut errors = vec![];
+                let krate = &mut *st.krate_mut();
+                FlatMapNodes::visit(krate, |mut item: P<Item>| {
+                    if let ItemKind::Mod(m) = &mut item.kind {
+                        split_module_items(&mut m.items, threshold, &mut errors);
+                    }
+                    smallvec![item]
+                });
+                split_module_items(&mut krate.module.items, threshold, &mut errors);
+
+                let source_map = cx.session().source_map();
+                errors
+                    .iter()
+                    .map(|e| format!("{}: {}", source_map.span_to_string(e.span()), e))
+                    .collect::<Vec<_>>()
+            })
+            .expect("Failed to run compiler");
+
+        if !errors.is_empty() {
+            return Err(format!("split_modules failed:\n{}", errors.join("\n")));
+        }
+        Ok(())
     }
 }
 
-impl Transform for ReorganizeDefinitions {
-    fn transform(&self, krate: &mut Crate, st: &CommandState, cx: &RefactorCtxt) {
-        let mut reorg = Reorganizer::new(st, cx);
-        reorg.run(krate)
+/// Move the items of a module that come from different headers into one
+/// child module per header, for `split_modules`
+fn split_module_items(items: &mut Vec<P<Item>>, threshold: usize, errors: &mut Vec<ReorganizeError>) {
+    if items.len() <= threshold {
+        return;
+    }
+
+    // Header of each item; header modules themselves are left alone
+    let headers = items
+        .iter()
+        .map(|item| match item.kind {
+            ItemKind::Mod(..) => None,
+            _ => parse_source_header(&item.attrs, DEFAULT_HEADER_ATTR).unwrap_or_else(|e| {
+                errors.push(e);
+                None
+            }),
+        })
+        .map(|header| header.map(|(path, _)| path))
+        .collect::<Vec<_>>();
+    let header_count = headers.iter().flatten().collect::<HashSet<_>>().len();
+    if header_count < 2 {
+        return;
+    }
+
+    // Pick a fresh module name for each header, in order of appearance
+    let mut taken: HashSet<String> = items.iter().map(|item| item.ident.to_string()).collect();
+    let mut names: HashMap<String, Ident> = HashMap::new();
+    for path in headers.iter().flatten() {
+        if names.contains_key(path) {
+            continue;
+        }
+        let stem = std::path::Path::new(path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("header");
+        let mut base = normalize_module_name(stem);
+        if base.is_empty() || base.starts_with(|c: char| c.is_ascii_digit()) {
+            base = format!("h_{}", base);
+        }
+        if Ident::from_str(&base).is_reserved() {
+            base.push_str("_h");
+        }
+        let mut name = base.clone();
+        let mut counter = 0;
+        while taken.contains(&name) {
+            name = format!("{}_{}", base, counter);
+            counter += 1;
+        }
+        taken.insert(name.clone());
+        names.insert(path.clone(), Ident::from_str(&name));
+    }
+
+    let mut groups: HashMap<String, Vec<P<Item>>> = HashMap::new();
+    // Each child module takes the place of the first item of its header
+    let mut slots: Vec<(usize, String)> = vec![];
+    let mut rest = Vec::with_capacity(items.len());
+    for (mut item, header) in items.drain(..).zip(headers) {
+        let header = match_or!([header] Some(header) => header; { rest.push(item); continue });
+        if !groups.contains_key(&header) {
+            slots.push((rest.len(), header.clone()));
+        }
+        item.attrs.retain(|attr| !is_header_attr(attr, DEFAULT_HEADER_ATTR));
+        if let VisibilityKind::Inherited = item.vis.node {
+            item.vis.node = VisibilityKind::Restricted {
+                path: P(mk().path("super")),
+                id: DUMMY_NODE_ID,
+            };
+        }
+        groups.entry(header).or_default().push(item);
     }
 
-    fn min_phase(&self) -> Phase {
-        Phase::Phase3
+    // Insert back to front, so that the remaining positions stay valid
+    for (idx, header) in slots.into_iter().rev() {
+        let name = names[&header];
+        let mut child_items = vec![mk().use_glob_item("super")];
+        child_items.extend(groups.remove(&header).unwrap());
+        rest.insert(idx, mk().pub_().mod_item(name, mk().mod_(child_items)));
+        rest.insert(idx, mk().pub_().use_glob_item(vec!["self".to_string(), name.to_string()]));
     }
+    *items = rest;
 }
 
 pub fn register_commands(reg: &mut Registry) {
-    use super::mk;
-
-    reg.register("reorganize_definitions", |_args| mk(ReorganizeDefinitions))
+    reg.register("reorganize_definitions", |args| Box::new(ReorganizeDefinitions {
+        options: Options::from_args(args),
+    }));
+    reg.register("split_modules", |args| Box::new(SplitModules::from_args(args)));
 }
@@ -0,0 +1,151 @@
+//! Classification of translated items by the header they were declared in.
+//!
+//! `reorganize_definitions` uses this to tell header modules, system
+//! headers and malformed header attributes apart; other transforms that
+//! need to tell header declarations apart can use the same rules.
+//!
+//! The classification only looks at the item itself. The module it names
+//! for a project header is the one `reorganize_definitions` picks when
+//! nothing else routes the declarations: the `pin`, `plan_in`, `map_in`,
+//! `module_rules`, `internal_module` and `prefix_map` options and
+//! `prefer_module` attributes all take precedence over it.
+
+use syntax::ast::{AttrKind, Attribute, Ident};
+
+use crate::ast_manip::util::is_c2rust_attr;
+use c2rust_ast_printer::pprust::path_to_string;
+
+#[cfg(test)]
+mod tests;
+
+/// Attribute the translator marks header modules with
+pub const DEFAULT_HEADER_ATTR: &str = "header_src";
+
+/// Where the declarations of a translated item belong
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The item isn't marked with a header, so it stays where it is
+    Source,
+    /// The item comes from the system header at `header`. Such declarations
+    /// are gathered in a single module for the system headers.
+    System { header: String },
+    /// The item comes from the project header at `header`, whose module is
+    /// named `module` by default (see the module docs for what overrides it)
+    Header { header: String, module: String },
+    /// The item's header attribute can't be parsed
+    Malformed,
+}
+
+/// Settings for classifying items, matching the `header_attr` and
+/// `std_include_dirs` options of `reorganize_definitions`
+#[derive(Clone, Debug)]
+pub struct PlacementRules {
+    /// Name of the header attribute, see `is_header_attr`
+    pub header_attr: String,
+    /// Directories holding system headers, if not just `/usr/include`
+    pub std_include_dirs: Option<Vec<String>>,
+}
+
+impl Default for PlacementRules {
+    fn default() -> Self {
+        PlacementRules {
+            header_attr: DEFAULT_HEADER_ATTR.to_string(),
+            std_include_dirs: None,
+        }
+    }
+}
+
+impl PlacementRules {
+    /// Classify an item (usually a header module) named `ident` with the
+    /// attributes `attrs`. Only the first header attribute counts.
+    pub fn classify(&self, attrs: &[Attribute], ident: Ident) -> Placement {
+        let attr = match attrs.iter().find(|attr| is_header_attr(attr, &self.header_attr)) {
+            Some(attr) => attr,
+            None => return Placement::Source,
+        };
+        let header = match parse_header_attr(attr) {
+            Some((header, _)) => header,
+            None => return Placement::Malformed,
+        };
+        if self.is_std_header(&header) {
+            Placement::System { header }
+        } else {
+            let module = header_module_name(&header, ident);
+            Placement::Header { header, module }
+        }
+    }
+
+    /// Is `path` the path of a system header?
+    pub fn is_std_header(&self, path: &str) -> bool {
+        is_std_header(path, self.std_include_dirs.as_ref().map(|dirs| &dirs[..]))
+    }
+}
+
+/// Is `path` the path of a system header, i.e. one under any of
+/// `std_include_dirs`, or containing `/usr/include` if there are none?
+pub fn is_std_header(path: &str, std_include_dirs: Option<&[String]>) -> bool {
+    match std_include_dirs {
+        Some(dirs) => {
+            let path = std::path::Path::new(path);
+            dirs.iter().any(|dir| path.starts_with(dir))
+        }
+        None => path.contains("/usr/include"),
+    }
+}
+
+/// Classify an item named `ident` with the attributes `attrs` by the
+/// default rules: `#[c2rust::header_src]` marks header modules, and headers
+/// under `/usr/include` are system headers.
+pub fn classify_item(attrs: &[Attribute], ident: Ident) -> Placement {
+    PlacementRules::default().classify(attrs, ident)
+}
+
+/// Is `attr` the header attribute `name`? A bare name is looked up in the
+/// `c2rust` tool namespace, a path like `my_tool::origin` is matched as is.
+pub fn is_header_attr(attr: &Attribute, name: &str) -> bool {
+    if !name.contains("::") {
+        return is_c2rust_attr(attr, name);
+    }
+    match &attr.kind {
+        AttrKind::Normal(item) => path_to_string(&item.path) == name,
+        _ => false,
+    }
+}
+
+/// Check if the `Item` has the `#[header_src = "/some/path"]` attribute (or
+/// the one named by `header_attr`)
+pub fn has_source_header(attrs: &[Attribute], header_attr: &str) -> bool {
+    attrs.iter().any(|attr| is_header_attr(attr, header_attr))
+}
+
+/// Parse the header path and include line out of a header attribute of the
+/// form `#[header_src = "/some/path:line"]`, or `None` if it is malformed
+pub fn parse_header_attr(attr: &Attribute) -> Option<(String, usize)> {
+    let value_str = attr.value_str()?.as_str();
+    let mut iter = value_str.split(':');
+    let path = iter.next().filter(|path| !path.is_empty())?;
+    let line = iter.next().and_then(|line| line.parse().ok())?;
+    Some((path.to_string(), line))
+}
+
+/// Normalized name of the module for the header at `path`: the file stem of
+/// the path, or the name of its header module `ident` without the `_h`
+/// suffix if the path has none
+pub fn header_module_name(path: &str, ident: Ident) -> String {
+    let stem = std::path::Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty());
+    match stem {
+        Some(stem) => normalize_module_name(stem),
+        None => normalize_module_name(ident.as_str().trim_end_matches("_h")),
+    }
+}
+
+/// Lowercase `name` and replace characters that can't appear in an
+/// identifier with `_`, for comparing module names with file names
+pub fn normalize_module_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
@@ -0,0 +1,104 @@
+use syntax::ast::{Attribute, Ident};
+use syntax_pos::edition::Edition;
+
+use super::{classify_item, header_module_name, Placement, PlacementRules};
+use c2rust_ast_builder::mk;
+
+fn header_attr(name: &str, value: &str) -> Vec<Attribute> {
+    let path: Vec<&str> = if name.contains("::") {
+        name.split("::").collect()
+    } else {
+        vec!["c2rust", name]
+    };
+    mk().str_attr(path, value).into_attrs()
+}
+
+#[test]
+fn items_without_header_stay() {
+    syntax::with_globals(Edition::Edition2018, || {
+        let attrs = mk().single_attr("inline").into_attrs();
+        assert_eq!(classify_item(&attrs, Ident::from_str("foo")), Placement::Source);
+    });
+}
+
+#[test]
+fn project_headers_name_their_module() {
+    syntax::with_globals(Edition::Edition2018, || {
+        let attrs = header_attr("header_src", "/home/user/some/workspace/foo-bar.h:3");
+        assert_eq!(
+            classify_item(&attrs, Ident::from_str("foo_bar_h")),
+            Placement::Header {
+                header: "/home/user/some/workspace/foo-bar.h".to_string(),
+                module: "foo_bar".to_string(),
+            },
+        );
+    });
+}
+
+#[test]
+fn system_headers_by_default_and_by_include_dirs() {
+    syntax::with_globals(Edition::Edition2018, || {
+        let stdio = header_attr("header_src", "/usr/include/stdio.h:1");
+        let sdk = header_attr("header_src", "/opt/sdk/include/sdk.h:1");
+        assert_eq!(
+            classify_item(&stdio, Ident::from_str("stdio_h")),
+            Placement::System { header: "/usr/include/stdio.h".to_string() },
+        );
+        assert_eq!(
+            classify_item(&sdk, Ident::from_str("sdk_h")),
+            Placement::Header {
+                header: "/opt/sdk/include/sdk.h".to_string(),
+                module: "sdk".to_string(),
+            },
+        );
+
+        // Include dirs replace `/usr/include` rather than adding to it
+        let rules = PlacementRules {
+            std_include_dirs: Some(vec!["/opt/sdk/include".to_string()]),
+            ..PlacementRules::default()
+        };
+        assert_eq!(
+            rules.classify(&sdk, Ident::from_str("sdk_h")),
+            Placement::System { header: "/opt/sdk/include/sdk.h".to_string() },
+        );
+        assert!(!rules.is_std_header("/usr/include/stdio.h"));
+    });
+}
+
+#[test]
+fn malformed_header_attrs() {
+    syntax::with_globals(Edition::Edition2018, || {
+        for value in &["/home/user/some/workspace/foo.h", ":3", "/home/user/foo.h:three"] {
+            let attrs = header_attr("header_src", value);
+            assert_eq!(classify_item(&attrs, Ident::from_str("foo_h")), Placement::Malformed, "{}", value);
+        }
+    });
+}
+
+#[test]
+fn custom_header_attr() {
+    syntax::with_globals(Edition::Edition2018, || {
+        let rules = PlacementRules {
+            header_attr: "my_tool::origin".to_string(),
+            ..PlacementRules::default()
+        };
+        let custom = header_attr("my_tool::origin", "/home/user/some/workspace/foo.h:1");
+        let default = header_attr("header_src", "/home/user/some/workspace/foo.h:1");
+        assert_eq!(
+            rules.classify(&custom, Ident::from_str("foo_h")),
+            Placement::Header {
+                header: "/home/user/some/workspace/foo.h".to_string(),
+                module: "foo".to_string(),
+            },
+        );
+        assert_eq!(rules.classify(&default, Ident::from_str("foo_h")), Placement::Source);
+    });
+}
+
+#[test]
+fn module_names_fall_back_to_the_ident() {
+    syntax::with_globals(Edition::Edition2018, || {
+        assert_eq!(header_module_name("/home/user/Foo.Bar.h", Ident::from_str("x_h")), "foo_bar");
+        assert_eq!(header_module_name("/", Ident::from_str("Packet_h")), "packet");
+    });
+}
@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustc::session::config::Input;
+use syntax_pos::FileName;
+
+use super::{destination_modules, Options, Reorganizer};
+use crate::command::{RefactorState, Registry};
+use crate::driver;
+use crate::file_io::FileIO;
+use c2rust_ast_printer::pprust;
+
+/// `FileIO` for crates that only exist in memory. The pass never saves the
+/// crate here, so there is nothing to read or write.
+struct NoFiles;
+
+impl FileIO for NoFiles {
+    fn file_exists(&self, _path: &Path) -> bool {
+        false
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<String> {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not part of the in-memory crate", path.display()),
+        ))
+    }
+
+    fn write_file(&self, path: &Path, _s: &str) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("can't write {} from a test", path.display()),
+        ))
+    }
+}
+
+/// Run `f` on a refactoring state for the crate `src`, without touching the
+/// filesystem
+fn with_crate_str<F, R>(src: &str, f: F) -> R
+where
+    F: FnOnce(RefactorState) -> R,
+    R: Send,
+{
+    let rustc_args = ["rustc", "--edition", "2018", "--crate-type", "lib", "test.rs"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect::<Vec<_>>();
+    let mut config = driver::create_config(&rustc_args);
+    config.input = Input::Str {
+        name: FileName::Custom("test.rs".to_string()),
+        input: src.to_string(),
+    };
+    config.input_path = None;
+
+    driver::run_refactoring(config, Registry::new(), Arc::new(NoFiles), HashSet::new(), f)
+}
+
+/// Run `reorganize_definitions` with the `KEY=VALUE` options `args` on the
+/// crate `src`, without touching the filesystem. Returns the reorganized
+/// crate pretty-printed (without comments), or the errors the pass reported.
+pub fn reorganize_str(src: &str, args: &[&str]) -> Result<String, Vec<String>> {
+    let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let mut options = Options::from_args(&args);
+    options.load_plan().map_err(|e| vec![e])?;
+
+    with_crate_str(src, |mut state| {
+        state
+            .transform_crate(options.phase, |st, cx| {
+                let mut reorg = Reorganizer::new(st, cx, &options);
+                reorg.run(&mut *st.krate_mut());
+
+                let source_map = cx.session().source_map();
+                let errors = reorg.errors
+                    .into_inner()
+                    .iter()
+                    .map(|e| e.to_message(source_map))
+                    .collect::<Vec<_>>();
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+
+                let krate = st.krate();
+                Ok(pprust::to_string(|s| s.print_mod(&krate.module, &krate.attrs)))
+            })
+            .expect("Failed to run compiler")
+    })
+}
+
+const TWO_BUFFERS: &str = r#"
+#![feature(register_tool)]
+#![register_tool(c2rust)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:3"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const SIZE: usize = 16;
+    }
+
+    pub fn size() -> usize {
+        buffer_h::SIZE
+    }
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:2"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const SIZE: usize = SIZE_VALUE;
+    }
+
+    pub fn twice() -> usize {
+        2 * buffer_h::SIZE
+    }
+}
+"#;
+
+#[test]
+fn merges_equal_consts() {
+    let src = TWO_BUFFERS.replace("SIZE_VALUE", "0x10");
+    let out = reorganize_str(&src, &[]).unwrap();
+    assert_eq!(out.matches("pub const SIZE: usize").count(), 1, "{}", out);
+    assert!(!out.contains("buffer_h"), "{}", out);
+}
+
+#[test]
+fn reports_conflicting_consts() {
+    let src = TWO_BUFFERS.replace("SIZE_VALUE", "32");
+    let errors = reorganize_str(&src, &[]).unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("two different declarations of SIZE")), "{:?}", errors);
+}
+
+#[test]
+fn maps_merged_consts_to_their_destination() {
+    let src = TWO_BUFFERS.replace("SIZE_VALUE", "0x10");
+    let mapping = with_crate_str(&src, |mut state| {
+        state
+            .transform_crate(driver::Phase::Phase3, |st, cx| {
+                destination_modules(st, cx, &[]).map(|mapping| {
+                    mapping
+                        .values()
+                        .map(|module| module.to_string())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .expect("Failed to run compiler")
+    })
+    .unwrap();
+    // Both copies of SIZE end up in the same module
+    assert_eq!(mapping.len(), 2, "{:?}", mapping);
+    assert_eq!(mapping[0], mapping[1]);
+    assert!(mapping[0] == "buffer" || mapping[0] == "user", "{:?}", mapping);
+}
+
+#[test]
+fn merges_structs_with_same_repr() {
+    let src = r#"
+#![feature(register_tool)]
+#![register_tool(c2rust)]
+
+pub mod packet {
+    #[c2rust::header_src = "/home/user/some/workspace/packet.h:3"]
+    pub mod packet_h {
+        #[repr(C)]
+        #[c2rust::src_loc = "4:0"]
+        pub struct header {
+            pub kind: u8,
+            pub len: u32,
+        }
+    }
+
+    pub fn len(h: &packet_h::header) -> u32 {
+        h.len
+    }
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/packet.h:2"]
+    pub mod packet_h {
+        #[repr(C)]
+        #[c2rust::src_loc = "4:0"]
+        pub struct header {
+            pub kind: u8,
+            pub len: u32,
+        }
+    }
+
+    pub fn kind(h: &packet_h::header) -> u8 {
+        h.kind
+    }
+}
+"#;
+    let out = reorganize_str(src, &[]).unwrap();
+    assert_eq!(out.matches("pub struct header").count(), 1, "{}", out);
+}
+
+#[test]
+fn keeps_structs_with_different_repr_apart() {
+    let src = r#"
+#![feature(register_tool)]
+#![register_tool(c2rust)]
+
+pub mod packet {
+    #[c2rust::header_src = "/home/user/some/workspace/packet.h:3"]
+    pub mod packet_h {
+        #[repr(C)]
+        #[c2rust::src_loc = "4:0"]
+        pub struct header {
+            pub kind: u8,
+            pub len: u32,
+        }
+    }
+
+    pub fn len(h: &packet_h::header) -> u32 {
+        h.len
+    }
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/packet.h:2"]
+    pub mod packet_h {
+        #[repr(C, packed)]
+        #[c2rust::src_loc = "4:0"]
+        pub struct header {
+            pub kind: u8,
+            pub len: u32,
+        }
+    }
+
+    pub fn kind(h: &packet_h::header) -> u8 {
+        h.kind
+    }
+}
+"#;
+    let errors = reorganize_str(src, &[]).unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("two different declarations of header")), "{:?}", errors);
+}
+
+#[test]
+fn merges_equal_generic_aliases() {
+    let src = r#"
+#![feature(register_tool)]
+#![register_tool(c2rust)]
+
+pub mod point {
+    #[c2rust::header_src = "/home/user/some/workspace/vec.h:3"]
+    pub mod vec_h {
+        #[c2rust::src_loc = "4:0"]
+        pub type Vec2<T> = [T; 2];
+    }
+
+    pub fn origin() -> vec_h::Vec2<u32> {
+        [0, 0]
+    }
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/vec.h:2"]
+    pub mod vec_h {
+        #[c2rust::src_loc = "4:0"]
+        pub type Vec2<T> = [T; 2];
+    }
+
+    pub fn unit() -> vec_h::Vec2<u32> {
+        [1, 0]
+    }
+}
+"#;
+    let out = reorganize_str(src, &[]).unwrap();
+    assert_eq!(out.matches("pub type Vec2").count(), 1, "{}", out);
+}
+
+#[test]
+fn keeps_generic_and_plain_aliases_apart() {
+    let src = r#"
+#![feature(register_tool)]
+#![register_tool(c2rust)]
+
+pub mod point {
+    #[c2rust::header_src = "/home/user/some/workspace/vec.h:3"]
+    pub mod vec_h {
+        #[c2rust::src_loc = "4:0"]
+        pub type Vec2<T> = [T; 2];
+    }
+
+    pub fn origin() -> vec_h::Vec2<u32> {
+        [0, 0]
+    }
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/vec.h:2"]
+    pub mod vec_h {
+        #[c2rust::src_loc = "4:0"]
+        pub type Vec2 = [u32; 2];
+    }
+
+    pub fn unit() -> vec_h::Vec2 {
+        [1, 0]
+    }
+}
+"#;
+    let errors = reorganize_str(src, &[]).unwrap_err();
+    assert!(errors.iter().any(|e| e.contains("two different declarations of Vec2")), "{:?}", errors);
+}
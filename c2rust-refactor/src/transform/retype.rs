@@ -661,7 +661,7 @@ fn parse_rule<'a>(p: &mut Parser<'a>) -> PResult<'a, Rule> {
 }
 
 impl Command for TypeFixRules {
-    fn run(&mut self, state: &mut RefactorState) {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
         let rules = self.rules.iter()
             .map(|s| driver::run_parser(state.session(), s, parse_rule))
             .collect::<Vec<_>>();
@@ -692,6 +692,7 @@ impl Command for TypeFixRules {
                 TypeckLoopResult::Finished
             }
         }).expect("Could not retype crate!");
+        Ok(())
     }
 }
 
@@ -780,7 +781,7 @@ impl AutoRetype {
 }
 
 impl Command for AutoRetype {
-    fn run(&mut self, state: &mut RefactorState) {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
         let type_annotations = state.transform_crate(Phase::Phase3, |st, cx| {
             let mut retype_prep = RetypePrepFolder::new(st, cx, &self.mark_types);
             st.map_krate(|krate| {
@@ -803,6 +804,7 @@ impl Command for AutoRetype {
                 krate.visit(&mut folder)
             });
         }).expect("Failed to run compiler");
+        Ok(())
     }
 }
 
@@ -133,7 +133,7 @@ impl Transform for InsertRemoveArgs {
 pub struct TestTypeckLoop;
 
 impl Command for TestTypeckLoop {
-    fn run(&mut self, state: &mut RefactorState) {
+    fn run(&mut self, state: &mut RefactorState) -> Result<(), String> {
         let mut i = 3;
         state.run_typeck_loop(|_krate, _st, _cx| {
             i -= 1;
@@ -144,6 +144,7 @@ impl Command for TestTypeckLoop {
                 TypeckLoopResult::Iterate
             }
         }).unwrap();
+        Ok(())
     }
 }
 
@@ -61,10 +61,6 @@ pub mod bar {
 pub mod foo {
     use libc;
 
-    use crate::bar::bar_t;
-    use crate::bar::Bar;
-    use crate::compat_h_0::conflicting;
-
     // Comment on foo_t
 
     #[repr(C)]
@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+    pub struct Buffer {
+        pub len: usize,
+    }
+}
+
+pub mod io {
+    use super::buffer::Buffer;
+
+    pub fn buffer_len(b: &Buffer) -> usize {
+        b.len
+    }
+
+    pub fn is_empty(b: &super::buffer::Buffer) -> bool {
+        self::buffer_len(b) == 0
+    }
+}
+
+fn main() {}
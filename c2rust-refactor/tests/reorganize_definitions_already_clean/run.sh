@@ -0,0 +1,12 @@
+#!/bin/sh
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# A crate without header modules, e.g. one the pass already ran on, is left
+# exactly as it was, relative paths included.
+$refactor \
+    reorganize_definitions \
+    -- old.rs $rustflags
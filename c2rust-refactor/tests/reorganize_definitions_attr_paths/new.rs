@@ -0,0 +1,17 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+
+    // =============== BEGIN buffer_h ================
+    pub struct Buf {
+        pub len: usize,
+    }
+
+    #[c2rust::related(crate::buffer::Buf)]
+    pub fn empty_len() -> usize {
+        0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:1"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Buf {
+            pub len: usize,
+        }
+    }
+
+    #[c2rust::related(buffer_h::Buf)]
+    pub fn empty_len() -> usize {
+        0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,89 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# Benchmark: two modules include the same header with 5000 named and 5000
+# unnamed structs, so every declaration has to be deduplicated against its
+# copy from the other module. The unnamed structs only differ in the length
+# of their last field, so matching them by content has to stay close to
+# linear for the run to finish within `limit` seconds (60 by default, or
+# $REORGANIZE_BENCH_LIMIT).
+count=5000
+limit=${REORGANIZE_BENCH_LIMIT:-60}
+
+header_items() {
+    i=0
+    while [ $i -lt $count ]; do
+        echo "        #[c2rust::src_loc = \"$((2 * i + 2)):8\"]"
+        echo "        pub struct S$i {"
+        echo "            pub x: i32,"
+        echo "        }"
+        echo "        #[c2rust::src_loc = \"$((2 * i + 3)):8\"]"
+        echo "        pub struct C2RustUnnamed_$i {"
+        echo "            pub a: i32,"
+        echo "            pub b: i32,"
+        echo "            pub c: i32,"
+        echo "            pub x: [u8; $((i + 1))],"
+        echo "        }"
+        i=$((i + 1))
+    done
+}
+
+{
+    echo '#![register_tool(c2rust)]'
+    echo '#![allow(dead_code)]'
+    echo
+    for m in big user; do
+        echo "pub mod $m {"
+        echo '    #[c2rust::header_src = "/home/user/some/workspace/big.h:1"]'
+        echo '    pub mod big_h {'
+        header_items
+        echo '    }'
+        echo
+        echo "    pub fn ${m}_x(s: &big_h::S0) -> i32 {"
+        echo '        s.x'
+        echo '    }'
+        echo '}'
+        echo
+    done
+    echo 'fn main() {}'
+} > old.rs
+
+{
+    echo '#![register_tool(c2rust)]'
+    echo '#![allow(dead_code)]'
+    echo
+    echo 'pub mod big {'
+    echo
+    echo '    // =============== BEGIN big_h ================'
+    header_items | grep -v src_loc
+    echo
+    echo '    pub fn big_x(s: &crate::big::S0) -> i32 {'
+    echo '        s.x'
+    echo '    }'
+    echo '}'
+    echo
+    echo 'pub mod user {'
+    echo
+    echo '    pub fn user_x(s: &crate::big::S0) -> i32 {'
+    echo '        s.x'
+    echo '    }'
+    echo '}'
+    echo
+    echo 'fn main() {}'
+} > new.rs
+
+start=`date +%s`
+$refactor \
+    reorganize_definitions \
+    -- old.rs $rustflags
+end=`date +%s`
+echo "reorganize_definitions on $((2 * count)) declarations: $((end - start))s" >&2
+if [ $((end - start)) -gt $limit ]; then
+    echo "reorganize_definitions took longer than ${limit}s" >&2
+    exit 1
+fi
@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod local {
+
+    // =============== BEGIN local_h ================
+    pub type Callback = dyn Fn() + Send;
+
+    pub fn run_local(cb: &crate::local::Callback) {}
+}
+
+pub mod shared {
+
+    // =============== BEGIN shared_h ================
+    pub type Callback = dyn Fn() + Send + Sync;
+
+    pub fn run_shared(cb: &crate::shared::Callback) {}
+}
+
+fn main() {}
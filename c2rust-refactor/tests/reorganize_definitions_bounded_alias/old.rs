@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod local {
+    #[c2rust::header_src = "/home/user/some/workspace/local.h:1"]
+    pub mod local_h {
+        #[c2rust::src_loc = "3:0"]
+        pub type Callback = dyn Fn() + Send;
+    }
+
+    pub fn run_local(cb: &local_h::Callback) {}
+}
+
+pub mod shared {
+    #[c2rust::header_src = "/home/user/some/workspace/shared.h:1"]
+    pub mod shared_h {
+        #[c2rust::src_loc = "3:0"]
+        pub type Callback = dyn Fn() + Send + Sync;
+    }
+
+    pub fn run_shared(cb: &shared_h::Callback) {}
+}
+
+fn main() {}
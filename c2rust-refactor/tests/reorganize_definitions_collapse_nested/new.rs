@@ -0,0 +1,17 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+// =============== BEGIN stdio_h ================
+pub type stdlib_fpos_t = i64;
+
+pub type fpos_t = u8;
+
+pub mod log {
+
+    pub fn rewind(pos: crate::stdlib_fpos_t) -> crate::stdlib_fpos_t {
+        pos
+    }
+}
+
+fn main() {}
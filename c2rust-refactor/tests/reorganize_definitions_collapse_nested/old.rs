@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub type fpos_t = u8;
+
+pub mod log {
+    #[c2rust::header_src = "/usr/include/stdio.h:1"]
+    pub mod stdio_h {
+        #[c2rust::src_loc = "84:1"]
+        pub type fpos_t = i64;
+    }
+
+    pub fn rewind(pos: stdio_h::fpos_t) -> stdio_h::fpos_t {
+        pos
+    }
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+// =============== BEGIN compat_h ================
+pub struct conflicting {
+    pub x: i32,
+}
+
+pub mod foo {
+
+    pub fn make() -> crate::conflicting {
+        crate::conflicting { x: 0 }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod foo {
+    #[c2rust::header_src = "/home/user/some/workspace/compat.h:2"]
+    pub mod compat_h {
+        #[c2rust::src_loc = "3:0"]
+        pub struct conflicting {
+            pub x: i32,
+        }
+    }
+
+    pub fn make() -> compat_h::conflicting {
+        compat_h::conflicting { x: 0 }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod settings {
+    pub struct cfg_limits_gfx {
+        pub max_textures: i64,
+    }
+    pub struct cfg_limits {
+        pub max_sockets: i32,
+    }
+}
+
+pub mod net {
+
+    pub fn sockets(limits: &crate::settings::cfg_limits) -> i32 {
+        limits.max_sockets
+    }
+}
+
+pub mod gfx {
+
+    pub fn textures(limits: &crate::settings::cfg_limits_gfx) -> i64 {
+        limits.max_textures
+    }
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod net {
+    #[c2rust::header_src = "/home/user/some/workspace/net.h:1"]
+    pub mod net_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct cfg_limits {
+            pub max_sockets: i32,
+        }
+    }
+
+    pub fn sockets(limits: &net_h::cfg_limits) -> i32 {
+        limits.max_sockets
+    }
+}
+
+pub mod gfx {
+    #[c2rust::header_src = "/home/user/some/workspace/gfx.h:1"]
+    pub mod gfx_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct cfg_limits {
+            pub max_textures: i64,
+        }
+    }
+
+    pub fn textures(limits: &gfx_h::cfg_limits) -> i64 {
+        limits.max_textures
+    }
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod a {
+    pub mod util_h {
+        pub const fn answer() -> i32 {
+            42
+        }
+    }
+
+    pub const A: i32 = util_h::answer();
+}
+
+pub mod b {
+    pub mod util_h {
+        pub fn answer() -> i32 {
+            42
+        }
+    }
+
+    pub fn b() -> i32 {
+        util_h::answer()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod a {
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:1"]
+    pub mod util_h {
+        #[c2rust::src_loc = "3:0"]
+        pub const fn answer() -> i32 {
+            42
+        }
+    }
+
+    pub const A: i32 = util_h::answer();
+}
+
+pub mod b {
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:1"]
+    pub mod util_h {
+        #[c2rust::src_loc = "3:0"]
+        pub fn answer() -> i32 {
+            42
+        }
+    }
+
+    pub fn b() -> i32 {
+        util_h::answer()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+#![register_tool(c2rust)]
+
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:3"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const SIZE: usize = 16;
+        #[c2rust::src_loc = "5:0"]
+        pub const EMPTY: i32 = -1;
+    }
+
+    pub static mut BUF: [u8; buffer_h::SIZE] = [0; buffer_h::SIZE];
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:2"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const SIZE: usize = 0x10;
+        #[c2rust::src_loc = "5:0"]
+        pub const EMPTY: i32 = -(1i32);
+    }
+
+    pub fn first(buf: [u8; buffer_h::SIZE]) -> i32 {
+        if buf[0] == 0 { buffer_h::EMPTY } else { buf[0] as i32 }
+    }
+}
+
+fn main() {}
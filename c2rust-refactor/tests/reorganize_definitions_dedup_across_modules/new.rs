@@ -0,0 +1,38 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod clock {
+
+    // =============== BEGIN clock_h ================
+    pub type ticks_t = i64;
+
+    #[repr(C)]
+    pub struct timeval {
+        pub tv_sec: i64,
+        pub tv_usec: i64,
+    }
+
+    pub fn now(ticks: crate::clock::ticks_t) -> timeval {
+        timeval {
+            tv_sec: ticks / 1000,
+            tv_usec: ticks % 1000 * 1000,
+        }
+    }
+}
+
+pub mod net {
+    #[repr(C)]
+    pub struct sockaddr {
+        pub sa_family: u16,
+    }
+
+    pub fn timeout() -> crate::clock::timeval {
+        crate::clock::timeval {
+            tv_sec: 5,
+            tv_usec: 0,
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,41 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod clock {
+    #[c2rust::header_src = "/home/user/some/workspace/clock.h:1"]
+    pub mod clock_h {
+        #[c2rust::src_loc = "3:1"]
+        pub type ticks_t = i64;
+    }
+
+    #[repr(C)]
+    pub struct timeval {
+        pub tv_sec: i64,
+        pub tv_usec: i64,
+    }
+
+    pub fn now(ticks: clock_h::ticks_t) -> timeval {
+        timeval { tv_sec: ticks / 1000, tv_usec: ticks % 1000 * 1000 }
+    }
+}
+
+pub mod net {
+    #[repr(C)]
+    pub struct timeval {
+        pub tv_sec: i64,
+        pub tv_usec: i64,
+    }
+
+    #[repr(C)]
+    pub struct sockaddr {
+        pub sa_family: u16,
+    }
+
+    pub fn timeout() -> timeval {
+        timeval { tv_sec: 5, tv_usec: 0 }
+    }
+}
+
+fn main() {}
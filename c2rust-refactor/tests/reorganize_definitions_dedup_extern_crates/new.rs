@@ -0,0 +1,25 @@
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod io {
+    #[allow(unused_extern_crates)]
+    extern crate libc as c;
+
+    pub fn stdin() -> c::c_int {
+        0
+    }
+}
+
+pub mod sys {
+
+    // =============== BEGIN sys_h ================
+    pub type fd_t = i32;
+
+    pub fn stdout() -> crate::sys::fd_t {
+        1
+    }
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod io {
+    #[allow(unused_extern_crates)]
+    extern crate libc;
+    extern crate libc as c;
+
+    pub fn stdin() -> c::c_int {
+        0
+    }
+}
+
+pub mod sys {
+    #[c2rust::header_src = "/home/user/some/workspace/sys.h:1"]
+    pub mod sys_h {
+        #[c2rust::src_loc = "3:1"]
+        pub type fd_t = i32;
+    }
+
+    pub fn stdout() -> sys_h::fd_t {
+        1
+    }
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod point {
+
+    // =============== BEGIN point_h ================
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    pub fn origin() -> crate::point::Point {
+        crate::point::Point { x: 0, y: 0 }
+    }
+}
+
+pub mod user {
+
+    pub fn show(p: crate::point::Point) -> String {
+        format!("{:?}", p)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,39 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod point {
+    #[c2rust::header_src = "/home/user/some/workspace/point.h:1"]
+    pub mod point_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        #[c2rust::src_loc = "3:0"]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+
+    pub fn origin() -> point_h::Point {
+        point_h::Point { x: 0, y: 0 }
+    }
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/point.h:1"]
+    pub mod point_h {
+        #[derive(Clone, Copy, Debug)]
+        #[repr(C)]
+        #[c2rust::src_loc = "3:0"]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+
+    pub fn show(p: point_h::Point) -> String {
+        format!("{:?}", p)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod shapes {
+    #[c2rust::header_src = "/usr/include/x86_64-linux-gnu/bits/types.h:1"]
+    pub mod types_h {
+        #[c2rust::src_loc = "42:1"]
+        pub type __uint32_t = u32;
+    }
+
+    #[c2rust::header_src = "/home/user/some/workspace/shapes.h:2"]
+    pub mod shapes_h {
+        #[c2rust::src_loc = "3:1"]
+        pub const SHAPE_CIRCLE: u32 = 0;
+        #[c2rust::src_loc = "5:8"]
+        pub struct shape {
+            pub kind: crate::shapes::types_h::__uint32_t,
+        }
+    }
+
+    pub fn is_circle(s: &shapes_h::shape) -> bool {
+        s.kind == shapes_h::SHAPE_CIRCLE
+    }
+}
+
+fn main() {}
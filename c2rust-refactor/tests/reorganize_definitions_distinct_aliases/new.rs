@@ -0,0 +1,26 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod bytes {
+
+    // =============== BEGIN bytes_h ================
+    pub type handle = u8;
+    pub const HANDLE_SIZE: usize = 1;
+
+    pub fn first(h: crate::bytes::handle) -> usize {
+        crate::bytes::HANDLE_SIZE + h as usize
+    }
+}
+
+pub mod ptrs {
+
+    // =============== BEGIN ptrs_h ================
+    pub type handle = *mut core::ffi::c_void;
+    pub const HANDLE_SIZE: usize = 8;
+
+    pub fn is_null(h: crate::ptrs::handle) -> bool {
+        crate::ptrs::HANDLE_SIZE > 0 && h.is_null()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod bytes {
+    #[c2rust::header_src = "/home/user/some/workspace/bytes.h:1"]
+    pub mod bytes_h {
+        #[c2rust::src_loc = "3:0"]
+        pub type handle = u8;
+        #[c2rust::src_loc = "4:0"]
+        pub const HANDLE_SIZE: usize = 1;
+    }
+
+    pub fn first(h: bytes_h::handle) -> usize {
+        bytes_h::HANDLE_SIZE + h as usize
+    }
+}
+
+pub mod ptrs {
+    #[c2rust::header_src = "/home/user/some/workspace/ptrs.h:1"]
+    pub mod ptrs_h {
+        #[c2rust::src_loc = "3:0"]
+        pub type handle = *mut core::ffi::c_void;
+        #[c2rust::src_loc = "4:0"]
+        pub const HANDLE_SIZE: usize = 8;
+    }
+
+    pub fn is_null(h: ptrs_h::handle) -> bool {
+        ptrs_h::HANDLE_SIZE > 0 && h.is_null()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod point_h {
+    /// A point on the screen.
+    /// Coordinates are in pixels.
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+}
+pub mod a {
+
+    pub fn origin() -> crate::point_h::Point {
+        crate::point_h::Point { x: 0, y: 0 }
+    }
+}
+
+pub mod b {
+
+    pub fn x_of(p: crate::point_h::Point) -> i32 {
+        p.x
+    }
+}
+
+fn main() {}
@@ -0,0 +1,42 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod a {
+    #[c2rust::header_src = "/home/user/some/workspace/point.h:1"]
+    pub mod point_h {
+        /// A point on the screen.
+        #[c2rust::src_loc = "2:8"]
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+
+    pub fn origin() -> point_h::Point {
+        point_h::Point { x: 0, y: 0 }
+    }
+}
+
+pub mod b {
+    #[c2rust::header_src = "/home/user/some/workspace/point.h:1"]
+    pub mod point_h {
+        /// A point on the screen.
+        /// Coordinates are in pixels.
+        #[c2rust::src_loc = "2:8"]
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+
+    pub fn x_of(p: point_h::Point) -> i32 {
+        p.x
+    }
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+
+    // =============== BEGIN buffer_h ================
+    pub struct Foo {
+        pub x: i32,
+    }
+
+    /// Makes a [buffer::Foo].
+    pub fn make() -> crate::buffer::Foo {
+        crate::buffer::Foo { x: 0 }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:3"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "4:0"]
+        pub struct Foo {
+            pub x: i32,
+        }
+    }
+
+    /// Makes a [buffer_h::Foo].
+    pub fn make() -> buffer_h::Foo {
+        buffer_h::Foo { x: 0 }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+$refactor \
+    reorganize_definitions dry_run=1 \
+    -- old.rs $rustflags > log
+grep -q "^crate::shapes::shapes_h::shape -> crate::shapes$" log
+grep -q "^crate::shapes::types_h::__uint32_t -> crate::stdlib$" log
+
+# The crate itself must be left alone
+if [ -f old.new ]; then
+    cmp old.rs old.new
+else
+    cp old.rs old.new
+fi
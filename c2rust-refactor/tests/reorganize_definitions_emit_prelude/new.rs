@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod foo {
+
+    // =============== BEGIN foo_h ================
+    pub struct Foo {
+        pub x: i32,
+    }
+    pub type FooRef = *mut crate::foo::Foo;
+    pub(crate) struct Hidden;
+
+    pub fn get(f: crate::foo::FooRef) -> i32 {
+        unsafe { (*f).x }
+    }
+}
+
+fn main() {}
+pub mod prelude {
+    pub use crate::foo::Foo;
+    pub use crate::foo::FooRef;
+}
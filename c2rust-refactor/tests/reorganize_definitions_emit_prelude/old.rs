@@ -0,0 +1,23 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod foo {
+    #[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]
+    pub mod foo_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Foo {
+            pub x: i32,
+        }
+        #[c2rust::src_loc = "5:0"]
+        pub type FooRef = *mut Foo;
+        #[c2rust::src_loc = "6:0"]
+        pub(crate) struct Hidden;
+    }
+
+    pub fn get(f: foo_h::FooRef) -> i32 {
+        unsafe { (*f).x }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/Buffer.h:1"]
+    pub mod Buffer_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Buffer {
+            pub len: usize,
+        }
+    }
+
+    pub fn buffer_len(b: &Buffer_h::Buffer) -> usize {
+        b.len
+    }
+}
+
+pub mod buf {
+    #[c2rust::header_src = "/home/user/some/workspace/Buffer.h:1"]
+    pub mod Buffer_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Buffer {
+            pub len: usize,
+        }
+    }
+
+    pub fn buf_empty(b: &Buffer_h::Buffer) -> bool {
+        b.len == 0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod foo_h {
+    pub struct Foo {
+        pub y: i64,
+    }
+}
+
+pub mod bar {
+    #[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]
+    pub mod foo_h {
+        #[c2rust::src_loc = "3:0"]
+        pub struct Foo {
+            pub x: i32,
+        }
+    }
+
+    pub fn get(foo: foo_h::Foo) -> i32 {
+        foo.x
+    }
+}
+
+fn main() {}
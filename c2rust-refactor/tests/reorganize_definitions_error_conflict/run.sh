@@ -0,0 +1,17 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# The pass must name both headers and fail without rewriting the crate.
+if $refactor \
+    reorganize_definitions "prefix_map=cfg_=>settings" \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "module settings would get two different declarations of cfg_limits, from /home/user/some/workspace/net.h and /home/user/some/workspace/gfx.h" log
+cp old.rs old.new
@@ -0,0 +1,21 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod outer {
+    pub mod middle {
+        pub mod inner {
+            #[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]
+            pub mod foo_h {
+                #[c2rust::src_loc = "3:1"]
+                pub type foo_t = u32;
+            }
+
+            pub fn get(x: foo_h::foo_t) -> u32 {
+                x
+            }
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# The header module sits four levels deep, so the pass must refuse to run
+# and fail without rewriting the crate.
+if $refactor \
+    reorganize_definitions max_module_depth=3 \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "modules are nested 4 levels deep, exceeding max_module_depth=3" log
+cp old.rs old.new
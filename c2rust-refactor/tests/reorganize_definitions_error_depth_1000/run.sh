@@ -0,0 +1,45 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# A header module nested 1000 modules deep: with the default options the pass
+# must refuse to run with an error rather than overflow the stack.
+depth=1000
+
+{
+    echo '#![register_tool(c2rust)]'
+    echo '#![allow(dead_code)]'
+    echo '#![allow(non_camel_case_types)]'
+    echo
+    i=1
+    while [ $i -lt $depth ]; do
+        echo "pub mod m$i {"
+        i=$((i + 1))
+    done
+    echo '#[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]'
+    echo 'pub mod foo_h {'
+    echo '    #[c2rust::src_loc = "3:1"]'
+    echo '    pub type foo_t = u32;'
+    echo '}'
+    i=1
+    while [ $i -lt $depth ]; do
+        echo '}'
+        i=$((i + 1))
+    done
+    echo
+    echo 'fn main() {}'
+} > old.rs
+cp old.rs new.rs
+
+if $refactor \
+    reorganize_definitions \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "modules are nested $depth levels deep, exceeding max_module_depth=256" log
+cp old.rs old.new
@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod prelude {
+    pub fn hello() {}
+}
+
+pub mod foo {
+    #[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]
+    pub mod foo_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Foo {
+            pub x: i32,
+        }
+    }
+
+    pub fn get(f: &foo_h::Foo) -> i32 {
+        f.x
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# The crate already has a prelude module, so emitting another one must make
+# the pass fail without rewriting the crate.
+if $refactor \
+    reorganize_definitions emit_prelude=1 \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "module prelude is defined more than once in the same parent module" log
+cp old.rs old.new
@@ -0,0 +1,15 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod bar {
+    #[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]
+    pub struct Foo {
+        pub x: i32,
+    }
+
+    pub fn get(foo: Foo) -> i32 {
+        foo.x
+    }
+}
+
+fn main() {}
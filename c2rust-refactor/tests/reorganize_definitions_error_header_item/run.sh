@@ -0,0 +1,17 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# The pass must report the problem and fail without rewriting the crate.
+if $refactor \
+    reorganize_definitions \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "header_src attribute on Foo, which is not a module" log
+cp old.rs old.new
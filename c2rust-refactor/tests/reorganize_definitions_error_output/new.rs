@@ -0,0 +1,17 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod foo {
+    #[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]
+    pub mod foo_h {
+        #[c2rust::src_loc = "2:0"]
+        pub type foo_t = i32;
+    }
+
+    pub fn get(x: foo_h::foo_t) -> i32 {
+        x
+    }
+}
+
+fn main() {}
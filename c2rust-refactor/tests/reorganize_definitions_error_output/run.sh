@@ -0,0 +1,18 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# The module map can't be written into a directory that doesn't exist, so
+# the command must fail without rewriting the crate.
+if $refactor \
+    reorganize_definitions map_out=no_such_dir/map.json \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "could not write module map no_such_dir/map.json" log
+cp old.rs old.new
@@ -0,0 +1,23 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# A plan that isn't valid JSON must make the command fail without rewriting
+# the crate.
+cat > plan.json <<'PLAN'
+[routes]
+'crate::foo::foo_h::foo_t' = "crate::foo"
+PLAN
+if $refactor \
+    reorganize_definitions plan_in=plan.json \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "Could not parse reorganization plan plan.json" log
+rm plan.json
+cp old.rs old.new
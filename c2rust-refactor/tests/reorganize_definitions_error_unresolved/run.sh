@@ -0,0 +1,18 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# The pinned module path has an empty segment, so there is no module to move
+# foo_t into and the pass must fail without rewriting the crate.
+if $refactor \
+    reorganize_definitions pin=foo_t:util::::io \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly succeeded" >&2
+    exit 1
+fi
+grep -q "could not find a destination module for foo_t" log
+cp old.rs old.new
@@ -0,0 +1,21 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod io {
+    extern "C" {
+        // =============== BEGIN io_h ================
+        pub fn io_read(fd: i32) -> i32;
+        pub fn io_write(fd: i32, byte: i32) -> i32;
+    }
+    extern "system" {
+        pub fn io_close(fd: i32) -> i32;
+    }
+
+    pub unsafe fn copy_byte(from: i32, to: i32) -> i32 {
+        let byte = crate::io::io_read(from);
+        crate::io::io_close(from);
+        crate::io::io_write(to, byte)
+    }
+}
+
+fn main() {}
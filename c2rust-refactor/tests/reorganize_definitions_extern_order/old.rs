@@ -0,0 +1,29 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod io {
+    #[c2rust::header_src = "/home/user/some/workspace/io.h:1"]
+    pub mod io_h {
+        extern "C" {
+            #[c2rust::src_loc = "2:1"]
+            pub fn io_read(fd: i32) -> i32;
+        }
+        extern "system" {
+            #[c2rust::src_loc = "3:1"]
+            pub fn io_close(fd: i32) -> i32;
+        }
+        extern "C" {
+            #[c2rust::src_loc = "4:1"]
+            pub fn io_write(fd: i32, byte: i32) -> i32;
+        }
+    }
+
+    pub unsafe fn copy_byte(from: i32, to: i32) -> i32 {
+        let byte = io_h::io_read(from);
+        io_h::io_close(from);
+        io_h::io_write(to, byte)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod io {
+
+    pub use self::ffi::*;
+    pub mod ffi {
+        use super::*;
+        extern "C" {
+            // =============== BEGIN io_h ================
+            pub fn io_open() -> i32;
+            pub fn io_read(fd: i32) -> i32;
+            pub fn io_close(fd: i32) -> i32;
+        }
+    }
+
+    pub fn roundtrip() -> i32 {
+        unsafe {
+            let fd = crate::io::io_open();
+            crate::io::io_read(fd);
+            crate::io::io_close(fd)
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod io {
+    #[c2rust::header_src = "/home/user/some/workspace/io.h:1"]
+    pub mod io_h {
+        extern "C" {
+            #[c2rust::src_loc = "2:0"]
+            pub fn io_open() -> i32;
+            #[c2rust::src_loc = "3:0"]
+            pub fn io_read(fd: i32) -> i32;
+            #[c2rust::src_loc = "4:0"]
+            pub fn io_close(fd: i32) -> i32;
+        }
+    }
+
+    pub fn roundtrip() -> i32 {
+        unsafe {
+            let fd = io_h::io_open();
+            io_h::io_read(fd);
+            io_h::io_close(fd)
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod util_h {
+    extern "C" {
+        pub fn util_init();
+    }
+
+    pub fn helper() -> u32 {
+        1
+    }
+
+    // =============== BEGIN util_h ================
+    pub const LIMIT: u32 = 8;
+}
+
+pub mod app {
+
+    pub fn run() -> u32 {
+        unsafe { crate::util_h::util_init() };
+        crate::util_h::LIMIT + crate::util_h::helper()
+    }
+}
+
+fn main() {}
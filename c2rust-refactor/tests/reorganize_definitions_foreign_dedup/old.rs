@@ -0,0 +1,32 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod util_h {
+    extern "C" {
+        pub fn util_init();
+    }
+
+    pub fn helper() -> u32 {
+        1
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:2"]
+    pub mod util_h {
+        extern "C" {
+            #[c2rust::src_loc = "3:0"]
+            pub fn util_init();
+        }
+        #[c2rust::src_loc = "4:0"]
+        pub const LIMIT: u32 = 8;
+    }
+
+    pub fn run() -> u32 {
+        unsafe { util_h::util_init() };
+        util_h::LIMIT + crate::util_h::helper()
+    }
+}
+
+fn main() {}
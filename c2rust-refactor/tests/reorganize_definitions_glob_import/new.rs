@@ -0,0 +1,26 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+pub mod geo {
+
+    // =============== BEGIN geo_h ================
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    pub fn origin() -> crate::geo::Point {
+        crate::geo::Point { x: 0, y: 0 }
+    }
+}
+
+pub mod app {
+    use crate::geo::*;
+
+    pub fn unit() -> crate::geo::Point {
+        crate::geo::Point { x: 1, y: 1 }
+    }
+}
+
+fn main() {}
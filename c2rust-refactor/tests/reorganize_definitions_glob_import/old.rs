@@ -0,0 +1,37 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+pub mod geo {
+    #[c2rust::header_src = "/home/user/some/workspace/geo.h:1"]
+    pub mod geo_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+
+    pub fn origin() -> geo_h::Point {
+        geo_h::Point { x: 0, y: 0 }
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/geo.h:1"]
+    pub mod geo_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+    use self::geo_h::*;
+
+    pub fn unit() -> Point {
+        Point { x: 1, y: 1 }
+    }
+}
+
+fn main() {}
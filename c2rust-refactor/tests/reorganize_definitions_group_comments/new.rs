@@ -0,0 +1,18 @@
+#![register_tool(c2rust)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+pub mod graphics {
+
+    // from gfx.h
+    pub const gfx_init: u32 = 1;
+    // from draw.h
+    pub const gfx_line: u32 = 2;
+    pub const gfx_rect: u32 = 3;
+
+    pub fn setup() -> u32 {
+        crate::graphics::gfx_init + crate::graphics::gfx_line + crate::graphics::gfx_rect
+    }
+}
+
+fn main() {}
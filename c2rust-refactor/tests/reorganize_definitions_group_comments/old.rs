@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+pub mod graphics {
+    #[c2rust::header_src = "/home/user/some/workspace/gfx.h:2"]
+    pub mod gfx_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const gfx_init: u32 = 1;
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/draw.h:3"]
+    pub mod draw_h {
+        #[c2rust::src_loc = "7:0"]
+        pub const gfx_line: u32 = 2;
+        #[c2rust::src_loc = "8:0"]
+        pub const gfx_rect: u32 = 3;
+    }
+
+    pub fn setup() -> u32 {
+        gfx_h::gfx_init + draw_h::gfx_line + draw_h::gfx_rect
+    }
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+pub mod buffer {
+
+    // =============== BEGIN Buffer_h ================
+    pub struct Buffer {
+        pub len: usize,
+    }
+
+    pub type Len = usize;
+
+    pub fn buffer_len(b: &crate::buffer::Buffer) -> Len {
+        b.len
+    }
+}
+
+pub mod buf {
+    use super::buffer::{Buffer, Len};
+
+    pub fn buf_len(b: &crate::buffer::Buffer) -> Len {
+        b.len
+    }
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/Buffer.h:1"]
+    pub mod Buffer_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Buffer {
+            pub len: usize,
+        }
+    }
+
+    pub type Len = usize;
+
+    pub fn buffer_len(b: &Buffer_h::Buffer) -> Len {
+        b.len
+    }
+}
+
+pub mod buf {
+    #[c2rust::header_src = "/home/user/some/workspace/Buffer.h:1"]
+    pub mod Buffer_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Buffer {
+            pub len: usize,
+        }
+    }
+    use super::buffer::Len;
+    use self::Buffer_h::Buffer;
+
+    pub fn buf_len(b: &Buffer) -> Len {
+        b.len
+    }
+}
+
+fn main() {}
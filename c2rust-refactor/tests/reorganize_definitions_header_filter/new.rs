@@ -0,0 +1,24 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod shape {
+
+    // =============== BEGIN shape_h ================
+    pub struct Shape {
+        pub w: i32,
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:2"]
+    pub mod util_h {
+        pub type unit = i32;
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/color.h:3"]
+    pub mod color_h {
+        pub type color = u32;
+    }
+
+    pub fn make(w: util_h::unit, _c: color_h::color) -> crate::shape::Shape {
+        crate::shape::Shape { w }
+    }
+}
+
+fn main() {}
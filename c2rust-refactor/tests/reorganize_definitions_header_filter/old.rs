@@ -0,0 +1,29 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod shape {
+    #[c2rust::header_src = "/home/user/some/workspace/shape.h:1"]
+    pub mod shape_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Shape {
+            pub w: i32,
+        }
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:2"]
+    pub mod util_h {
+        #[c2rust::src_loc = "2:1"]
+        pub type unit = i32;
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/color.h:3"]
+    pub mod color_h {
+        #[c2rust::src_loc = "2:1"]
+        pub type color = u32;
+    }
+
+    pub fn make(w: util_h::unit, _c: color_h::color) -> shape_h::Shape {
+        shape_h::Shape { w }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buf {
+
+    // =============== BEGIN buf_h ================
+    pub struct Buf {
+        pub n: usize,
+    }
+
+    impl crate::buf::Buf {
+        pub fn len(&self) -> usize {
+            self.n
+        }
+    }
+}
+
+pub mod user {
+
+    pub fn size(b: &crate::buf::Buf) -> usize {
+        b.len()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,41 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod buf {
+    #[c2rust::header_src = "/home/user/some/workspace/buf.h:1"]
+    pub mod buf_h {
+        #[c2rust::src_loc = "3:0"]
+        pub struct Buf {
+            pub n: usize,
+        }
+    }
+
+    impl buf_h::Buf {
+        pub fn len(&self) -> usize {
+            self.n
+        }
+    }
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/buf.h:1"]
+    pub mod buf_h {
+        #[c2rust::src_loc = "3:0"]
+        pub struct Buf {
+            pub n: usize,
+        }
+    }
+
+    impl buf_h::Buf {
+        pub fn len(&self) -> usize {
+            self.n
+        }
+    }
+
+    pub fn size(b: &buf_h::Buf) -> usize {
+        b.len()
+    }
+}
+
+fn main() {}
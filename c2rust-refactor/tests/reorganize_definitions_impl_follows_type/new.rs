@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod shape {
+
+    // =============== BEGIN shape_h ================
+    pub struct Shape {
+        pub w: i32,
+        pub h: i32,
+    }
+
+    // ================ END shape_h ================
+    // =============== BEGIN util_h ================
+    impl crate::shape::Shape {
+        pub fn area(&self) -> i32 {
+            self.w * self.h
+        }
+    }
+
+    pub fn square(n: i32) -> crate::shape::Shape {
+        crate::shape::Shape { w: n, h: n }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod shape {
+    #[c2rust::header_src = "/home/user/some/workspace/shape.h:1"]
+    pub mod shape_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Shape {
+            pub w: i32,
+            pub h: i32,
+        }
+    }
+
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:2"]
+    pub mod util_h {
+        #[c2rust::src_loc = "3:1"]
+        impl super::shape_h::Shape {
+            pub fn area(&self) -> i32 {
+                self.w * self.h
+            }
+        }
+    }
+
+    pub fn square(n: i32) -> shape_h::Shape {
+        shape_h::Shape { w: n, h: n }
+    }
+}
+
+fn main() {}
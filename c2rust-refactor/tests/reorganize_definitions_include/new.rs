@@ -0,0 +1,38 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod a {
+    #[c2rust::header_src = "/home/user/some/workspace/a.h:1"]
+    pub mod a_h {
+        #[c2rust::src_loc = "2:0"]
+        pub const A: u32 = 1;
+    }
+
+    pub fn get_a() -> u32 {
+        a_h::A
+    }
+}
+
+pub mod b {
+
+    // =============== BEGIN b_h ================
+    pub const B: u32 = 2;
+
+    pub fn get_b() -> u32 {
+        crate::b::B
+    }
+}
+
+pub mod c {
+    #[c2rust::header_src = "/home/user/some/workspace/c.h:1"]
+    pub mod c_h {
+        #[c2rust::src_loc = "2:0"]
+        pub const C: u32 = 3;
+    }
+
+    pub fn get_c() -> u32 {
+        c_h::C
+    }
+}
+
+fn main() {}
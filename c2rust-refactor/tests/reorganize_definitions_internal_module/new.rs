@@ -0,0 +1,21 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod internal {
+    pub type C2RustUnnamed = u32;
+}
+pub mod shapes {
+
+    // =============== BEGIN shapes_h ================
+    pub const SHAPE_CIRCLE: crate::internal::C2RustUnnamed = 0;
+    pub struct shape {
+        pub kind: u32,
+    }
+
+    pub fn is_circle(s: &crate::shapes::shape) -> bool {
+        s.kind == crate::shapes::SHAPE_CIRCLE
+    }
+}
+
+fn main() {}
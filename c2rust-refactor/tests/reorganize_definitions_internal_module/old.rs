@@ -0,0 +1,24 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod shapes {
+    #[c2rust::header_src = "/home/user/some/workspace/shapes.h:1"]
+    pub mod shapes_h {
+        #[c2rust::src_loc = "2:1"]
+        pub type C2RustUnnamed = u32;
+        #[c2rust::src_loc = "3:5"]
+        pub const SHAPE_CIRCLE: C2RustUnnamed = 0;
+        #[c2rust::src_loc = "6:8"]
+        pub struct shape {
+            pub kind: u32,
+        }
+    }
+
+    pub fn is_circle(s: &shapes_h::shape) -> bool {
+        s.kind == shapes_h::SHAPE_CIRCLE
+    }
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+pub mod buffer {
+
+    // =============== BEGIN Buffer_h ================
+    pub struct Buffer {
+        pub len: usize,
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/Buffer.h:1"]
+    pub mod Buffer_h {}
+
+    pub fn buffer_len(b: &crate::buffer::Buffer) -> usize {
+        b.len
+    }
+}
+
+pub mod buf {
+    #[c2rust::header_src = "/home/user/some/workspace/Buffer.h:1"]
+    pub mod Buffer_h {}
+
+    pub fn buf_empty(b: &crate::buffer::Buffer) -> bool {
+        b.len == 0
+    }
+}
+
+fn main() {}
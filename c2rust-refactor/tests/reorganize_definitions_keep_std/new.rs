@@ -0,0 +1,18 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod a {
+
+    // =============== BEGIN a_h ================
+    pub const LIMIT: usize = 8;
+    pub mod stdio_h {
+        #[repr(C)]
+        pub struct _IO_FILE;
+    }
+
+    pub unsafe fn use_a(f: *mut stdio_h::_IO_FILE) -> usize {
+        crate::a::LIMIT
+    }
+}
+
+fn main() {}
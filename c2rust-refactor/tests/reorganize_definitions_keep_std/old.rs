@@ -0,0 +1,23 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod a {
+    #[c2rust::header_src = "/usr/include/stdio.h:1"]
+    pub mod stdio_h {
+        #[repr(C)]
+        #[c2rust::src_loc = "10:0"]
+        pub struct _IO_FILE;
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/a.h:2"]
+    pub mod a_h {
+        #[c2rust::src_loc = "3:0"]
+        pub const LIMIT: usize = 8;
+    }
+
+    pub unsafe fn use_a(f: *mut stdio_h::_IO_FILE) -> usize {
+        a_h::LIMIT
+    }
+}
+
+fn main() {}
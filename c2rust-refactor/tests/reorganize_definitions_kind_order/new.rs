@@ -0,0 +1,26 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+    use std::mem::size_of;
+
+    // =============== BEGIN buffer_h ================
+    pub struct Buf {
+        pub len: usize,
+    }
+    pub const CAP: usize = 4;
+
+    pub static mut COUNT: usize = 0;
+
+    pub fn len(b: &crate::buffer::Buf) -> usize {
+        b.len + size_of::<u8>()
+    }
+
+    impl crate::buffer::Buf {
+        pub fn cap(&self) -> usize {
+            crate::buffer::CAP
+        }
+    }
+}
+
+fn main() {}
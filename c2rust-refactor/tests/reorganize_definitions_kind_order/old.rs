@@ -0,0 +1,31 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:1"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Buf {
+            pub len: usize,
+        }
+        #[c2rust::src_loc = "5:0"]
+        pub const CAP: usize = 4;
+    }
+
+    pub fn len(b: &buffer_h::Buf) -> usize {
+        b.len + size_of::<u8>()
+    }
+
+    impl buffer_h::Buf {
+        pub fn cap(&self) -> usize {
+            buffer_h::CAP
+        }
+    }
+
+    pub static mut COUNT: usize = 0;
+
+    use std::mem::size_of;
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod io_h_0 {
+    extern "C" {
+        #[link_name = "open_file64"]
+        pub fn open_file(path: *const u8) -> i32;
+    }
+}
+
+pub mod io_h {
+    extern "C" {
+        pub fn open_file(path: *const u8) -> i32;
+    }
+}
+
+pub mod x {
+
+    pub unsafe fn open_x(path: *const u8) -> i32 {
+        crate::io_h::open_file(path)
+    }
+}
+
+pub mod y {
+
+    pub unsafe fn open_y(path: *const u8) -> i32 {
+        crate::io_h_0::open_file(path)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod x {
+    #[c2rust::header_src = "/home/user/some/workspace/io.h:1"]
+    pub mod io_h {
+        extern "C" {
+            #[c2rust::src_loc = "3:0"]
+            pub fn open_file(path: *const u8) -> i32;
+        }
+    }
+
+    pub unsafe fn open_x(path: *const u8) -> i32 {
+        io_h::open_file(path)
+    }
+}
+
+pub mod y {
+    #[c2rust::header_src = "/home/user/some/workspace/io.h:1"]
+    pub mod io_h {
+        extern "C" {
+            #[c2rust::src_loc = "3:0"]
+            #[link_name = "open_file64"]
+            pub fn open_file(path: *const u8) -> i32;
+        }
+    }
+
+    pub unsafe fn open_y(path: *const u8) -> i32 {
+        io_h::open_file(path)
+    }
+}
+
+fn main() {}
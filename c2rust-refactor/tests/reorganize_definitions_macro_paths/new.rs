@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+
+    // =============== BEGIN buffer_h ================
+    pub struct Foo {
+        pub len: usize,
+    }
+
+    macro_rules! empty_foo {
+        () => {
+            crate::buffer::Foo { len: 0 }
+        };
+    }
+
+    pub fn make() -> crate::buffer::Foo {
+        empty_foo!()
+    }
+}
+
+fn main() {}
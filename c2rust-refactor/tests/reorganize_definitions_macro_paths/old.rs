@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:1"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Foo {
+            pub len: usize,
+        }
+    }
+
+    macro_rules! empty_foo {
+        () => {
+            buffer_h::Foo { len: 0 }
+        };
+    }
+
+    pub fn make() -> buffer_h::Foo {
+        empty_foo!()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+#[macro_use]
+pub mod calc {
+
+    // =============== BEGIN calc_h ================
+    macro_rules! twice {
+        ($e:expr) => {
+            $e * 2
+        };
+    }
+
+    pub fn double(x: i32) -> i32 {
+        twice!(x)
+    }
+}
+
+fn main() {}
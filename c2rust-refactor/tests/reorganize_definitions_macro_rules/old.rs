@@ -0,0 +1,21 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod calc {
+    #[macro_use]
+    #[c2rust::header_src = "/home/user/some/workspace/calc.h:1"]
+    pub mod calc_h {
+        macro_rules! twice {
+            ($e:expr) => {
+                $e * 2
+            };
+        }
+    }
+
+    pub fn double(x: i32) -> i32 {
+        twice!(x)
+    }
+}
+
+fn main() {}
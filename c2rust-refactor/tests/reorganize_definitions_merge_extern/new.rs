@@ -0,0 +1,18 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod app {
+    extern "C" {
+        // =============== BEGIN app_h ================
+        pub fn app_init() -> i32;
+        fn abort() -> !;
+    }
+
+    pub unsafe fn start() {
+        if crate::app::app_init() != 0 {
+            abort();
+        }
+    }
+}
+
+fn main() {}
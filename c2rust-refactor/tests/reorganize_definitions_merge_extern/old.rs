@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/app.h:1"]
+    pub mod app_h {
+        extern "C" {
+            #[c2rust::src_loc = "2:1"]
+            pub fn app_init() -> i32;
+        }
+    }
+
+    extern "C" {
+        fn abort() -> !;
+    }
+
+    pub unsafe fn start() {
+        if app_h::app_init() != 0 {
+            abort();
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod app {
+    extern "C" {
+        // =============== BEGIN app_h ================
+        pub fn app_init(flags: i32) -> i32;
+    }
+
+    pub unsafe fn start() -> i32 {
+        crate::app::app_init(0)
+    }
+}
+
+pub mod tool {
+
+    pub unsafe fn run() -> i32 {
+        crate::app::app_init(1)
+    }
+}
+
+fn main() {}
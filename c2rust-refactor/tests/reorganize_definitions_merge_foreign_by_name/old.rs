@@ -0,0 +1,33 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/app.h:1"]
+    pub mod app_h {
+        extern "C" {
+            #[c2rust::src_loc = "2:1"]
+            pub fn app_init(flags: i32) -> i32;
+        }
+    }
+
+    pub unsafe fn start() -> i32 {
+        app_h::app_init(0)
+    }
+}
+
+pub mod tool {
+    #[c2rust::header_src = "/home/user/some/workspace/app.h:1"]
+    pub mod app_h {
+        extern "C" {
+            #[c2rust::src_loc = "2:1"]
+            pub fn app_init(flags: u32) -> i32;
+        }
+    }
+
+    pub unsafe fn run() -> i32 {
+        app_h::app_init(1)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+$refactor \
+    reorganize_definitions merge_foreign_by_name=1 \
+    -- old.rs $rustflags 2> log
+grep -q "warning: extern declaration of app_init merged" log
+rm log
@@ -0,0 +1,24 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_snake_case)]
+
+pub mod buffer {
+
+    pub fn buffer_len(b: &crate::buf::Buffer) -> usize {
+        b.len
+    }
+}
+
+pub mod buf {
+
+    // =============== BEGIN Buffer_h ================
+    pub struct Buffer {
+        pub len: usize,
+    }
+
+    pub fn buf_empty(b: &crate::buf::Buffer) -> bool {
+        b.len == 0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+$refactor \
+    reorganize_definitions map_in=map.json map_out=map_out.json \
+    -- old.rs $rustflags
+
+# The mapping we applied is written back out
+grep -q '"crate::buf"' map_out.json
+rm map_out.json
@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod log_h {
+    pub const LOG_LEVEL: i32 = 1;
+}
+
+pub mod net {
+    pub struct tcp_conn {
+        pub fd: i32,
+    }
+    pub struct udp_conn {
+        pub fd: i32,
+    }
+}
+
+pub mod server {
+
+    pub fn fds(tcp: &crate::net::tcp_conn, udp: &crate::net::udp_conn) -> i32 {
+        tcp.fd + udp.fd + crate::log_h::LOG_LEVEL
+    }
+}
+
+fn main() {}
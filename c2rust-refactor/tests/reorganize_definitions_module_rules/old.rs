@@ -0,0 +1,33 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod server {
+    #[c2rust::header_src = "/home/user/some/workspace/net_tcp.h:1"]
+    pub mod net_tcp_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct tcp_conn {
+            pub fd: i32,
+        }
+    }
+
+    #[c2rust::header_src = "/home/user/some/workspace/net_udp.h:2"]
+    pub mod net_udp_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct udp_conn {
+            pub fd: i32,
+        }
+    }
+
+    #[c2rust::header_src = "/home/user/some/workspace/log.h:3"]
+    pub mod log_h {
+        #[c2rust::src_loc = "2:0"]
+        pub const LOG_LEVEL: i32 = 1;
+    }
+
+    pub fn fds(tcp: &net_tcp_h::tcp_conn, udp: &net_udp_h::udp_conn) -> i32 {
+        tcp.fd + udp.fd + log_h::LOG_LEVEL
+    }
+}
+
+fn main() {}
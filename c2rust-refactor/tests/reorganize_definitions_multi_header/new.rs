@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod common {
+
+    // =============== BEGIN common_h ================
+    pub const BASE: u32 = 1;
+
+    // ================ END common_h ================
+    // =============== BEGIN util_h ================
+    pub const LIMIT: u32 = 8;
+
+    pub fn base() -> u32 {
+        crate::common::BASE
+    }
+}
+
+pub mod app {
+
+    pub fn limit() -> u32 {
+        crate::common::LIMIT
+    }
+}
+
+fn main() {}
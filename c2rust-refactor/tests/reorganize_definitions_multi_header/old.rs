@@ -0,0 +1,30 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod common {
+    #[c2rust::header_src = "/home/user/some/workspace/common.h:1"]
+    pub mod common_h {
+        #[c2rust::src_loc = "3:0"]
+        pub const BASE: u32 = 1;
+    }
+
+    pub fn base() -> u32 {
+        common_h::BASE
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:2"]
+    #[c2rust::header_src = "/home/user/some/workspace/common.h:3"]
+    pub mod util_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const LIMIT: u32 = 8;
+    }
+
+    pub fn limit() -> u32 {
+        util_h::LIMIT
+    }
+}
+
+fn main() {}
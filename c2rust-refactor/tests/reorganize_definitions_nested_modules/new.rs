@@ -0,0 +1,21 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod net {
+    pub mod socket {
+
+        // =============== BEGIN socket_h ================
+        pub struct Socket {
+            pub fd: i32,
+        }
+    }
+
+    pub mod conn {
+
+        pub fn is_open(s: &crate::net::socket::Socket) -> bool {
+            s.fd >= 0
+        }
+    }
+}
+
+fn main() {}
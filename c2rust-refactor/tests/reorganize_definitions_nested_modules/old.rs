@@ -0,0 +1,31 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod net {
+    pub mod socket {
+        #[c2rust::header_src = "/home/user/some/workspace/net/socket.h:1"]
+        pub mod socket_h {
+            #[c2rust::src_loc = "3:8"]
+            pub struct Socket {
+                pub fd: i32,
+            }
+        }
+    }
+
+    pub mod conn {
+        #[c2rust::header_src = "/home/user/some/workspace/net/socket.h:1"]
+        pub mod socket_h {
+            #[c2rust::src_loc = "3:8"]
+            pub struct Socket {
+                pub fd: i32,
+            }
+        }
+
+        pub fn is_open(s: &socket_h::Socket) -> bool {
+            s.fd >= 0
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+#![feature(libc)]
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+extern crate libc;
+
+pub mod helpers {
+    pub mod math {
+        pub fn add(a: libc::c_int, b: libc::c_int) -> libc::c_int {
+            a + b
+        }
+        pub fn sub(a: libc::c_int, b: libc::c_int) -> libc::c_int {
+            a - b
+        }
+    }
+    pub const LIMIT: libc::c_int = 10;
+}
+
+pub mod list {
+
+    // =============== BEGIN list_h ================
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct node {
+        pub value: libc::c_int,
+        pub next: *mut crate::list::node,
+    }
+    use crate::helpers::{math::{add, sub}, LIMIT};
+
+    pub unsafe fn clamp(mut head: *mut crate::list::node) {
+        while !head.is_null() {
+            (*head).value = sub(add((*head).value, LIMIT), LIMIT);
+            head = (*head).next
+        }
+    }
+}
+
+pub mod sum {
+    use crate::{helpers::{math::add, LIMIT}};
+
+    pub unsafe fn list_sum(mut head: *mut crate::list::node) -> libc::c_int {
+        let mut total: libc::c_int = LIMIT;
+        while !head.is_null() {
+            total = add(total, (*head).value);
+            head = (*head).next
+        }
+        return total;
+    }
+}
+
+fn main() {}
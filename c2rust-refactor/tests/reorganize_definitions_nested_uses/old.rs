@@ -0,0 +1,66 @@
+#![feature(libc)]
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+extern crate libc;
+
+pub mod helpers {
+    pub mod math {
+        pub fn add(a: libc::c_int, b: libc::c_int) -> libc::c_int {
+            a + b
+        }
+        pub fn sub(a: libc::c_int, b: libc::c_int) -> libc::c_int {
+            a - b
+        }
+    }
+    pub const LIMIT: libc::c_int = 10;
+}
+
+pub mod list {
+    #[c2rust::header_src = "/home/user/project/list.h:2"]
+    pub mod list_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        #[c2rust::src_loc = "4:8"]
+        pub struct node {
+            pub value: libc::c_int,
+            pub next: *mut node,
+        }
+    }
+    use crate::helpers::{math::{add, sub}, LIMIT};
+
+    pub unsafe fn clamp(mut head: *mut list_h::node) {
+        while !head.is_null() {
+            (*head).value = sub(add((*head).value, LIMIT), LIMIT);
+            head = (*head).next
+        }
+    }
+}
+
+pub mod sum {
+    #[c2rust::header_src = "/home/user/project/list.h:2"]
+    pub mod list_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        #[c2rust::src_loc = "4:8"]
+        pub struct node {
+            pub value: libc::c_int,
+            pub next: *mut node,
+        }
+    }
+    use crate::{helpers::{math::add, LIMIT}, sum::list_h::node};
+
+    pub unsafe fn list_sum(mut head: *mut node) -> libc::c_int {
+        let mut total: libc::c_int = LIMIT;
+        while !head.is_null() {
+            total = add(total, (*head).value);
+            head = (*head).next
+        }
+        return total;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod foo {
+
+    // =============== BEGIN foo_h ================
+    #[repr(C)]
+    pub struct Foo {
+        pub x: i32,
+    }
+    impl crate::foo::Foo {
+        pub const ZERO: crate::foo::Foo = crate::foo::Foo { x: 0 };
+    }
+    pub fn make_foo() -> crate::foo::Foo {
+        crate::foo::Foo { x: 1 }
+    }
+
+    pub fn get() -> i32 {
+        crate::foo::make_foo().x
+    }
+}
+
+fn main() {}
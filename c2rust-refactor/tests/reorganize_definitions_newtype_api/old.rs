@@ -0,0 +1,28 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod foo {
+    #[c2rust::header_src = "/home/user/some/workspace/foo.h:1"]
+    pub mod foo_h {
+        #[repr(C)]
+        #[c2rust::src_loc = "2:0"]
+        pub struct Foo {
+            pub x: i32,
+        }
+        #[c2rust::src_loc = "3:0"]
+        impl Foo {
+            pub const ZERO: Foo = Foo { x: 0 };
+        }
+        #[c2rust::src_loc = "4:0"]
+        pub fn make_foo() -> Foo {
+            Foo { x: 1 }
+        }
+    }
+
+    pub fn get() -> i32 {
+        foo_h::make_foo().x
+    }
+}
+
+fn main() {}
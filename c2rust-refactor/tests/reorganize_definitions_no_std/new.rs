@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![no_std]
+#![crate_type = "lib"]
+#![allow(dead_code)]
+
+pub mod system {
+    pub type uint32_t = u32;
+}
+
+pub mod a {
+
+    pub fn use_a(x: crate::system::uint32_t) -> crate::system::uint32_t {
+        x
+    }
+}
+
+pub mod b {
+
+    pub fn use_b(x: crate::system::uint32_t) -> crate::system::uint32_t {
+        x + 1
+    }
+}
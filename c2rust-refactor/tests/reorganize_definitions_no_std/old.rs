@@ -0,0 +1,29 @@
+#![register_tool(c2rust)]
+
+#![no_std]
+#![crate_type = "lib"]
+#![allow(dead_code)]
+
+pub mod a {
+    #[c2rust::header_src = "/usr/include/stdint.h:1"]
+    pub mod stdint_h {
+        #[c2rust::src_loc = "10:0"]
+        pub type uint32_t = u32;
+    }
+
+    pub fn use_a(x: stdint_h::uint32_t) -> stdint_h::uint32_t {
+        x
+    }
+}
+
+pub mod b {
+    #[c2rust::header_src = "/usr/include/stdint.h:1"]
+    pub mod stdint_h {
+        #[c2rust::src_loc = "10:0"]
+        pub type uint32_t = u32;
+    }
+
+    pub fn use_b(x: stdint_h::uint32_t) -> stdint_h::uint32_t {
+        x + 1
+    }
+}
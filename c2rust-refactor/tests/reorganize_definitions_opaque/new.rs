@@ -0,0 +1,19 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod stdlib {
+    #[repr(C)]
+    pub struct _IO_FILE;
+}
+
+pub mod a {
+
+    pub unsafe fn use_a(f: *mut crate::stdlib::_IO_FILE) {}
+}
+
+pub mod b {
+
+    pub unsafe fn use_b(f: *mut crate::stdlib::_IO_FILE) {}
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod a {
+    #[c2rust::header_src = "/usr/include/stdio.h:1"]
+    pub mod stdio_h {
+        #[repr(C)]
+        #[c2rust::src_loc = "10:0"]
+        pub struct _IO_FILE;
+    }
+
+    pub unsafe fn use_a(f: *mut stdio_h::_IO_FILE) {}
+}
+
+pub mod b {
+    #[c2rust::header_src = "/usr/include/stdio.h:1"]
+    pub mod stdio_h {
+        #[repr(C)]
+        #[c2rust::src_loc = "10:0"]
+        pub struct _IO_FILE {}
+    }
+
+    pub unsafe fn use_b(f: *mut stdio_h::_IO_FILE) {}
+}
+
+fn main() {}
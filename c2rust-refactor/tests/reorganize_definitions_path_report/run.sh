@@ -0,0 +1,18 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+$refactor \
+    reorganize_definitions path_report=paths.json \
+    -- old.rs $rustflags
+
+# Both copies of SIZE are reported, pointing at the one that was kept
+grep -q '"crate::buffer::buffer_h::SIZE"' paths.json
+grep -q '"crate::user::buffer_h::SIZE"' paths.json
+grep -q '"new_path": "crate::buffer::SIZE"' paths.json
+grep -q '"module": "crate::buffer"' paths.json
+rm paths.json
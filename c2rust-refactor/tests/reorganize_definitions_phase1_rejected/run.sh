@@ -0,0 +1,18 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# The pass needs name resolution, so the command must refuse to run at
+# phase 1 and leave the input untouched.
+if $refactor \
+    reorganize_definitions phase=1 \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly accepted phase=1" >&2
+    exit 1
+fi
+grep -q "Phase1 is too early; the pass requires phase 2 or later" log
+cp old.rs old.new
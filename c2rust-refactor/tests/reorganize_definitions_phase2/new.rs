@@ -0,0 +1,23 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+
+    // =============== BEGIN buffer_h ================
+    pub struct Buffer {
+        pub len: usize,
+    }
+
+    pub fn buffer_len(b: &crate::buffer::Buffer) -> u32 {
+        b.len
+    }
+}
+
+pub mod io {
+
+    pub fn is_empty(b: &crate::buffer::Buffer) -> bool {
+        b.len == 0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:1"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Buffer {
+            pub len: usize,
+        }
+    }
+
+    pub fn buffer_len(b: &buffer_h::Buffer) -> u32 {
+        b.len
+    }
+}
+
+pub mod io {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:1"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Buffer {
+            pub len: usize,
+        }
+    }
+
+    pub fn is_empty(b: &buffer_h::Buffer) -> bool {
+        b.len == 0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+
+pub mod foo {
+    #[header_src = "/home/user/some/workspace/foo.h"]
+    pub mod foo_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        pub struct Foo {
+            pub a: i32,
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# These options need phase 3 results, so the command must refuse to run them
+# at phase 2 and leave the input untouched.
+if $refactor \
+    reorganize_definitions phase=2 rewrite_doclinks=1 emit_prelude=1 report_type_cycles=1 \
+    -- old.rs $rustflags 2> log; then
+    echo "reorganize_definitions unexpectedly accepted phase=2" >&2
+    exit 1
+fi
+grep -q "rewrite_doclinks, emit_prelude, report_type_cycles requires phase 3" log
+cp old.rs old.new
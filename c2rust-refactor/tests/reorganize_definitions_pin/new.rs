@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod sys {
+    pub const SYS_VERSION: u32 = 1;
+}
+
+pub mod timer {
+
+    // =============== BEGIN types_h ================
+    pub type __uint32_t = u32;
+
+    pub fn elapsed(
+        start: crate::timer::__uint32_t,
+        end: crate::timer::__uint32_t,
+    ) -> crate::timer::__uint32_t {
+        end - start
+    }
+}
+
+fn main() {}
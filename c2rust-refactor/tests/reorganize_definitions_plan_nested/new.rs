@@ -0,0 +1,23 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod sys {
+    pub mod types {
+        pub type __uint32_t = u32;
+    }
+}
+pub mod shapes {
+
+    // =============== BEGIN shapes_h ================
+    pub const SHAPE_CIRCLE: u32 = 0;
+    pub struct shape {
+        pub kind: crate::sys::types::__uint32_t,
+    }
+
+    pub fn is_circle(s: &crate::shapes::shape) -> bool {
+        s.kind == crate::shapes::SHAPE_CIRCLE
+    }
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# Route a declaration into a nested module that doesn't exist yet
+cat > plan.json <<PLAN
+{"crate::shapes::types_h::__uint32_t": "crate::sys::types"}
+PLAN
+
+$refactor \
+    reorganize_definitions plan_in=plan.json \
+    -- old.rs $rustflags
+rm plan.json
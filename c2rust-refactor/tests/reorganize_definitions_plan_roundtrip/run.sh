@@ -0,0 +1,22 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+# Write the plan along with a direct run...
+$refactor \
+    reorganize_definitions plan_out=plan.json \
+    -- old.rs $rustflags
+grep -q '"crate::shapes::shapes_h::shape": "crate::shapes"' plan.json
+grep -q '"crate::shapes::types_h::__uint32_t": "crate::stdlib"' plan.json
+mv old.new direct.new
+
+# ... then apply it, which has to produce the same crate
+$refactor \
+    reorganize_definitions plan_in=plan.json \
+    -- old.rs $rustflags
+diff -wB direct.new old.new
+rm direct.new plan.json
@@ -0,0 +1,27 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod a {
+    pub mod buf {
+
+        pub fn a_len(b: &crate::b::buf::Buf) -> usize {
+            b.len
+        }
+    }
+}
+
+pub mod b {
+    pub mod buf {
+
+        // =============== BEGIN buf_h ================
+        pub struct Buf {
+            pub len: usize,
+        }
+
+        pub fn b_len(b: &crate::b::buf::Buf) -> usize {
+            b.len
+        }
+    }
+}
+
+fn main() {}
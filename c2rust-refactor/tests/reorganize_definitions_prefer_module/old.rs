@@ -0,0 +1,39 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod a {
+    pub mod buf {
+        #[c2rust::header_src = "/home/user/some/workspace/buf.h:1"]
+        pub mod buf_h {
+            #[c2rust::prefer_module = "b::buf"]
+            #[c2rust::src_loc = "2:0"]
+            pub struct Buf {
+                pub len: usize,
+            }
+        }
+
+        pub fn a_len(b: &buf_h::Buf) -> usize {
+            b.len
+        }
+    }
+}
+
+pub mod b {
+    pub mod buf {
+        #[c2rust::header_src = "/home/user/some/workspace/buf.h:1"]
+        pub mod buf_h {
+            #[c2rust::prefer_module = "b::buf"]
+            #[c2rust::src_loc = "2:0"]
+            pub struct Buf {
+                pub len: usize,
+            }
+        }
+
+        pub fn b_len(b: &buf_h::Buf) -> usize {
+            b.len
+        }
+    }
+}
+
+fn main() {}
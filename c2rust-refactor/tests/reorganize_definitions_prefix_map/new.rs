@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+pub mod graphics {
+    pub const gfx_draw: u32 = 1;
+    pub const gfx_clear: u32 = 2;
+}
+
+pub mod app {
+
+    // =============== BEGIN app_h ================
+    pub const VERSION: u32 = 3;
+
+    pub fn run() -> u32 {
+        crate::graphics::gfx_draw + crate::graphics::gfx_clear + crate::app::VERSION
+    }
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/app.h:3"]
+    pub mod app_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const gfx_draw: u32 = 1;
+        #[c2rust::src_loc = "5:0"]
+        pub const gfx_clear: u32 = 2;
+        #[c2rust::src_loc = "6:0"]
+        pub const VERSION: u32 = 3;
+    }
+
+    pub fn run() -> u32 {
+        app_h::gfx_draw + app_h::gfx_clear + app_h::VERSION
+    }
+}
+
+fn main() {}
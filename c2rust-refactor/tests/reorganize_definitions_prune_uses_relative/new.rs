@@ -0,0 +1,57 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod util {
+    pub struct Point {
+        pub x: i32,
+    }
+
+    pub struct Size {
+        pub w: i32,
+    }
+
+    pub struct Label {
+        pub text: u8,
+    }
+
+    pub struct Color {
+        pub rgb: u32,
+    }
+}
+
+pub mod app {
+
+    // =============== BEGIN app_h ================
+    pub type app_id = u32;
+
+    // Used through the module's own path
+    use crate::util::Label;
+    // Used by the child module through the module's own path
+    use crate::util::Color;
+
+    // The pass rewrites `self::Point` into `crate::util::Point`, which no
+    // longer needs the import
+    pub fn origin(_id: crate::app::app_id) -> crate::util::Point {
+        crate::util::Point { x: 0 }
+    }
+
+    pub fn empty() -> crate::app::Label {
+        crate::app::Label { text: 0 }
+    }
+
+    pub mod inner {
+        // Rewritten into `use crate::util::Size;`
+        use crate::util::Size;
+
+        pub fn unit() -> Size {
+            Size { w: 1 }
+        }
+
+        pub fn black() -> crate::app::Color {
+            crate::app::Color { rgb: 0 }
+        }
+    }
+}
+
+fn main() {}
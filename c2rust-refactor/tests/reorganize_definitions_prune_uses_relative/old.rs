@@ -0,0 +1,62 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod util {
+    pub struct Point {
+        pub x: i32,
+    }
+
+    pub struct Size {
+        pub w: i32,
+    }
+
+    pub struct Label {
+        pub text: u8,
+    }
+
+    pub struct Color {
+        pub rgb: u32,
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/app.h:1"]
+    pub mod app_h {
+        #[c2rust::src_loc = "3:1"]
+        pub type app_id = u32;
+    }
+
+    use crate::util::Point;
+    use crate::util::Size;
+    // Used through the module's own path
+    use crate::util::Label;
+    // Used by the child module through the module's own path
+    use crate::util::Color;
+
+    // The pass rewrites `self::Point` into `crate::util::Point`, which no
+    // longer needs the import
+    pub fn origin(_id: app_h::app_id) -> self::Point {
+        self::Point { x: 0 }
+    }
+
+    pub fn empty() -> crate::app::Label {
+        crate::app::Label { text: 0 }
+    }
+
+    pub mod inner {
+        // Rewritten into `use crate::util::Size;`
+        use super::Size;
+
+        pub fn unit() -> Size {
+            Size { w: 1 }
+        }
+
+        pub fn black() -> crate::app::Color {
+            crate::app::Color { rgb: 0 }
+        }
+    }
+}
+
+fn main() {}
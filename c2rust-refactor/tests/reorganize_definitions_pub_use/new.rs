@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod geom {
+
+    // =============== BEGIN geom_h ================
+    pub struct Point(pub i32, pub i32);
+
+    pub fn origin() -> crate::geom::Point {
+        crate::geom::Point(0, 0)
+    }
+}
+
+pub mod app {
+    pub use crate::geom::Point;
+
+    pub fn unit() -> crate::geom::Point {
+        crate::geom::Point(1, 1)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod geom {
+    #[c2rust::header_src = "/home/user/some/workspace/geom.h:1"]
+    pub mod geom_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Point(pub i32, pub i32);
+    }
+
+    pub fn origin() -> geom_h::Point {
+        geom_h::Point(0, 0)
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/geom.h:2"]
+    pub mod geom_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Point(pub i32, pub i32);
+    }
+    pub use self::geom_h::Point;
+
+    pub fn unit() -> Point {
+        Point(1, 1)
+    }
+}
+
+fn main() {}
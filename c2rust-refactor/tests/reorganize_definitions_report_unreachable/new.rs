@@ -0,0 +1,21 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+mod app {
+
+    // =============== BEGIN app_h ================
+    pub struct Used {
+        pub x: i32,
+    }
+    pub struct Orphan {
+        pub x: i32,
+    }
+
+    pub fn make() -> crate::app::Used {
+        crate::app::Used { x: 1 }
+    }
+}
+
+fn main() {
+    app::make();
+}
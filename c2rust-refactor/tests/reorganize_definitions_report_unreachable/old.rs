@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/app.h:1"]
+    pub mod app_h {
+        #[c2rust::src_loc = "2:0"]
+        pub struct Used {
+            pub x: i32,
+        }
+        #[c2rust::src_loc = "5:0"]
+        pub struct Orphan {
+            pub x: i32,
+        }
+    }
+
+    pub fn make() -> app_h::Used {
+        app_h::Used { x: 1 }
+    }
+}
+
+fn main() {
+    app::make();
+}
@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub(crate) mod stdlib {
+    pub(crate) type __u8 = u8;
+}
+pub mod parser {
+
+    // =============== BEGIN parser_h ================
+    struct Token {
+        pub kind: crate::stdlib::__u8,
+    }
+    pub(crate) const MAX_DEPTH: u32 = 32;
+
+    fn is_deep(depth: u32) -> bool {
+        depth > crate::parser::MAX_DEPTH
+    }
+
+    fn kind_of(t: &crate::parser::Token) -> crate::stdlib::__u8 {
+        t.kind
+    }
+}
+
+fn main() {}
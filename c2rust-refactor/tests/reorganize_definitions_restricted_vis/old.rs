@@ -0,0 +1,32 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod parser {
+    #[c2rust::header_src = "/usr/include/x86_64-linux-gnu/bits/types.h:1"]
+    pub mod types_h {
+        #[c2rust::src_loc = "21:1"]
+        pub(in crate::parser) type __u8 = u8;
+    }
+
+    #[c2rust::header_src = "/home/user/some/workspace/parser.h:2"]
+    pub mod parser_h {
+        #[c2rust::src_loc = "3:8"]
+        pub(super) struct Token {
+            pub kind: crate::parser::types_h::__u8,
+        }
+        #[c2rust::src_loc = "6:1"]
+        pub(crate) const MAX_DEPTH: u32 = 32;
+    }
+
+    fn is_deep(depth: u32) -> bool {
+        depth > parser_h::MAX_DEPTH
+    }
+
+    fn kind_of(t: &parser_h::Token) -> types_h::__u8 {
+        t.kind
+    }
+}
+
+fn main() {}
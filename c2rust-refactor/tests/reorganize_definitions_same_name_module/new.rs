@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod util_h {
+    pub fn helper() -> u32 {
+        1
+    }
+
+    // =============== BEGIN util_h ================
+    pub const LIMIT: u32 = 8;
+}
+
+pub mod app {
+
+    pub fn run() -> u32 {
+        crate::util_h::LIMIT + crate::util_h::helper()
+    }
+}
+
+fn main() {}
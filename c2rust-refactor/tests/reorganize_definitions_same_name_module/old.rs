@@ -0,0 +1,23 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod util_h {
+    pub fn helper() -> u32 {
+        1
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/util.h:2"]
+    pub mod util_h {
+        #[c2rust::src_loc = "4:0"]
+        pub const LIMIT: u32 = 8;
+    }
+
+    pub fn run() -> u32 {
+        util_h::LIMIT + crate::util_h::helper()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod app {
+    pub use crate::app::Mode::Fast;
+
+    // =============== BEGIN app_h ================
+    #[repr(u32)]
+    pub enum Mode {
+        Fast = 0,
+        Slow = 1,
+    }
+
+    pub fn default_mode() -> u32 {
+        0
+    }
+}
+
+fn main() {}
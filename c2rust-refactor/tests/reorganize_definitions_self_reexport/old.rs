@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/app.h:1"]
+    pub mod app_h {
+        #[repr(u32)]
+        #[c2rust::src_loc = "2:0"]
+        pub enum Mode {
+            Fast = 0,
+            Slow = 1,
+        }
+        pub use self::Mode::Fast;
+    }
+
+    pub fn default_mode() -> u32 {
+        0
+    }
+}
+
+fn main() {}
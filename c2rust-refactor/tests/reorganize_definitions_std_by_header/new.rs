@@ -0,0 +1,27 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+pub mod stdlib {
+    pub mod string {
+
+        // =============== BEGIN string_h ================
+        pub type wchar_t = i32;
+    }
+    pub mod stdio {
+
+        // =============== BEGIN stdio_h ================
+        pub type fpos_t = i64;
+    }
+}
+
+pub mod log {
+
+    pub fn rewind(
+        pos: crate::stdlib::stdio::fpos_t,
+        ch: crate::stdlib::string::wchar_t,
+    ) -> crate::stdlib::stdio::fpos_t {
+        pos - ch as crate::stdlib::stdio::fpos_t
+    }
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod log {
+    #[c2rust::header_src = "/usr/include/stdio.h:1"]
+    pub mod stdio_h {
+        #[c2rust::src_loc = "84:1"]
+        pub type fpos_t = i64;
+    }
+    #[c2rust::header_src = "/usr/include/string.h:2"]
+    pub mod string_h {
+        #[c2rust::src_loc = "42:1"]
+        pub type wchar_t = i32;
+    }
+
+    pub fn rewind(pos: stdio_h::fpos_t, ch: string_h::wchar_t) -> stdio_h::fpos_t {
+        pos - ch as stdio_h::fpos_t
+    }
+}
+
+fn main() {}
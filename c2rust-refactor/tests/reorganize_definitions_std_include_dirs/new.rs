@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod stdlib {
+    pub type __uint32_t = u32;
+}
+pub mod timer {
+
+    // =============== BEGIN timer_h ================
+    pub struct timer {
+        pub elapsed: crate::stdlib::__uint32_t,
+    }
+
+    pub fn elapsed(t: &crate::timer::timer) -> crate::stdlib::__uint32_t {
+        t.elapsed
+    }
+}
+
+fn main() {}
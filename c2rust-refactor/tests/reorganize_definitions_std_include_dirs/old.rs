@@ -0,0 +1,27 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod timer {
+    #[c2rust::header_src = "/opt/sysroot/include/bits/types.h:1"]
+    pub mod types_h {
+        #[c2rust::src_loc = "42:1"]
+        pub type __uint32_t = u32;
+    }
+
+    // Not a system header: only a prefix of the directory name matches
+    #[c2rust::header_src = "/opt/sysroot/include-extra/timer.h:2"]
+    pub mod timer_h {
+        #[c2rust::src_loc = "5:8"]
+        pub struct timer {
+            pub elapsed: crate::timer::types_h::__uint32_t,
+        }
+    }
+
+    pub fn elapsed(t: &timer_h::timer) -> types_h::__uint32_t {
+        t.elapsed
+    }
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod sys {
+
+    // =============== BEGIN types_h ================
+    pub type __uint32_t = u32;
+    pub const SYS_VERSION: u32 = 1;
+}
+
+pub mod timer {
+
+    pub fn elapsed(
+        start: crate::sys::__uint32_t,
+        end: crate::sys::__uint32_t,
+    ) -> crate::sys::__uint32_t {
+        end - start
+    }
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+#![allow(non_camel_case_types)]
+
+pub mod sys {
+    pub const SYS_VERSION: u32 = 1;
+}
+
+pub mod timer {
+    #[c2rust::header_src = "/usr/include/x86_64-linux-gnu/bits/types.h:1"]
+    pub mod types_h {
+        #[c2rust::src_loc = "42:1"]
+        pub type __uint32_t = u32;
+    }
+
+    pub fn elapsed(start: types_h::__uint32_t, end: types_h::__uint32_t) -> types_h::__uint32_t {
+        end - start
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+#!/bin/sh
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+$refactor \
+    reorganize_definitions \
+    -- old.rs $rustflags 2> log
+grep -q "moved 1 declarations, merged 1 duplicates, .* dropped 0 declarations" log
@@ -0,0 +1,18 @@
+#![feature(trait_alias)]
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod cb {
+
+    // =============== BEGIN cb_h ================
+    pub trait Callback = Fn() + Send;
+
+    pub fn noop() {}
+}
+
+pub mod user {
+
+    pub fn unrelated() {}
+}
+
+fn main() {}
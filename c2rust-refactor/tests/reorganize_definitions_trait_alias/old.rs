@@ -0,0 +1,26 @@
+#![feature(trait_alias)]
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod cb {
+    #[c2rust::header_src = "/home/user/some/workspace/cb.h:1"]
+    pub mod cb_h {
+        #[c2rust::src_loc = "3:0"]
+        pub trait Callback = Fn() + Send;
+    }
+
+    pub fn noop() {}
+}
+
+pub mod user {
+    #[c2rust::header_src = "/home/user/some/workspace/cb.h:1"]
+    pub mod cb_h {
+        #[c2rust::src_loc = "3:0"]
+        pub trait Callback = Send + Fn();
+    }
+
+    pub fn unrelated() {}
+}
+
+fn main() {}
@@ -0,0 +1,46 @@
+#![feature(libc)]
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(mutable_transmutes)]
+#![allow(unused_mut)]
+extern crate libc;
+
+pub mod codec {
+    extern "C" {
+        // =============== BEGIN codec_h ================
+        pub fn codec_init(opts: *const crate::codec::codec_opts) -> libc::c_int;
+        pub fn codec_finish() -> libc::c_int;
+    }
+
+    // =============== BEGIN codec_h ================
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    pub struct codec_opts {
+        pub level: libc::c_int,
+        pub flags: libc::c_uint,
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn codec_default_level() -> libc::c_int {
+        return 6 as libc::c_int;
+    }
+}
+
+pub mod app {
+
+    #[no_mangle]
+    pub unsafe extern "C" fn app_run() -> libc::c_int {
+        let mut opts: crate::codec::codec_opts = crate::codec::codec_opts {
+            level: 6 as libc::c_int,
+            flags: 0 as libc::c_int as libc::c_uint,
+        };
+        crate::codec::codec_init(&mut opts);
+        return crate::codec::codec_finish();
+    }
+}
+
+fn main() {}
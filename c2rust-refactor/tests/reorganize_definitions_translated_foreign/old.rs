@@ -0,0 +1,67 @@
+#![feature(libc)]
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(mutable_transmutes)]
+#![allow(unused_mut)]
+
+extern crate libc;
+
+pub mod codec {
+    #[c2rust::header_src = "/home/user/project/codec.h:3"]
+    pub mod codec_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        #[c2rust::src_loc = "5:16"]
+        pub struct codec_opts {
+            pub level: libc::c_int,
+            pub flags: libc::c_uint,
+        }
+        extern "C" {
+            #[c2rust::src_loc = "10:1"]
+            pub fn codec_init(opts: *const codec_opts) -> libc::c_int;
+            #[c2rust::src_loc = "11:1"]
+            pub fn codec_finish() -> libc::c_int;
+        }
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn codec_default_level() -> libc::c_int {
+        return 6 as libc::c_int;
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/project/codec.h:3"]
+    pub mod codec_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        #[c2rust::src_loc = "5:16"]
+        pub struct codec_opts {
+            pub level: libc::c_int,
+            pub flags: libc::c_uint,
+        }
+        extern "C" {
+            #[c2rust::src_loc = "10:1"]
+            pub fn codec_init(opts: *const codec_opts) -> libc::c_int;
+            #[c2rust::src_loc = "11:1"]
+            pub fn codec_finish() -> libc::c_int;
+        }
+    }
+
+    #[no_mangle]
+    pub unsafe extern "C" fn app_run() -> libc::c_int {
+        let mut opts: codec_h::codec_opts = codec_h::codec_opts {
+            level: 6 as libc::c_int,
+            flags: 0 as libc::c_int as libc::c_uint,
+        };
+        codec_h::codec_init(&mut opts);
+        return codec_h::codec_finish();
+    }
+}
+
+fn main() {}
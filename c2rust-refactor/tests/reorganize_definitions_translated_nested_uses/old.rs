@@ -0,0 +1,66 @@
+#![feature(libc)]
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(mutable_transmutes)]
+#![allow(unused_mut)]
+
+extern crate libc;
+
+pub mod list {
+    #[c2rust::header_src = "/home/user/project/list.h:2"]
+    pub mod list_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        #[c2rust::src_loc = "4:8"]
+        pub struct node {
+            pub value: libc::c_int,
+            pub next: *mut node,
+        }
+        #[c2rust::src_loc = "9:1"]
+        pub type node_t = node;
+    }
+    use self::list_h::{node, node_t};
+
+    #[no_mangle]
+    pub unsafe extern "C" fn list_len(mut head: *mut node_t) -> libc::c_int {
+        let mut n: libc::c_int = 0 as libc::c_int;
+        while !head.is_null() {
+            n += 1;
+            head = (*head).next
+        }
+        return n;
+    }
+}
+
+pub mod sum {
+    #[c2rust::header_src = "/home/user/project/list.h:2"]
+    pub mod list_h {
+        #[derive(Copy, Clone)]
+        #[repr(C)]
+        #[c2rust::src_loc = "4:8"]
+        pub struct node {
+            pub value: libc::c_int,
+            pub next: *mut node,
+        }
+        #[c2rust::src_loc = "9:1"]
+        pub type node_t = node;
+    }
+    use self::list_h::{node, node_t};
+
+    #[no_mangle]
+    pub unsafe extern "C" fn list_sum(mut head: *mut node_t) -> libc::c_int {
+        let mut total: libc::c_int = 0 as libc::c_int;
+        while !head.is_null() {
+            total += (*head).value;
+            head = (*head).next
+        }
+        return total;
+    }
+}
+
+fn main() {}
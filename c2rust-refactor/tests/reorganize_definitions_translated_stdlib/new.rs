@@ -0,0 +1,38 @@
+#![feature(libc)]
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(mutable_transmutes)]
+#![allow(unused_mut)]
+
+pub mod stdlib {
+    extern "C" {
+        pub fn malloc(_: libc::c_ulong) -> *mut libc::c_void;
+        pub fn free(__ptr: *mut libc::c_void);
+    }
+    pub type __uint32_t = libc::c_uint;
+}
+extern crate libc;
+
+pub mod a {
+
+    #[no_mangle]
+    pub unsafe extern "C" fn a_alloc(mut n: crate::stdlib::__uint32_t) -> *mut libc::c_void {
+        return crate::stdlib::malloc(n as libc::c_ulong);
+    }
+}
+
+pub mod b {
+
+    #[no_mangle]
+    pub unsafe extern "C" fn b_release(mut p: *mut libc::c_void, mut n: crate::stdlib::__uint32_t) {
+        if n != 0 as libc::c_int as libc::c_uint {
+            crate::stdlib::free(p);
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,64 @@
+#![feature(libc)]
+#![feature(rustc_private)]
+#![register_tool(c2rust)]
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(dead_code)]
+#![allow(mutable_transmutes)]
+#![allow(unused_mut)]
+
+extern crate libc;
+
+pub mod a {
+    #[c2rust::header_src = "/usr/include/x86_64-linux-gnu/bits/types.h:19"]
+    pub mod types_h {
+        #[c2rust::src_loc = "42:1"]
+        pub type __uint32_t = libc::c_uint;
+    }
+
+    #[c2rust::header_src = "/usr/include/stdlib.h:20"]
+    pub mod stdlib_h {
+        extern "C" {
+            #[c2rust::src_loc = "539:14"]
+            pub fn malloc(_: libc::c_ulong) -> *mut libc::c_void;
+            #[c2rust::src_loc = "565:13"]
+            pub fn free(__ptr: *mut libc::c_void);
+        }
+    }
+    use self::stdlib_h::malloc;
+    use self::types_h::__uint32_t;
+
+    #[no_mangle]
+    pub unsafe extern "C" fn a_alloc(mut n: __uint32_t) -> *mut libc::c_void {
+        return malloc(n as libc::c_ulong);
+    }
+}
+
+pub mod b {
+    #[c2rust::header_src = "/usr/include/x86_64-linux-gnu/bits/types.h:19"]
+    pub mod types_h {
+        #[c2rust::src_loc = "42:1"]
+        pub type __uint32_t = libc::c_uint;
+    }
+
+    #[c2rust::header_src = "/usr/include/stdlib.h:20"]
+    pub mod stdlib_h {
+        extern "C" {
+            #[c2rust::src_loc = "565:13"]
+            pub fn free(__ptr: *mut libc::c_void);
+        }
+    }
+    use self::stdlib_h::free;
+    use self::types_h::__uint32_t;
+
+    #[no_mangle]
+    pub unsafe extern "C" fn b_release(mut p: *mut libc::c_void, mut n: __uint32_t) {
+        if n != 0 as libc::c_int as libc::c_uint {
+            free(p);
+        }
+    }
+}
+
+fn main() {}
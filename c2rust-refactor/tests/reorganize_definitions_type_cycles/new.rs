@@ -0,0 +1,28 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod tree {
+
+    // =============== BEGIN tree_h ================
+    pub struct Tree {
+        pub root: *mut crate::node::Node,
+    }
+
+    pub fn root(t: &crate::tree::Tree) -> *mut crate::node::Node {
+        t.root
+    }
+}
+
+pub mod node {
+
+    // =============== BEGIN node_h ================
+    pub struct Node {
+        pub tree: *mut crate::tree::Tree,
+    }
+
+    pub fn is_orphan(n: &crate::node::Node) -> bool {
+        n.tree.is_null()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod tree {
+    #[c2rust::header_src = "/home/user/some/workspace/tree.h:1"]
+    pub mod tree_h {
+        #[c2rust::src_loc = "3:0"]
+        pub struct Tree {
+            pub root: *mut super::node_h::Node,
+        }
+    }
+
+    #[c2rust::header_src = "/home/user/some/workspace/node.h:2"]
+    pub mod node_h {
+        #[c2rust::src_loc = "3:0"]
+        pub struct Node {
+            pub tree: *mut super::tree_h::Tree,
+        }
+    }
+
+    pub fn root(t: &tree_h::Tree) -> *mut node_h::Node {
+        t.root
+    }
+}
+
+pub mod node {
+    pub fn is_orphan(n: &super::tree::node_h::Node) -> bool {
+        n.tree.is_null()
+    }
+}
+
+fn main() {}
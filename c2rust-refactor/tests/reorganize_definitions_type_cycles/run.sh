@@ -0,0 +1,11 @@
+#!/bin/sh
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+$refactor \
+    reorganize_definitions report_type_cycles=1 \
+    -- old.rs $rustflags 2> log
+grep -q "crate::node::Node, crate::tree::Tree refer to each other" log
@@ -0,0 +1,31 @@
+#![register_tool(c2rust)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+pub mod util {
+    pub fn helper() -> usize {
+        1
+    }
+}
+
+pub mod buffer {
+
+    // =============== BEGIN buffer_h ================
+    pub const SIZE: usize = 16;
+
+    use crate::util::helper;
+    use std::mem::size_of;
+
+    pub fn total() -> usize {
+        crate::buffer::SIZE + helper() + size_of::<u32>()
+    }
+}
+
+pub mod user {
+
+    pub fn first(buf: [u8; crate::buffer::SIZE]) -> u8 {
+        buf[0]
+    }
+}
+
+fn main() {}
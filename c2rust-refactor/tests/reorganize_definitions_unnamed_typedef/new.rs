@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod graphics {
+
+    // =============== BEGIN gfx_h ================
+    pub struct C2RustUnnamed {
+        pub x: i32,
+        pub y: i32,
+    }
+    pub type gfx_point = crate::graphics::C2RustUnnamed;
+    pub fn scale() -> i32 {
+        1
+    }
+}
+
+pub mod gfx {
+
+    pub fn origin() -> crate::graphics::gfx_point {
+        crate::graphics::C2RustUnnamed { x: 0, y: 0 }
+    }
+}
+
+fn main() {}
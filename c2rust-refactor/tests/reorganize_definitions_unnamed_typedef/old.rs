@@ -0,0 +1,29 @@
+#![register_tool(c2rust)]
+
+#![allow(non_camel_case_types)]
+#![allow(dead_code)]
+
+pub mod graphics {
+    pub fn scale() -> i32 {
+        1
+    }
+}
+
+pub mod gfx {
+    #[c2rust::header_src = "/home/user/some/workspace/gfx.h:1"]
+    pub mod gfx_h {
+        #[c2rust::src_loc = "3:9"]
+        pub struct C2RustUnnamed {
+            pub x: i32,
+            pub y: i32,
+        }
+        #[c2rust::src_loc = "6:1"]
+        pub type gfx_point = C2RustUnnamed;
+    }
+
+    pub fn origin() -> gfx_h::gfx_point {
+        gfx_h::C2RustUnnamed { x: 0, y: 0 }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod geo {
+
+    // =============== BEGIN geo_h ================
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    pub fn norm1(p: &crate::geo::Point) -> i32 {
+        p.x.abs() + p.y.abs()
+    }
+}
+
+pub mod app {
+    use crate::geo::Point as Pt;
+
+    pub fn origin() -> crate::geo::Point {
+        crate::geo::Point { x: 0, y: 0 }
+    }
+}
+
+fn main() {}
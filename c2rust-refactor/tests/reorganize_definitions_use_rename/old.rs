@@ -0,0 +1,36 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod geo {
+    #[c2rust::header_src = "/home/user/some/workspace/geo.h:1"]
+    pub mod geo_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+
+    pub fn norm1(p: &geo_h::Point) -> i32 {
+        p.x.abs() + p.y.abs()
+    }
+}
+
+pub mod app {
+    #[c2rust::header_src = "/home/user/some/workspace/geo.h:1"]
+    pub mod geo_h {
+        #[c2rust::src_loc = "2:8"]
+        pub struct Point {
+            pub x: i32,
+            pub y: i32,
+        }
+    }
+    use self::geo_h::Point as Pt;
+
+    pub fn origin() -> Pt {
+        Pt { x: 0, y: 0 }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod user {
+    use std::mem::size_of;
+    use std::cmp::max;
+    use std::mem::align_of;
+
+    pub fn padded() -> usize {
+        max(size_of::<u64>(), align_of::<u64>())
+    }
+}
+
+fn main() {}
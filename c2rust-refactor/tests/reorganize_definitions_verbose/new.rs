@@ -0,0 +1,20 @@
+#![register_tool(c2rust)]
+#![allow(non_upper_case_globals)]
+#![allow(dead_code)]
+
+pub mod buffer {
+
+    // =============== BEGIN buffer_h ================
+    pub const SIZE: usize = 16;
+
+    pub static mut BUF: [u8; crate::buffer::SIZE] = [0; crate::buffer::SIZE];
+}
+
+pub mod user {
+
+    pub fn first(buf: [u8; crate::buffer::SIZE]) -> u8 {
+        buf[0]
+    }
+}
+
+fn main() {}
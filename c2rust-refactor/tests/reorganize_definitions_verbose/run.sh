@@ -0,0 +1,16 @@
+#!/bin/sh
+set -e
+
+# work around System Integrity Protection on macOS
+if [ `uname` = 'Darwin' ]; then
+    export LD_LIBRARY_PATH=$not_LD_LIBRARY_PATH
+fi
+
+$refactor \
+    reorganize_definitions verbose=1 \
+    -- old.rs $rustflags 2> placement.log
+
+# Both copies of SIZE are traced to the module named after their header
+grep -q 'note: crate::buffer::buffer_h::SIZE -> crate::buffer: candidates for its header: crate::buffer' placement.log
+grep -q 'note: crate::user::buffer_h::SIZE -> crate::buffer: candidates for its header: crate::buffer' placement.log
+rm placement.log
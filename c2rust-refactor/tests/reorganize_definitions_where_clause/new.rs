@@ -0,0 +1,29 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod buffer {
+
+    // =============== BEGIN buffer_h ================
+    pub trait Marker {}
+
+    pub struct Wrapper<T>(pub T);
+
+    pub trait Describe {
+        fn describe(&self) -> usize;
+    }
+
+    impl<T> Describe for Wrapper<T>
+    where
+        T: crate::buffer::Marker,
+    {
+        fn describe(&self) -> usize {
+            0
+        }
+    }
+
+    pub fn wrap<T: crate::buffer::Marker>(t: T) -> Wrapper<T> {
+        Wrapper(t)
+    }
+}
+
+fn main() {}
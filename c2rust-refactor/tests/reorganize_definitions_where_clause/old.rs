@@ -0,0 +1,32 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod buffer {
+    #[c2rust::header_src = "/home/user/some/workspace/buffer.h:1"]
+    pub mod buffer_h {
+        #[c2rust::src_loc = "2:0"]
+        pub trait Marker {}
+    }
+
+    pub struct Wrapper<T>(pub T);
+
+    pub trait Describe {
+        fn describe(&self) -> usize;
+    }
+
+    impl<T> Describe for Wrapper<T>
+    where
+        T: buffer_h::Marker,
+    {
+        fn describe(&self) -> usize {
+            0
+        }
+    }
+
+    pub fn wrap<T: buffer_h::Marker>(t: T) -> Wrapper<T> {
+        Wrapper(t)
+    }
+}
+
+fn main() {}
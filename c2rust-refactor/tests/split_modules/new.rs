@@ -0,0 +1,28 @@
+#![register_tool(c2rust)]
+#![allow(dead_code)]
+
+pub mod all {
+    pub use self::point::*;
+    pub mod point {
+        use super::*;
+        pub struct Point {
+            pub x: i32,
+        }
+        pub(super) fn origin() -> Point {
+            Point { x: 0 }
+        }
+    }
+    pub use self::size::*;
+    pub mod size {
+        use super::*;
+        pub struct Size {
+            pub w: i32,
+        }
+    }
+
+    pub fn area(s: &Size) -> i32 {
+        s.w * origin().x
+    }
+}
+
+fn main() {}
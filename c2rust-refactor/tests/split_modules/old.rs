@@ -0,0 +1,24 @@
+#![register_tool(c2rust)]
+
+#![allow(dead_code)]
+
+pub mod all {
+    #[c2rust::header_src = "/home/user/some/workspace/point.h:1"]
+    pub struct Point {
+        pub x: i32,
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/size.h:2"]
+    pub struct Size {
+        pub w: i32,
+    }
+    #[c2rust::header_src = "/home/user/some/workspace/point.h:1"]
+    fn origin() -> Point {
+        Point { x: 0 }
+    }
+
+    pub fn area(s: &Size) -> i32 {
+        s.w * origin().x
+    }
+}
+
+fn main() {}